@@ -0,0 +1,57 @@
+use crate::get_database_connection;
+use crate::models::allocator_allowance_snapshots::{
+    ActiveModel, Column, Entity as AllocatorAllowanceSnapshots, Model,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, DbErr};
+
+/**
+ * Records the remaining DataCap of an allocator
+ *
+ * # Arguments
+ * @param allocator_id: i32 - The ID of the allocator
+ * @param allowance: i64 - The remaining DataCap in bytes
+ * @param pending_allocations: i64 - The DataCap in bytes requested by pending allocation requests
+ *
+ * # Returns
+ * @return Result<Model, sea_orm::DbErr> - The stored snapshot
+ */
+pub async fn create_allowance_snapshot(
+    allocator_id: i32,
+    allowance: i64,
+    pending_allocations: i64,
+) -> Result<Model, DbErr> {
+    let conn = get_database_connection().await?;
+    ActiveModel {
+        allocator_id: Set(allocator_id),
+        allowance: Set(allowance),
+        pending_allocations: Set(pending_allocations),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(&conn)
+    .await
+}
+
+/**
+ * Get the DataCap snapshots of an allocator taken after the given time, oldest first
+ *
+ * # Arguments
+ * @param allocator_id: i32 - The ID of the allocator
+ * @param since: DateTime<Utc> - The time of the oldest snapshot to return
+ *
+ * # Returns
+ * @return Result<Vec<Model>, sea_orm::DbErr> - The snapshots
+ */
+pub async fn get_allowance_snapshots(
+    allocator_id: i32,
+    since: DateTime<Utc>,
+) -> Result<Vec<Model>, DbErr> {
+    let conn = get_database_connection().await?;
+    AllocatorAllowanceSnapshots::find()
+        .filter(Column::AllocatorId.eq(allocator_id))
+        .filter(Column::CreatedAt.gte(since))
+        .order_by_asc(Column::CreatedAt)
+        .all(&conn)
+        .await
+}
@@ -2,7 +2,7 @@ use crate::get_database_connection;
 use crate::models::allocators::{
     ActiveModel, Column, Entity as Allocator, Model as AllocatorModel,
 };
-use sea_orm::{entity::*, query::*, DbErr};
+use sea_orm::{entity::*, prelude::DateTimeWithTimeZone, query::*, DbErr};
 
 /**
  * Get all allocators from the database
@@ -52,6 +52,10 @@ pub async fn get_allocator(
  * @param required_sps: Option<String> - Required number of SPs
  * @param required_replicas: Option<String> - Required number of replicas
  * @param registry_file_path: Option<String> - Path to JSON file specifying the allocator in registry repo
+ * @param client_contract_address: Option<String> - Address of the client contract
+ * @param identity_verifiers: Option<serde_json::Value> - Identity providers used to verify clients
+ * @param kyc_validity_days: Option<i32> - Number of days after which clients have to repeat KYC
+ * @param tranche_policy: Option<serde_json::Value> - Policy used to size the allocations of clients
  *
  * # Returns
  * @return Result<AllocatorModel, sea_orm::DbErr> - The result of the operation
@@ -72,6 +76,9 @@ pub async fn create_or_update_allocator(
     required_replicas: Option<String>,
     registry_file_path: Option<String>,
     client_contract_address: Option<String>,
+    identity_verifiers: Option<serde_json::Value>,
+    kyc_validity_days: Option<i32>,
+    tranche_policy: Option<serde_json::Value>,
 ) -> Result<AllocatorModel, sea_orm::DbErr> {
     let existing_allocator = get_allocator(&owner, &repo).await?;
     if let Some(allocator_model) = existing_allocator {
@@ -135,6 +142,11 @@ pub async fn create_or_update_allocator(
             allocator_active_model.client_contract_address = Set(None);
         }
 
+        allocator_active_model.identity_verifiers = Set(identity_verifiers);
+        allocator_active_model.kyc_validity_days = Set(kyc_validity_days);
+        allocator_active_model.tranche_policy = Set(tranche_policy);
+        allocator_active_model.removed_from_registry_at = Set(None);
+
         let updated_model = allocator_active_model.update(&conn).await?;
 
         Ok(updated_model)
@@ -198,6 +210,11 @@ pub async fn create_or_update_allocator(
                 new_allocator.client_contract_address = Set(None);
             }
         }
+
+        new_allocator.identity_verifiers = Set(identity_verifiers);
+        new_allocator.kyc_validity_days = Set(kyc_validity_days);
+        new_allocator.tranche_policy = Set(tranche_policy);
+
         let conn = get_database_connection()
             .await
             .expect("Failed to get DB connection");
@@ -263,6 +280,102 @@ pub async fn update_allocator_threshold(
     existing_allocator.update(&conn).await
 }
 
+/**
+ * Flag an allocator whose file no longer exists in the governance registry
+ *
+ * # Arguments
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ *
+ * # Returns
+ * @return Result<AllocatorModel, sea_orm::DbErr> - The flagged allocator
+ */
+pub async fn flag_allocator_removed_from_registry(
+    owner: &str,
+    repo: &str,
+) -> Result<AllocatorModel, sea_orm::DbErr> {
+    let conn = get_database_connection().await?;
+    let mut existing_allocator = get_allocator(owner, repo)
+        .await?
+        .ok_or_else(|| DbErr::Custom("Allocator not found".into()))?
+        .into_active_model();
+
+    existing_allocator.removed_from_registry_at = Set(Some(chrono::Utc::now().into()));
+
+    existing_allocator.update(&conn).await
+}
+
+/**
+ * Update the multisig state of an allocator read from the chain
+ *
+ * # Arguments
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ * @param multisig_threshold: i32 - The number of approvals required by the multisig
+ * @param multisig_signers: Vec<String> - The ID addresses of the multisig signers
+ * @param pending_multisig_transactions: serde_json::Value - The pending multisig transactions
+ * @param signers_diverged: bool - Whether the signers differ from the governance record
+ *
+ * # Returns
+ * @return Result<AllocatorModel, sea_orm::DbErr> - The updated allocator
+ */
+pub async fn update_allocator_multisig_state(
+    owner: &str,
+    repo: &str,
+    multisig_threshold: i32,
+    multisig_signers: Vec<String>,
+    pending_multisig_transactions: serde_json::Value,
+    signers_diverged: bool,
+) -> Result<AllocatorModel, sea_orm::DbErr> {
+    let conn = get_database_connection().await?;
+    let existing_allocator = get_allocator(owner, repo)
+        .await?
+        .ok_or_else(|| DbErr::Custom("Allocator not found".into()))?;
+    let diverged_at = if signers_diverged {
+        existing_allocator
+            .multisig_signers_diverged_at
+            .or(Some(chrono::Utc::now().into()))
+    } else {
+        None
+    };
+    let mut existing_allocator = existing_allocator.into_active_model();
+
+    existing_allocator.multisig_threshold = Set(Some(multisig_threshold));
+    existing_allocator.multisig_signers = Set(Some(multisig_signers));
+    existing_allocator.pending_multisig_transactions = Set(Some(pending_multisig_transactions));
+    existing_allocator.multisig_synced_at = Set(Some(chrono::Utc::now().into()));
+    existing_allocator.multisig_signers_diverged_at = Set(diverged_at);
+
+    existing_allocator.update(&conn).await
+}
+
+/**
+ * Set or clear the time since which an allocator cannot cover its pending allocation requests
+ *
+ * # Arguments
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ * @param allowance_shortfall_since: Option<DateTimeWithTimeZone> - The start of the shortfall or None if the allowance is sufficient
+ *
+ * # Returns
+ * @return Result<AllocatorModel, sea_orm::DbErr> - The updated allocator
+ */
+pub async fn update_allocator_allowance_shortfall(
+    owner: &str,
+    repo: &str,
+    allowance_shortfall_since: Option<DateTimeWithTimeZone>,
+) -> Result<AllocatorModel, sea_orm::DbErr> {
+    let conn = get_database_connection().await?;
+    let mut existing_allocator = get_allocator(owner, repo)
+        .await?
+        .ok_or_else(|| DbErr::Custom("Allocator not found".into()))?
+        .into_active_model();
+
+    existing_allocator.allowance_shortfall_since = Set(allowance_shortfall_since);
+
+    existing_allocator.update(&conn).await
+}
+
 /**
  * Delete an allocator from the database
  *
@@ -0,0 +1,115 @@
+use crate::get_database_connection;
+use crate::models::comparable_application_embeddings::{
+    ActiveModel, Column, Entity as ComparableApplicationEmbeddings,
+};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{entity::*, query::*, DbBackend, DbErr, Statement};
+
+/// Formats the embedding as a pgvector literal, e.g. `[0.1,0.2]`.
+fn to_vector(embedding: &[f32]) -> String {
+    let components: Vec<String> = embedding.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", components.join(","))
+}
+
+/**
+ * Creates or replaces the embedding of an application field
+ *
+ * # Arguments
+ * @param client_address: &str - The client address of the application
+ * @param field: &str - The embedded field of the application
+ * @param model: &str - The name of the embedding model
+ * @param embedding: Vec<f32> - The normalized embedding vector
+ *
+ * # Returns
+ * @return Result<(), sea_orm::DbErr> - The result of the operation
+ */
+pub async fn upsert_embedding(
+    client_address: &str,
+    field: &str,
+    model: &str,
+    embedding: Vec<f32>,
+) -> Result<(), DbErr> {
+    let conn = get_database_connection().await?;
+    let embedding = ActiveModel {
+        client_address: Set(client_address.to_string()),
+        field: Set(field.to_string()),
+        model: Set(model.to_string()),
+        embedding: Set(to_vector(&embedding)),
+    };
+    ComparableApplicationEmbeddings::insert(embedding)
+        .on_conflict(
+            OnConflict::columns([Column::ClientAddress, Column::Field, Column::Model])
+                .update_column(Column::Embedding)
+                .to_owned(),
+        )
+        .exec(&conn)
+        .await?;
+    Ok(())
+}
+
+/**
+ * Get the number of embedded applications of a field
+ *
+ * # Arguments
+ * @param field: &str - The embedded field of the application
+ * @param model: &str - The name of the embedding model
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of embeddings
+ */
+pub async fn get_embedding_count(field: &str, model: &str) -> Result<u64, DbErr> {
+    let conn = get_database_connection().await?;
+    ComparableApplicationEmbeddings::find()
+        .filter(Column::Field.eq(field))
+        .filter(Column::Model.eq(model))
+        .count(&conn)
+        .await
+}
+
+/**
+ * Get the embeddings of a field nearest to the query by cosine distance
+ *
+ * # Arguments
+ * @param field: &str - The embedded field of the application
+ * @param model: &str - The name of the embedding model
+ * @param embedding: &[f32] - The normalized query embedding
+ * @param excluded_client_address: &str - The client address of the querying application
+ * @param limit: u64 - The maximum number of embeddings to return
+ *
+ * # Returns
+ * @return Result<Vec<(String, f64)>, sea_orm::DbErr> - Client addresses with their cosine similarity, most similar first
+ */
+pub async fn get_nearest_embeddings(
+    field: &str,
+    model: &str,
+    embedding: &[f32],
+    excluded_client_address: &str,
+    limit: u64,
+) -> Result<Vec<(String, f64)>, DbErr> {
+    let conn = get_database_connection().await?;
+    let rows = conn
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT client_address, 1 - (embedding <=> $3::vector) AS similarity
+                FROM comparable_application_embeddings
+                WHERE field = $1 AND model = $2 AND client_address <> $4
+                ORDER BY embedding <=> $3::vector
+                LIMIT $5",
+            [
+                field.into(),
+                model.into(),
+                to_vector(embedding).into(),
+                excluded_client_address.into(),
+                (limit as i64).into(),
+            ],
+        ))
+        .await?;
+    rows.into_iter()
+        .map(|row| {
+            Ok((
+                row.try_get::<String>("", "client_address")?,
+                row.try_get::<f64>("", "similarity")?,
+            ))
+        })
+        .collect()
+}
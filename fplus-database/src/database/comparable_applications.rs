@@ -1,9 +1,10 @@
 use crate::get_database_connection;
 use crate::models::comparable_applications::{
-    ActiveModel, ApplicationComparableData, Entity as ComparableApplication,
+    ActiveModel, ApplicationComparableData, Column, Entity as ComparableApplication,
     Model as ComparableApplicationModel,
 };
 use sea_orm::prelude::Expr;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::{entity::*, Condition, DbErr, QueryFilter};
 
 pub async fn create_comparable_application(
@@ -19,6 +20,43 @@ pub async fn create_comparable_application(
     Ok(())
 }
 
+pub async fn create_or_update_comparable_application(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<(), sea_orm::DbErr> {
+    let conn = get_database_connection().await?;
+    let comparable_data = ActiveModel {
+        client_address: Set(client_address.to_string()),
+        application: Set(comparable_data.clone()),
+    };
+    ComparableApplication::insert(comparable_data)
+        .on_conflict(
+            OnConflict::column(Column::ClientAddress)
+                .update_column(Column::Application)
+                .to_owned(),
+        )
+        .exec(&conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_all_comparable_applications() -> Result<Vec<ComparableApplicationModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    let response = ComparableApplication::find().all(&conn).await?;
+    Ok(response)
+}
+
+pub async fn get_comparable_applications_by_addresses(
+    clients_addresses: Vec<String>,
+) -> Result<Vec<ComparableApplicationModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    let response = ComparableApplication::find()
+        .filter(Column::ClientAddress.is_in(clients_addresses))
+        .all(&conn)
+        .await?;
+    Ok(response)
+}
+
 pub async fn get_comparable_applications() -> Result<Vec<ComparableApplicationModel>, DbErr> {
     let conn = get_database_connection().await?;
     let condition = Condition::any()
@@ -0,0 +1,118 @@
+use crate::get_database_connection;
+use alloy::primitives::Address;
+use chrono::{DateTime, FixedOffset};
+use sea_orm::{query::*, DbBackend, DbErr};
+
+/**
+ * Check whether a signed message was consumed before
+ *
+ * # Arguments
+ * @param message_hash: &str - The EIP-712 signing hash of the message
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - true if the message was already consumed
+ */
+pub async fn is_signature_consumed(message_hash: &str) -> Result<bool, DbErr> {
+    let conn = get_database_connection().await?;
+    let row = conn
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT 1 FROM consumed_signatures WHERE message_hash = $1;",
+            [message_hash.into()],
+        ))
+        .await?;
+    Ok(row.is_some())
+}
+
+/**
+ * Marks a signed message as consumed
+ *
+ * # Arguments
+ * @param message_hash: &str - The EIP-712 signing hash of the message
+ * @param signer_address: &Address - The address recovered from the signature
+ * @param expires_at: &DateTime<FixedOffset> - The expiration date of the message
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - true if the message was consumed now, false if it was consumed before
+ */
+pub async fn consume_signature(
+    message_hash: &str,
+    signer_address: &Address,
+    expires_at: &DateTime<FixedOffset>,
+) -> Result<bool, DbErr> {
+    let conn = get_database_connection().await?;
+    consume_signature_with(&conn, message_hash, signer_address, expires_at).await
+}
+
+/**
+ * Marks a signed message as consumed using the given connection, so the caller can record
+ * the outcome of the message in the same transaction
+ *
+ * # Arguments
+ * @param conn: &C - The connection or transaction to use
+ * @param message_hash: &str - The EIP-712 signing hash of the message
+ * @param signer_address: &Address - The address recovered from the signature
+ * @param expires_at: &DateTime<FixedOffset> - The expiration date of the message
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - true if the message was consumed now, false if it was consumed before
+ */
+pub async fn consume_signature_with<C: ConnectionTrait>(
+    conn: &C,
+    message_hash: &str,
+    signer_address: &Address,
+    expires_at: &DateTime<FixedOffset>,
+) -> Result<bool, DbErr> {
+    let exec_res = conn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "INSERT INTO consumed_signatures (message_hash, signer_address, expires_at, consumed_at)
+                VALUES ($1, $2, $3, NOW())
+                ON CONFLICT (message_hash) DO NOTHING;",
+            [
+                message_hash.into(),
+                signer_address.to_checksum(None).into(),
+                (*expires_at).into(),
+            ],
+        ))
+        .await?;
+    Ok(exec_res.rows_affected() == 1)
+}
+
+/**
+ * Releases a consumed message whose operation failed afterwards, so it can be submitted again
+ *
+ * # Arguments
+ * @param message_hash: &str - The EIP-712 signing hash of the message
+ *
+ * # Returns
+ * @return Result<(), sea_orm::DbErr> - The result of the operation
+ */
+pub async fn release_signature(message_hash: &str) -> Result<(), DbErr> {
+    let conn = get_database_connection().await?;
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM consumed_signatures WHERE message_hash = $1;",
+        [message_hash.into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/**
+ * Delete consumed messages whose expiration date has passed.
+ * An expired message is rejected before the replay check, so its hash is no longer needed.
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of deleted rows
+ */
+pub async fn delete_expired_consumed_signatures() -> Result<u64, DbErr> {
+    let conn = get_database_connection().await?;
+    let exec_res = conn
+        .execute(Statement::from_string(
+            DbBackend::Postgres,
+            "DELETE FROM consumed_signatures WHERE expires_at < NOW();",
+        ))
+        .await?;
+    Ok(exec_res.rows_affected())
+}
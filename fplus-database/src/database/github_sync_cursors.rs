@@ -0,0 +1,59 @@
+use crate::get_database_connection;
+use crate::models::github_sync_cursors::{Column, Entity as GithubSyncCursors, Model};
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, DbBackend, DbErr};
+
+/**
+ * Get the point up to which the applications of a repository were reconciled with GitHub
+ *
+ * # Arguments
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ *
+ * # Returns
+ * @return Result<Option<Model>, sea_orm::DbErr> - The cursor if the repository was reconciled before
+ */
+pub async fn get_github_sync_cursor(owner: &str, repo: &str) -> Result<Option<Model>, DbErr> {
+    let conn = get_database_connection().await?;
+    GithubSyncCursors::find()
+        .filter(Column::Owner.eq(owner))
+        .filter(Column::Repo.eq(repo))
+        .one(&conn)
+        .await
+}
+
+/**
+ * Records the point up to which the applications of a repository were reconciled with GitHub
+ *
+ * # Arguments
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ * @param main_sha: &str - The last reconciled commit of the main branch
+ * @param pull_requests_updated_at: Option<DateTime<Utc>> - The last update of the reconciled pull requests
+ *
+ * # Returns
+ * @return Result<(), sea_orm::DbErr> - The result of the operation
+ */
+pub async fn upsert_github_sync_cursor(
+    owner: &str,
+    repo: &str,
+    main_sha: &str,
+    pull_requests_updated_at: Option<DateTime<Utc>>,
+) -> Result<(), DbErr> {
+    let conn = get_database_connection().await?;
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "INSERT INTO github_sync_cursors (owner, repo, main_sha, pull_requests_updated_at, synced_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (owner, repo)
+            DO UPDATE SET main_sha = $3, pull_requests_updated_at = $4, synced_at = NOW();",
+        [
+            owner.into(),
+            repo.into(),
+            main_sha.into(),
+            pull_requests_updated_at.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
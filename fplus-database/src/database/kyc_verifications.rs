@@ -0,0 +1,85 @@
+use crate::database::consumed_signatures::consume_signature_with;
+use crate::get_database_connection;
+use crate::models::kyc_verifications::{Column, Entity as KycVerifications, Model};
+use alloy::primitives::Address;
+use chrono::{DateTime, FixedOffset};
+use sea_orm::{entity::*, query::*, DbBackend, DbErr};
+
+/**
+ * Get the last KYC verification of a client
+ *
+ * # Arguments
+ * @param client_id: &str - The client address
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ *
+ * # Returns
+ * @return Result<Option<Model>, sea_orm::DbErr> - The last KYC verification if the client completed KYC
+ */
+pub async fn get_kyc_verification(
+    client_id: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<Option<Model>, DbErr> {
+    let conn = get_database_connection().await?;
+    KycVerifications::find()
+        .filter(Column::ClientId.eq(client_id))
+        .filter(Column::Owner.eq(owner))
+        .filter(Column::Repo.eq(repo))
+        .one(&conn)
+        .await
+}
+
+/**
+ * Records a successful KYC verification of a client, replacing the previous one, and consumes
+ * the signed message it was submitted with in the same transaction
+ *
+ * # Arguments
+ * @param client_id: &str - The client address
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ * @param evm_address: &Address - The address verified by the identity provider
+ * @param provider: &str - The identity provider which verified the address
+ * @param score: f64 - The score returned by the identity provider
+ * @param message_hash: &str - The EIP-712 signing hash of the submitted message
+ * @param expires_at: &DateTime<FixedOffset> - The expiration date of the submitted message
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - false if the message was consumed before, in which case nothing is recorded
+ */
+#[allow(clippy::too_many_arguments)]
+pub async fn upsert_kyc_verification(
+    client_id: &str,
+    owner: &str,
+    repo: &str,
+    evm_address: &Address,
+    provider: &str,
+    score: f64,
+    message_hash: &str,
+    expires_at: &DateTime<FixedOffset>,
+) -> Result<bool, DbErr> {
+    let conn = get_database_connection().await?;
+    let txn = conn.begin().await?;
+    if !consume_signature_with(&txn, message_hash, evm_address, expires_at).await? {
+        txn.rollback().await?;
+        return Ok(false);
+    }
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "INSERT INTO kyc_verifications (client_id, owner, repo, evm_address, provider, score, verified_at)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            ON CONFLICT (client_id, owner, repo)
+            DO UPDATE SET evm_address = $4, provider = $5, score = $6, verified_at = NOW();",
+        [
+            client_id.into(),
+            owner.into(),
+            repo.into(),
+            evm_address.to_checksum(None).into(),
+            provider.into(),
+            score.into(),
+        ],
+    ))
+    .await?;
+    txn.commit().await?;
+    Ok(true)
+}
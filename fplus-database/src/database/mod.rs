@@ -1,5 +1,12 @@
 pub mod allocation_amounts;
+pub mod allocator_allowance_snapshots;
 pub mod allocators;
 pub mod applications;
 pub mod autoallocations;
+pub mod comparable_application_embeddings;
 pub mod comparable_applications;
+pub mod consumed_signatures;
+pub mod github_sync_cursors;
+pub mod kyc_verifications;
+pub mod similarity_index;
+pub mod similarity_reports;
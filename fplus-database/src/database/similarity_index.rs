@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use crate::get_database_connection;
+use crate::models::similarity_index_documents::{
+    ActiveModel as DocumentActiveModel, Column as DocumentColumn, Entity as IndexDocuments,
+    Model as IndexDocumentModel,
+};
+use crate::models::similarity_index_postings::{
+    ActiveModel as PostingActiveModel, Column as PostingColumn, Entity as IndexPostings,
+    Model as IndexPostingModel,
+};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{entity::*, query::*, DbBackend, DbErr, TransactionTrait};
+
+const POSTINGS_INSERT_CHUNK: usize = 1000;
+
+/**
+ * Replaces the indexed terms of a document
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param client_address: &str - The client address of the application
+ * @param term_counts: &HashMap<String, i32> - Number of occurrences of each term in the field
+ * @param token_count: i32 - Number of tokens in the field
+ *
+ * # Returns
+ * @return Result<(), sea_orm::DbErr> - The result of the operation
+ */
+pub async fn index_document(
+    field: &str,
+    client_address: &str,
+    term_counts: &HashMap<String, i32>,
+    token_count: i32,
+) -> Result<(), DbErr> {
+    let conn = get_database_connection().await?;
+    let txn = conn.begin().await?;
+
+    IndexPostings::delete_many()
+        .filter(PostingColumn::Field.eq(field))
+        .filter(PostingColumn::ClientAddress.eq(client_address))
+        .exec(&txn)
+        .await?;
+
+    let postings: Vec<PostingActiveModel> = term_counts
+        .iter()
+        .map(|(term, count)| PostingActiveModel {
+            field: Set(field.to_string()),
+            term: Set(term.clone()),
+            client_address: Set(client_address.to_string()),
+            term_count: Set(*count),
+        })
+        .collect();
+    for chunk in postings.chunks(POSTINGS_INSERT_CHUNK) {
+        IndexPostings::insert_many(chunk.to_vec())
+            .exec(&txn)
+            .await?;
+    }
+
+    let document = DocumentActiveModel {
+        field: Set(field.to_string()),
+        client_address: Set(client_address.to_string()),
+        token_count: Set(token_count),
+    };
+    IndexDocuments::insert(document)
+        .on_conflict(
+            OnConflict::columns([DocumentColumn::Field, DocumentColumn::ClientAddress])
+                .update_column(DocumentColumn::TokenCount)
+                .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await
+}
+
+/**
+ * Get the number of indexed documents of a field
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of documents
+ */
+pub async fn get_document_count(field: &str) -> Result<u64, DbErr> {
+    let conn = get_database_connection().await?;
+    IndexDocuments::find()
+        .filter(DocumentColumn::Field.eq(field))
+        .count(&conn)
+        .await
+}
+
+/**
+ * Get the number of indexed documents of a field other than the one of a client
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param excluded_client_address: &str - The client address of the document not to count
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of documents
+ */
+pub async fn get_other_document_count(
+    field: &str,
+    excluded_client_address: &str,
+) -> Result<u64, DbErr> {
+    let conn = get_database_connection().await?;
+    IndexDocuments::find()
+        .filter(DocumentColumn::Field.eq(field))
+        .filter(DocumentColumn::ClientAddress.ne(excluded_client_address))
+        .count(&conn)
+        .await
+}
+
+/**
+ * Get the number of documents containing each of the terms
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param terms: Vec<String> - The terms to look up
+ * @param excluded_client_address: &str - The client address of the document not to count
+ *
+ * # Returns
+ * @return Result<HashMap<String, i64>, sea_orm::DbErr> - Document frequency of each term found in the index
+ */
+pub async fn get_document_frequencies(
+    field: &str,
+    terms: Vec<String>,
+    excluded_client_address: &str,
+) -> Result<HashMap<String, i64>, DbErr> {
+    let conn = get_database_connection().await?;
+    let rows = conn
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT term, COUNT(*) AS document_frequency FROM similarity_index_postings
+                WHERE field = $1 AND term = ANY($2) AND client_address <> $3
+                GROUP BY term",
+            [field.into(), terms.into(), excluded_client_address.into()],
+        ))
+        .await?;
+    rows.into_iter()
+        .map(|row| {
+            Ok((
+                row.try_get::<String>("", "term")?,
+                row.try_get::<i64>("", "document_frequency")?,
+            ))
+        })
+        .collect()
+}
+
+/**
+ * Get every posting of documents
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param clients_addresses: Vec<String> - The client addresses of the applications
+ *
+ * # Returns
+ * @return Result<Vec<IndexPostingModel>, sea_orm::DbErr> - The terms of the documents
+ */
+pub async fn get_document_postings(
+    field: &str,
+    clients_addresses: Vec<String>,
+) -> Result<Vec<IndexPostingModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    IndexPostings::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT * FROM similarity_index_postings WHERE field = $1 AND client_address = ANY($2)",
+            [field.into(), clients_addresses.into()],
+        ))
+        .all(&conn)
+        .await
+}
+
+/**
+ * Get the postings of the terms
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param terms: Vec<String> - The terms to look up
+ *
+ * # Returns
+ * @return Result<Vec<IndexPostingModel>, sea_orm::DbErr> - Documents containing any of the terms
+ */
+pub async fn get_postings(
+    field: &str,
+    terms: Vec<String>,
+) -> Result<Vec<IndexPostingModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    IndexPostings::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT * FROM similarity_index_postings WHERE field = $1 AND term = ANY($2)",
+            [field.into(), terms.into()],
+        ))
+        .all(&conn)
+        .await
+}
+
+/**
+ * Get indexed documents by client addresses
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param clients_addresses: Vec<String> - The client addresses of the applications
+ *
+ * # Returns
+ * @return Result<Vec<IndexDocumentModel>, sea_orm::DbErr> - The indexed documents
+ */
+pub async fn get_documents(
+    field: &str,
+    clients_addresses: Vec<String>,
+) -> Result<Vec<IndexDocumentModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    IndexDocuments::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT * FROM similarity_index_documents WHERE field = $1 AND client_address = ANY($2)",
+            [field.into(), clients_addresses.into()],
+        ))
+        .all(&conn)
+        .await
+}
+
+/**
+ * Get indexed documents with at most the given number of tokens
+ *
+ * # Arguments
+ * @param field: &str - The indexed field of the application
+ * @param max_token_count: i32 - The maximum number of tokens of the documents
+ *
+ * # Returns
+ * @return Result<Vec<IndexDocumentModel>, sea_orm::DbErr> - The indexed documents
+ */
+pub async fn get_documents_with_max_token_count(
+    field: &str,
+    max_token_count: i32,
+) -> Result<Vec<IndexDocumentModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    IndexDocuments::find()
+        .filter(DocumentColumn::Field.eq(field))
+        .filter(DocumentColumn::TokenCount.lte(max_token_count))
+        .all(&conn)
+        .await
+}
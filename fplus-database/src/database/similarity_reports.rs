@@ -0,0 +1,62 @@
+use crate::get_database_connection;
+use crate::models::similarity_reports::{
+    ActiveModel, Column, Entity as SimilarityReport, Model as SimilarityReportModel,
+};
+use chrono::Utc;
+use sea_orm::{entity::*, query::*, DbErr};
+
+/**
+ * Stores a similarity report of an application
+ *
+ * # Arguments
+ * @param client_address: &str - The client address of the application
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ * @param report: serde_json::Value - The similarity report
+ *
+ * # Returns
+ * @return Result<SimilarityReportModel, sea_orm::DbErr> - The stored report
+ */
+pub async fn create_similarity_report(
+    client_address: &str,
+    owner: &str,
+    repo: &str,
+    report: serde_json::Value,
+) -> Result<SimilarityReportModel, DbErr> {
+    let conn = get_database_connection().await?;
+    let new_report = ActiveModel {
+        client_address: Set(client_address.to_string()),
+        owner: Set(owner.to_string()),
+        repo: Set(repo.to_string()),
+        report: Set(report),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    };
+    new_report.insert(&conn).await
+}
+
+/**
+ * Get all similarity reports of an application, newest first
+ *
+ * # Arguments
+ * @param client_address: &str - The client address of the application
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ *
+ * # Returns
+ * @return Result<Vec<SimilarityReportModel>, sea_orm::DbErr> - The stored reports
+ */
+pub async fn get_similarity_reports(
+    client_address: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<SimilarityReportModel>, DbErr> {
+    let conn = get_database_connection().await?;
+    SimilarityReport::find()
+        .filter(Column::ClientAddress.eq(client_address))
+        .filter(Column::Owner.eq(owner))
+        .filter(Column::Repo.eq(repo))
+        .order_by_desc(Column::CreatedAt)
+        .all(&conn)
+        .await
+}
@@ -145,6 +145,9 @@ mod tests {
             required_replicas,
             registry_file_path,
             client_contract_address,
+            None,
+            None,
+            None,
         )
         .await;
         assert!(result.is_ok());
@@ -238,6 +241,9 @@ mod tests {
             required_replicas,
             registry_file_path,
             client_contract_address,
+            None,
+            None,
+            None,
         )
         .await;
 
@@ -23,6 +23,18 @@ pub struct Model {
     pub required_replicas: Option<String>,
     pub registry_file_path: Option<String>,
     pub client_contract_address: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub identity_verifiers: Option<Json>,
+    pub kyc_validity_days: Option<i32>,
+    pub removed_from_registry_at: Option<DateTimeWithTimeZone>,
+    pub multisig_signers: Option<Vec<String>>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub pending_multisig_transactions: Option<Json>,
+    pub multisig_synced_at: Option<DateTimeWithTimeZone>,
+    pub multisig_signers_diverged_at: Option<DateTimeWithTimeZone>,
+    pub allowance_shortfall_since: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub tranche_policy: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
@@ -15,10 +15,18 @@ pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
 pub struct ApplicationComparableData {
     pub project_desc: String,
     pub stored_data_desc: String,
     pub data_owner_name: String,
     pub data_set_sample: String,
+    #[serde(default)]
+    pub storage_providers: Vec<String>,
+    #[serde(default)]
+    pub website: String,
+    #[serde(default)]
+    pub social_media: String,
+    #[serde(default)]
+    pub addresses: Vec<String>,
 }
@@ -0,0 +1,17 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "similarity_index_documents")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub field: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub client_address: String,
+    pub token_count: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
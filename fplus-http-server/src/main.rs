@@ -1,9 +1,17 @@
+use fplus_database::database::consumed_signatures::delete_expired_consumed_signatures;
+use fplus_lib::core::allocator::allowance::snapshot_allocator_allowances;
+use fplus_lib::core::allocator::multisig::sync_allocator_multisigs;
+use fplus_lib::core::allocator::registry::sync_allocator_registry;
 use fplus_lib::core::allocator::update_installation_ids_logic;
+use fplus_lib::external_services::github_rate_limit::in_background;
+use fplus_lib::external_services::similarity_detection::build_similarity_index_if_empty;
 use log::info;
 mod middleware;
 use middleware::verifier_auth::VerifierAuth;
 pub(crate) mod router;
 use std::env;
+use utoipa::OpenApi;
+use utoipa_scalar::{Scalar, Servable};
 
 use actix_web::{
     middleware::{Compress, Logger},
@@ -55,11 +63,61 @@ async fn main() -> std::io::Result<()> {
         panic!("Failed to setup database connection: {}", e);
     }
 
+    tokio::spawn(async {
+        if let Err(e) = build_similarity_index_if_empty().await {
+            log::error!("Failed to build similarity index: {}", e);
+        }
+    });
+
     tokio::spawn(async {
         run_cron("0 0 0,4,8,12,16,20 * * * *", || {
             tokio::spawn(async {
                 if let Err(e) = update_installation_ids_logic().await {
-                    eprintln!("Error: {:?}", e);
+                    log::error!("Failed to update installation ids: {:?}", e);
+                }
+            })
+        })
+        .await;
+    });
+
+    tokio::spawn(async {
+        run_cron("0 0 3 * * * *", || {
+            tokio::spawn(async {
+                if let Err(e) = delete_expired_consumed_signatures().await {
+                    log::error!("Failed to delete expired consumed signatures: {}", e);
+                }
+            })
+        })
+        .await;
+    });
+
+    tokio::spawn(async {
+        run_cron("0 0 2 * * * *", || {
+            tokio::spawn(async {
+                if let Err(e) = in_background(sync_allocator_registry(false)).await {
+                    log::error!("Failed to sync allocator registry: {}", e);
+                }
+            })
+        })
+        .await;
+    });
+
+    tokio::spawn(async {
+        run_cron("0 30 2 * * * *", || {
+            tokio::spawn(async {
+                if let Err(e) = sync_allocator_multisigs().await {
+                    log::error!("Failed to sync allocator multisigs: {}", e);
+                }
+            })
+        })
+        .await;
+    });
+
+    tokio::spawn(async {
+        run_cron("0 15 0,6,12,18 * * * *", || {
+            tokio::spawn(async {
+                if let Err(e) = snapshot_allocator_allowances().await {
+                    log::error!("Failed to snapshot allocator allowances: {}", e);
                 }
             })
         })
@@ -76,6 +134,13 @@ async fn main() -> std::io::Result<()> {
             .wrap(Logger::default())
             .wrap(cors)
             .service(router::health)
+            .service(router::github_cache)
+            .service(router::github_rate_limits)
+            .service(router::openapi::openapi_json)
+            .service(Scalar::with_url(
+                "/docs",
+                router::openapi::ApiDoc::openapi(),
+            ))
             .service(router::application::create)
             .service(
                 web::scope("/verifier")
@@ -91,7 +156,8 @@ async fn main() -> std::io::Result<()> {
                     .service(router::application::remove_pending_allocation)
                     .service(router::application::propose_storage_providers)
                     .service(router::application::approve_storage_providers)
-                    .service(router::application::allocation_failed),
+                    .service(router::application::allocation_failed)
+                    .service(router::similarity::rerun),
             )
             .service(router::application::merged)
             .service(router::application::active)
@@ -100,6 +166,11 @@ async fn main() -> std::io::Result<()> {
             .service(router::application::total_dc_reached)
             .service(router::application::single)
             .service(router::application::application_with_allocation_amount_handler)
+            .service(router::application::compliance)
+            .service(router::application::next_tranche)
+            .service(router::application::application_schema)
+            .service(router::application::upgrade_schema)
+            .service(router::application::validate_issue)
             .service(router::application::validate_application_flow)
             .service(router::application::check_for_changes)
             .service(router::application::validate_application_trigger)
@@ -108,6 +179,7 @@ async fn main() -> std::io::Result<()> {
             .service(router::application::validate_application_merge)
             .service(router::application::delete_branch)
             .service(router::application::cache_renewal)
+            .service(router::application::cache_reconcile)
             .service(router::application::update_from_issue)
             .service(router::application::trigger_ssa)
             .service(router::application::submit_kyc)
@@ -118,9 +190,18 @@ async fn main() -> std::io::Result<()> {
             .service(router::allocator::allocator)
             .service(router::allocator::delete)
             .service(router::allocator::create_allocator_from_json)
+            .service(router::allocator::sync_allocators)
+            .service(router::allocator::sync_multisigs)
+            .service(router::allocator::snapshot_allowances)
+            .service(router::allocator::allowance_forecast)
+            .service(router::allocator::validate_allocator)
+            .service(router::allocator::allocator_schema)
             .service(router::allocator::update_allocator_force)
+            .service(router::allocator::template_drift)
             .service(router::autoallocator::last_client_allocation)
             .service(router::autoallocator::trigger_autoallocation)
+            .service(router::similarity::check)
+            .service(router::similarity::reports)
         // .service(router::allocator::get_installation_ids)
     })
     .bind(("0.0.0.0", 8080))?
@@ -6,18 +6,33 @@ use actix_web::{
 use fplus_database::database::allocators as allocators_db;
 use fplus_lib::core::{
     allocator::{
+        allowance::{get_allowance_forecast, snapshot_allocator_allowances},
         create_allocator_from_file, fetch_installation_ids, force_update_allocators,
         generate_github_app_jwt,
+        multisig::sync_allocator_multisigs,
+        registry::sync_allocator_registry,
+        template::{get_template_drift, stage_template_update},
+        validation::{validate_allocator_file, ALLOCATOR_FILE_SCHEMA},
     },
-    AllocatorUpdateForceInfo, ChangedAllocators,
+    AllocatorSyncQueryParams, AllocatorTemplateDriftInfo, AllocatorUpdateForceInfo,
+    AllocatorValidateQueryParams, ChangedAllocators,
 };
 use reqwest::Client;
+
+use super::ldn_error;
+
 /**
  * Get all allocators
  *
  * # Returns
  * @return HttpResponse - The result of the operation
  */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "All allocators")
+    )
+)]
 #[get("/allocators")]
 pub async fn allocators() -> actix_web::Result<impl Responder> {
     let allocators = allocators_db::get_allocators()
@@ -34,6 +49,12 @@ pub async fn allocators() -> actix_web::Result<impl Responder> {
  * # Returns
  * @return HttpResponse - The result of the operation
  */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "Allocators created")
+    )
+)]
 #[post("/allocator/create")]
 pub async fn create_allocator_from_json(
     files: web::Json<ChangedAllocators>,
@@ -41,13 +62,152 @@ pub async fn create_allocator_from_json(
     let ChangedAllocators { files_changed } = files.into_inner();
     create_allocator_from_file(files_changed)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("All files processed successfully")
             .expect("Serialization of static string should succeed"),
     ))
 }
 
+/**
+ * Syncs the allocators in the db with the allocator files in the governance repository
+ *
+ * # Arguments
+ * @param query: web::Query<AllocatorSyncQueryParams> - Whether to only report the differences (default true)
+ *
+ * # Returns
+ * @return HttpResponse - The sync report
+ */
+#[utoipa::path(
+    tag = "allocator",
+    params(AllocatorSyncQueryParams),
+    responses(
+        (status = 200, description = "Sync report of the allocator registry")
+    )
+)]
+#[post("/allocator/sync")]
+pub async fn sync_allocators(
+    query: web::Query<AllocatorSyncQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let dry_run = query.into_inner().dry_run.unwrap_or(true);
+    let report = sync_allocator_registry(dry_run).await.map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/**
+ * Syncs the multisig threshold, signers and pending transactions of all allocators from the chain
+ *
+ * # Returns
+ * @return HttpResponse - The sync report with signers diverging from the governance records
+ */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "Sync report of the allocator multisigs")
+    )
+)]
+#[post("/allocator/multisig/sync")]
+pub async fn sync_multisigs() -> actix_web::Result<impl Responder> {
+    let report = sync_allocator_multisigs().await.map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/**
+ * Stores a snapshot of the remaining DataCap of all allocators
+ *
+ * # Returns
+ * @return HttpResponse - The forecast of each allocator
+ */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "Allowance snapshot report")
+    )
+)]
+#[post("/allocator/allowance/snapshot")]
+pub async fn snapshot_allowances() -> actix_web::Result<impl Responder> {
+    let report = snapshot_allocator_allowances().await.map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/**
+ * Get the remaining DataCap, burn rate and projected run-out date of an allocator
+ *
+ * # Arguments
+ * @param path: web::Path<(String, String)> - The owner and repo of the allocator
+ *
+ * # Returns
+ * @return HttpResponse - The forecast computed from the stored snapshots
+ */
+#[utoipa::path(
+    tag = "allocator",
+    params(
+        ("owner" = String, Path, description = "Owner of the allocator repository"),
+        ("repo" = String, Path, description = "Allocator repository")
+    ),
+    responses(
+        (status = 200, description = "Allowance forecast of the allocator")
+    )
+)]
+#[get("/allocator/{owner}/{repo}/allowance")]
+pub async fn allowance_forecast(
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<impl Responder> {
+    let (owner, repo) = path.into_inner();
+    let forecast = get_allowance_forecast(&owner, &repo)
+        .await
+        .map_err(ErrorNotFound)?;
+    Ok(HttpResponse::Ok().json(forecast))
+}
+
+/**
+ * Validates an allocator file against the allocator schema and the state of its multisig
+ *
+ * # Arguments
+ * @param file: web::Json<serde_json::Value> - The allocator JSON file
+ * @param query: web::Query<AllocatorValidateQueryParams> - Whether to check the multisig on chain (default true)
+ *
+ * # Returns
+ * @return HttpResponse - The list of violations with their JSON pointers
+ */
+#[utoipa::path(
+    tag = "allocator",
+    params(AllocatorValidateQueryParams),
+    responses(
+        (status = 200, description = "Validation report of the allocator file")
+    )
+)]
+#[post("/allocator/validate")]
+pub async fn validate_allocator(
+    file: web::Json<serde_json::Value>,
+    query: web::Query<AllocatorValidateQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let check_chain = query.into_inner().check_chain.unwrap_or(true);
+    let report = validate_allocator_file(&file.into_inner(), check_chain)
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/**
+ * Get the JSON schema of allocator files
+ *
+ * # Returns
+ * @return HttpResponse - The JSON schema
+ */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "JSON schema of allocator files", content_type = "application/schema+json")
+    )
+)]
+#[get("/allocator/schema")]
+pub async fn allocator_schema() -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok()
+        .content_type("application/schema+json")
+        .body(ALLOCATOR_FILE_SCHEMA))
+}
+
 /**
  * Get an allocator
  *
@@ -57,6 +217,17 @@ pub async fn create_allocator_from_json(
  * # Returns
  * @return HttpResponse - The result of the operation
  */
+#[utoipa::path(
+    tag = "allocator",
+    params(
+        ("owner" = String, Path, description = "Owner of the allocator repository"),
+        ("repo" = String, Path, description = "Allocator repository")
+    ),
+    responses(
+        (status = 200, description = "Allocator"),
+        (status = 404, description = "Allocator not found")
+    )
+)]
 #[get("/allocator/{owner}/{repo}")]
 pub async fn allocator(path: web::Path<(String, String)>) -> actix_web::Result<impl Responder> {
     let (owner, repo) = path.into_inner();
@@ -79,6 +250,16 @@ pub async fn allocator(path: web::Path<(String, String)>) -> actix_web::Result<i
  * # Returns
  * @return HttpResponse - The result of the operation
  */
+#[utoipa::path(
+    tag = "allocator",
+    params(
+        ("owner" = String, Path, description = "Owner of the allocator repository"),
+        ("repo" = String, Path, description = "Allocator repository")
+    ),
+    responses(
+        (status = 200, description = "Allocator deleted")
+    )
+)]
 #[delete("/allocator/{owner}/{repo}")]
 pub async fn delete(path: web::Path<(String, String)>) -> actix_web::Result<impl Responder> {
     let (owner, repo) = path.into_inner();
@@ -92,10 +273,17 @@ pub async fn delete(path: web::Path<(String, String)>) -> actix_web::Result<impl
  * Force updating allocator files from template.
  * It receives a list of changed files and allocators to update.
  * If allocators is not provided, it will update all allocators as long as they have an installation id.
+ * If staged is set, it opens a pull request per allocator instead of committing to the default branch.
  *
  * # Arguments
  * @param AllocatorUpdateForceInfo - The list of changed JSON file names and allocators to update
  */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "Allocator files updated, or the pull requests opened when staged")
+    )
+)]
 #[post("/allocator/update/force")]
 pub async fn update_allocator_force(
     body: web::Json<AllocatorUpdateForceInfo>,
@@ -104,21 +292,57 @@ pub async fn update_allocator_force(
     let AllocatorUpdateForceInfo {
         files,
         allocators: affected_allocators,
+        staged,
     } = body.into_inner();
 
+    if staged.unwrap_or(false) {
+        let updates = stage_template_update(files, affected_allocators)
+            .await
+            .map_err(ldn_error)?;
+        return Ok(HttpResponse::Ok().json(updates));
+    }
+
     // Logic will be implemented in allocator::update_allocator_force
     force_update_allocators(files, affected_allocators)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().json(()))
 }
 
+/**
+ * Reports which allocator template files are missing, outdated or locally modified in the allocator repositories.
+ * If allocators is not provided, it will check all allocators as long as they have an installation id.
+ *
+ * # Arguments
+ * @param AllocatorTemplateDriftInfo - The list of template file names and allocators to check
+ *
+ * # Returns
+ * @return HttpResponse - The drift report with a diff for each drifted file
+ */
+#[utoipa::path(
+    tag = "allocator",
+    responses(
+        (status = 200, description = "Template drift report of each allocator")
+    )
+)]
+#[post("/allocator/template/drift")]
+pub async fn template_drift(
+    body: web::Json<AllocatorTemplateDriftInfo>,
+) -> actix_web::Result<impl Responder> {
+    let AllocatorTemplateDriftInfo {
+        files,
+        allocators: affected_allocators,
+    } = body.into_inner();
+    let report = get_template_drift(files, affected_allocators)
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
 #[get("/get_installation_ids")]
 pub async fn get_installation_ids() -> actix_web::Result<impl Responder> {
     let client = Client::new();
-    let jwt = generate_github_app_jwt()
-        .await
-        .map_err(ErrorInternalServerError)?;
+    let jwt = generate_github_app_jwt().await.map_err(ldn_error)?;
 
     let ids = fetch_installation_ids(&client, &jwt).await.map_err(|e| {
         log::error!("Failed to generate GitHub App JWT: {}", e);
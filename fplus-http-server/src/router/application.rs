@@ -3,25 +3,53 @@ use actix_web::{
     get, post, web, HttpResponse, Responder,
 };
 use fplus_lib::core::{
-    application::file::{StorageProviderChangeVerifier, VerifierInput},
-    ApplicationQueryParams, BranchDeleteInfo, CompleteGovernanceReviewInfo,
-    CompleteNewApplicationApprovalInfo, CompleteNewApplicationProposalInfo, CreateApplicationInfo,
-    DcReachedInfo, GithubQueryParams, LDNApplication, MoreInfoNeeded, NotifyRefillInfo,
-    StorageProvidersChangeApprovalInfo, StorageProvidersChangeProposalInfo, SubmitKYCInfo,
-    TriggerSSAInfo, ValidationPullRequestData, VerifierActionsQueryParams,
+    application::{
+        file::{
+            ApplicationFile, ApplicationResponse, StorageProviderChangeVerifier, VerifierInput,
+        },
+        reconciliation::{reconcile_applications, ReconciliationReport},
+        schema::{
+            application_file_json_schema, upgrade_application_files, ApplicationSchemaUpgrade,
+        },
+        tranche::Tranche,
+    },
+    ApplicationQueryParams, ApplicationSchemaUpgradeInfo, BranchDeleteInfo,
+    CompleteGovernanceReviewInfo, CompleteNewApplicationApprovalInfo,
+    CompleteNewApplicationProposalInfo, CreateApplicationInfo, DcReachedInfo, GithubQueryParams,
+    IssueValidationQueryParams, LDNApplication, MoreInfoNeeded, NotifyRefillInfo,
+    ReconciliationQueryParams, StorageProvidersChangeApprovalInfo,
+    StorageProvidersChangeProposalInfo, SubmitKYCInfo, TriggerSSAInfo, ValidationPullRequestData,
+    VerifierActionsQueryParams,
 };
+use fplus_lib::parsers::validation::IssueValidationReport;
 
+use super::ldn_error;
+
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Application created from the issue")
+    )
+)]
 #[post("/application")]
 pub async fn create(info: web::Json<CreateApplicationInfo>) -> actix_web::Result<impl Responder> {
     let app = LDNApplication::new_from_issue(info.into_inner())
         .await
-        .map_err(ErrorBadRequest)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(format!(
         "Created new application for issue: {}",
         app.application_id.clone()
     )))
 }
 
+#[utoipa::path(
+    tag = "application",
+    params(ApplicationQueryParams),
+    responses(
+        (status = 200, description = "Application file", body = ApplicationFile),
+        (status = 404, description = "Application not found")
+    )
+)]
 #[get("/application")]
 pub async fn single(
     query: web::Query<ApplicationQueryParams>,
@@ -34,6 +62,13 @@ pub async fn single(
     Ok(HttpResponse::Ok().body(body))
 }
 
+#[utoipa::path(
+    tag = "application",
+    params(ApplicationQueryParams),
+    responses(
+        (status = 200, description = "Application file with the allocation amount options of the allocator")
+    )
+)]
 #[get("/application/with-allocation-amount")]
 pub async fn application_with_allocation_amount_handler(
     query: web::Query<ApplicationQueryParams>,
@@ -45,6 +80,121 @@ pub async fn application_with_allocation_amount_handler(
     Ok(HttpResponse::Ok().json(application))
 }
 
+#[utoipa::path(
+    tag = "application",
+    params(ApplicationQueryParams),
+    responses(
+        (status = 200, description = "Compliance report of the application")
+    )
+)]
+#[get("/application/compliance")]
+pub async fn compliance(
+    query: web::Query<ApplicationQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let ApplicationQueryParams { id, owner, repo } = query.into_inner();
+    let report = LDNApplication::compliance_report(id, owner, repo)
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    tag = "application",
+    params(IssueValidationQueryParams),
+    responses(
+        (status = 200, description = "Validation report of the application issue", body = IssueValidationReport)
+    )
+)]
+#[get("/application/issue/validate")]
+pub async fn validate_issue(
+    query: web::Query<IssueValidationQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let IssueValidationQueryParams {
+        issue_number,
+        owner,
+        repo,
+    } = query.into_inner();
+    let report = LDNApplication::validate_application_issue(issue_number, owner, repo)
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    tag = "application",
+    params(ApplicationQueryParams),
+    responses(
+        (status = 200, description = "Next tranche of the application, if any", body = Option<Tranche>)
+    )
+)]
+#[get("/application/next_tranche")]
+pub async fn next_tranche(
+    query: web::Query<ApplicationQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let ApplicationQueryParams { id, owner, repo } = query.into_inner();
+    let tranche = LDNApplication::next_tranche(&id, &owner, &repo)
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(tranche))
+}
+
+/**
+ * Get the JSON schema of application files, generated from the current application file type
+ *
+ * # Returns
+ * @return HttpResponse - The JSON schema
+ */
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "JSON schema of application files", content_type = "application/schema+json")
+    )
+)]
+#[get("/application/schema")]
+pub async fn application_schema() -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok()
+        .content_type("application/schema+json")
+        .json(application_file_json_schema()))
+}
+
+/**
+ * Upgrades the application files of the allocator repositories to the current schema version.
+ * If allocators is not provided, it will upgrade all allocators as long as they have an installation id.
+ *
+ * # Arguments
+ * @param ApplicationSchemaUpgradeInfo - The allocators to upgrade and whether to only report the outdated files
+ *
+ * # Returns
+ * @return HttpResponse - The upgraded files and the pull request opened for each allocator
+ */
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Upgraded files and pull request of each allocator", body = Vec<ApplicationSchemaUpgrade>)
+    )
+)]
+#[post("/application/schema/upgrade")]
+pub async fn upgrade_schema(
+    body: web::Json<ApplicationSchemaUpgradeInfo>,
+) -> actix_web::Result<impl Responder> {
+    let ApplicationSchemaUpgradeInfo {
+        allocators,
+        dry_run,
+    } = body.into_inner();
+    let upgrades = upgrade_application_files(allocators, dry_run.unwrap_or(false))
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(upgrades))
+}
+
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Updated application file", body = ApplicationFile)
+    )
+)]
 #[post("/application/trigger")]
 pub async fn trigger(
     query: web::Query<VerifierActionsQueryParams>,
@@ -59,6 +209,7 @@ pub async fn trigger(
     let CompleteGovernanceReviewInfo {
         allocation_amount,
         client_contract_address,
+        override_tranche_policy,
     } = info.into_inner();
     let response = ldn_application
         .complete_governance_review(
@@ -67,9 +218,10 @@ pub async fn trigger(
             query.repo.clone(),
             allocation_amount,
             client_contract_address,
+            override_tranche_policy.unwrap_or(false),
         )
         .await
-        .map_err(ErrorBadRequest)?;
+        .map_err(ldn_error)?;
 
     let serialized_app = serde_json::to_string_pretty(&response)
         .map_err(|_| ErrorInternalServerError("Failed to serialize success message".to_string()))?;
@@ -77,6 +229,14 @@ pub async fn trigger(
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Updated application file", body = ApplicationFile)
+    )
+)]
 #[post("/application/approve_changes")]
 pub async fn approve_changes(
     query: web::Query<VerifierActionsQueryParams>,
@@ -96,6 +256,14 @@ pub async fn approve_changes(
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Updated application file", body = ApplicationFile)
+    )
+)]
 #[post("/application/propose")]
 pub async fn propose(
     info: web::Json<CompleteNewApplicationProposalInfo>,
@@ -126,12 +294,20 @@ pub async fn propose(
             new_allocation_amount,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     let serialized_app = serde_json::to_string_pretty(&response)
         .map_err(|_| ErrorInternalServerError("Failed to serialize success message".to_string()))?;
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Storage providers change proposed")
+    )
+)]
 #[post("/application/propose_storage_providers")]
 pub async fn propose_storage_providers(
     info: web::Json<StorageProvidersChangeProposalInfo>,
@@ -163,13 +339,21 @@ pub async fn propose_storage_providers(
             max_deviation,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
     ))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Storage providers change approved")
+    )
+)]
 #[post("/application/approve_storage_providers")]
 pub async fn approve_storage_providers(
     info: web::Json<StorageProvidersChangeApprovalInfo>,
@@ -195,13 +379,21 @@ pub async fn approve_storage_providers(
             request_id,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
     ))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Updated application file", body = ApplicationFile)
+    )
+)]
 #[post("/application/approve")]
 pub async fn approve(
     query: web::Query<VerifierActionsQueryParams>,
@@ -228,12 +420,20 @@ pub async fn approve(
             None,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     let serialized_app = serde_json::to_string_pretty(&app).map_err(ErrorInternalServerError)?;
 
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Application declined")
+    )
+)]
 #[post("/application/decline")]
 pub async fn decline(
     query: web::Query<VerifierActionsQueryParams>,
@@ -245,11 +445,19 @@ pub async fn decline(
     ldn_application
         .decline_application(query.owner.clone(), query.repo.clone())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
 
     Ok(HttpResponse::Ok().body(()))
 }
 
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Updated application file", body = ApplicationFile)
+    )
+)]
 #[post("/application/additional_info_required")]
 pub async fn additional_info_required(
     query: web::Query<VerifierActionsQueryParams>,
@@ -263,12 +471,18 @@ pub async fn additional_info_required(
     let app = ldn_application
         .additional_info_required(query.owner.clone(), query.repo.clone(), verifier_message)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     let serialized_app = serde_json::to_string_pretty(&app).map_err(ErrorInternalServerError)?;
 
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "All applications", body = Vec<ApplicationResponse>)
+    )
+)]
 #[get("/applications")]
 pub async fn all_applications() -> actix_web::Result<impl Responder> {
     let apps = LDNApplication::all_applications()
@@ -281,49 +495,81 @@ pub async fn all_applications() -> actix_web::Result<impl Responder> {
         .body(parsed))
 }
 
+#[utoipa::path(
+    tag = "application",
+    params(GithubQueryParams),
+    responses(
+        (status = 200, description = "Active applications of the allocator", body = Vec<ApplicationResponse>)
+    )
+)]
 #[get("/application/active")]
 pub async fn active(query: web::Query<GithubQueryParams>) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
     let app = LDNApplication::active(owner, repo, None)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     let serialized_app = serde_json::to_string_pretty(&app).map_err(ErrorInternalServerError)?;
 
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
+#[utoipa::path(
+    tag = "application",
+    params(GithubQueryParams),
+    responses(
+        (status = 200, description = "Merged applications of the allocator with their GitHub file info")
+    )
+)]
 #[get("/application/merged")]
 pub async fn merged(query: web::Query<GithubQueryParams>) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
     let apps = LDNApplication::merged(owner, repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     let serialized_apps = serde_json::to_string_pretty(&apps).map_err(ErrorInternalServerError)?;
 
     Ok(HttpResponse::Ok().body(serialized_apps))
 }
 
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Refill notification sent")
+    )
+)]
 #[post("/application/notify_refill")]
 pub async fn notify_refill(info: web::Json<NotifyRefillInfo>) -> actix_web::Result<impl Responder> {
     LDNApplication::notify_refill(info.into_inner())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
     ))
 }
 
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Whether the application reached its total DataCap")
+    )
+)]
 #[post("/application/totaldcreached")]
 pub async fn total_dc_reached(data: web::Json<DcReachedInfo>) -> actix_web::Result<impl Responder> {
     let DcReachedInfo { id, owner, repo } = data.into_inner();
     let applications = LDNApplication::total_dc_reached(id, owner, repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().json(applications))
 }
 
-#[post("application/flow/validate")]
+#[utoipa::path(
+    tag = "validation",
+    responses(
+        (status = 200, description = "Whether the application flow is valid")
+    )
+)]
+#[post("/application/flow/validate")]
 pub async fn validate_application_flow(
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
@@ -336,14 +582,20 @@ pub async fn validate_application_flow(
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
         let result = LDNApplication::validate_flow(pr_number, &user_handle, owner, repo)
             .await
-            .map_err(ErrorInternalServerError)?;
+            .map_err(ldn_error)?;
         Ok(HttpResponse::Ok().json(result))
     } else {
         Err(ErrorBadRequest("Invalid PR Number"))
     }
 }
 
-#[post("application/trigger/validate")]
+#[utoipa::path(
+    tag = "validation",
+    responses(
+        (status = 200, description = "Whether the trigger is valid")
+    )
+)]
+#[post("/application/trigger/validate")]
 pub async fn validate_application_trigger(
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
@@ -357,14 +609,20 @@ pub async fn validate_application_trigger(
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
         let result = LDNApplication::validate_trigger(pr_number, &user_handle, owner, repo)
             .await
-            .map_err(ErrorInternalServerError)?;
+            .map_err(ldn_error)?;
         Ok(HttpResponse::Ok().json(result))
     } else {
         Err(ErrorBadRequest("Invalid PR Number"))
     }
 }
 
-#[post("application/proposal/validate")]
+#[utoipa::path(
+    tag = "validation",
+    responses(
+        (status = 200, description = "Whether the proposal is valid")
+    )
+)]
+#[post("/application/proposal/validate")]
 pub async fn validate_application_proposal(
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
@@ -378,14 +636,20 @@ pub async fn validate_application_proposal(
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
         let result = LDNApplication::validate_proposal(pr_number, owner, repo)
             .await
-            .map_err(ErrorInternalServerError)?;
+            .map_err(ldn_error)?;
         Ok(HttpResponse::Ok().json(result))
     } else {
         Err(ErrorBadRequest("Invalid PR Number"))
     }
 }
 
-#[post("application/approval/validate")]
+#[utoipa::path(
+    tag = "validation",
+    responses(
+        (status = 200, description = "Whether the approval is valid")
+    )
+)]
+#[post("/application/approval/validate")]
 pub async fn validate_application_approval(
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
@@ -399,14 +663,20 @@ pub async fn validate_application_approval(
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
         let result = LDNApplication::validate_approval(pr_number, owner, repo)
             .await
-            .map_err(ErrorInternalServerError)?;
+            .map_err(ldn_error)?;
         Ok(HttpResponse::Ok().json(result))
     } else {
         Err(ErrorBadRequest("Invalid PR Number"))
     }
 }
 
-#[post("application/merge/validate")]
+#[utoipa::path(
+    tag = "validation",
+    responses(
+        (status = 200, description = "Whether the application can be merged")
+    )
+)]
+#[post("/application/merge/validate")]
 pub async fn validate_application_merge(
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
@@ -420,51 +690,104 @@ pub async fn validate_application_merge(
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
         let result = LDNApplication::validate_merge_application(pr_number, owner, repo)
             .await
-            .map_err(ErrorInternalServerError)?;
+            .map_err(ldn_error)?;
         Ok(HttpResponse::Ok().json(result))
     } else {
         Err(ErrorBadRequest("Invalid PR Number"))
     }
 }
 
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Whether the branch was deleted")
+    )
+)]
 #[post("/application/branch/delete")]
 pub async fn delete_branch(data: web::Json<BranchDeleteInfo>) -> actix_web::Result<impl Responder> {
     let info = data.into_inner();
     let result = LDNApplication::delete_branch(info.owner, info.repo, info.branch_name)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().json(result))
 }
 
-#[post("application/cache/renewal")]
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Cache renewed")
+    )
+)]
+#[post("/application/cache/renewal")]
 pub async fn cache_renewal(
     info: web::Json<GithubQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = info.into_inner();
     LDNApplication::cache_renewal_active(owner.clone(), repo.clone())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
 
     LDNApplication::cache_renewal_merged(owner, repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().json("Cache renewal for active and merged applications succeeded"))
 }
 
-#[post("application/update-from-issue")]
+/**
+ * Reconciles the cached applications of a repository with the changes on GitHub since the previous run.
+ * Runs as a dry run unless dry_run is false.
+ *
+ * # Returns
+ * @return HttpResponse - The added, updated, deleted and conflicting applications
+ */
+#[utoipa::path(
+    tag = "application",
+    params(ReconciliationQueryParams),
+    responses(
+        (status = 200, description = "Differences between GitHub and the database", body = ReconciliationReport)
+    )
+)]
+#[post("/application/cache/reconcile")]
+pub async fn cache_reconcile(
+    query: web::Query<ReconciliationQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let ReconciliationQueryParams {
+        owner,
+        repo,
+        dry_run,
+    } = query.into_inner();
+    let report = reconcile_applications(owner, repo, dry_run.unwrap_or(true))
+        .await
+        .map_err(ldn_error)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "Application updated from the issue")
+    )
+)]
+#[post("/application/update-from-issue")]
 pub async fn update_from_issue(
     info: web::Json<CreateApplicationInfo>,
 ) -> actix_web::Result<impl Responder> {
     let app = LDNApplication::update_from_issue(info.into_inner())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(format!(
         "Updated application for issue: {}",
         app.application_id.clone()
     )))
 }
 
-#[post("application/check_for_changes")]
+#[utoipa::path(
+    tag = "validation",
+    responses(
+        (status = 200, description = "Whether the application changes are valid")
+    )
+)]
+#[post("/application/check_for_changes")]
 pub async fn check_for_changes(
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
@@ -478,14 +801,20 @@ pub async fn check_for_changes(
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
         let result = LDNApplication::check_for_changes(pr_number, &user_handle, owner, repo)
             .await
-            .map_err(ErrorInternalServerError)?;
+            .map_err(ldn_error)?;
         Ok(HttpResponse::Ok().json(result))
     } else {
         Err(ErrorBadRequest("Invalid PR Number"))
     }
 }
 
-#[post("application/submit_kyc")]
+#[utoipa::path(
+    tag = "application",
+    responses(
+        (status = 200, description = "KYC submitted")
+    )
+)]
+#[post("/application/submit_kyc")]
 pub async fn submit_kyc(info: web::Json<SubmitKYCInfo>) -> actix_web::Result<impl Responder> {
     let ldn_application = LDNApplication::load(
         info.message.client_id.clone(),
@@ -498,7 +827,7 @@ pub async fn submit_kyc(info: web::Json<SubmitKYCInfo>) -> actix_web::Result<imp
     ldn_application
         .submit_kyc(&info.into_inner())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Address verified with score")
             .expect("Serialization of static string should succeed"),
@@ -510,7 +839,15 @@ pub async fn health() -> actix_web::Result<impl Responder> {
     Ok(HttpResponse::Ok().body("OK"))
 }
 
-#[post("application/request_kyc")]
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "KYC requested")
+    )
+)]
+#[post("/application/request_kyc")]
 pub async fn request_kyc(
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
@@ -521,14 +858,22 @@ pub async fn request_kyc(
     ldn_application
         .request_kyc(&query.id, &query.owner, &query.repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
     ))
 }
 
-#[post("application/trigger_ssa")]
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Subsequent allocation triggered")
+    )
+)]
+#[post("/application/trigger_ssa")]
 pub async fn trigger_ssa(
     query: web::Query<VerifierActionsQueryParams>,
     info: web::Json<TriggerSSAInfo>,
@@ -541,14 +886,22 @@ pub async fn trigger_ssa(
         info.into_inner(),
     )
     .await
-    .map_err(ErrorInternalServerError)?;
+    .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
     ))
 }
 
-#[post("application/remove_pending_allocation")]
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Pending allocation removed")
+    )
+)]
+#[post("/application/remove_pending_allocation")]
 pub async fn remove_pending_allocation(
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
@@ -559,7 +912,7 @@ pub async fn remove_pending_allocation(
     ldn_application
         .remove_pending_allocation(&query.id, &query.owner, &query.repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
 
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
@@ -567,7 +920,15 @@ pub async fn remove_pending_allocation(
     ))
 }
 
-#[post("application/allocation_failed")]
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Allocation reverted to ready to sign")
+    )
+)]
+#[post("/application/allocation_failed")]
 pub async fn allocation_failed(
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
@@ -578,7 +939,7 @@ pub async fn allocation_failed(
     ldn_application
         .revert_to_ready_to_sign(&query.id, &query.owner, &query.repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
 
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
@@ -3,6 +3,16 @@ use actix_web::{get, post, web, HttpResponse, Responder};
 use fplus_database::database::autoallocations as autoallocations_db;
 use fplus_lib::core::autoallocator;
 use fplus_lib::core::{LastAutoallocationQueryParams, TriggerAutoallocationInfo};
+
+use super::ldn_error;
+
+#[utoipa::path(
+    tag = "autoallocator",
+    params(LastAutoallocationQueryParams),
+    responses(
+        (status = 200, description = "Last autoallocation of the wallet")
+    )
+)]
 #[get("/autoallocator/last_client_allocation")]
 pub async fn last_client_allocation(
     query: web::Query<LastAutoallocationQueryParams>,
@@ -17,13 +27,19 @@ pub async fn last_client_allocation(
     Ok(HttpResponse::Ok().body(serialized_last_client_allocation))
 }
 
-#[post("autoallocator/trigger_autoallocation")]
+#[utoipa::path(
+    tag = "autoallocator",
+    responses(
+        (status = 200, description = "Autoallocation triggered")
+    )
+)]
+#[post("/autoallocator/trigger_autoallocation")]
 pub async fn trigger_autoallocation(
     info: web::Json<TriggerAutoallocationInfo>,
 ) -> actix_web::Result<impl Responder> {
     autoallocator::trigger_autoallocation(&info.into_inner())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(ldn_error)?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
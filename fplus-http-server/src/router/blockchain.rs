@@ -21,6 +21,13 @@ use fplus_lib::external_services::{
 /// }
 /// ```
 
+#[utoipa::path(
+    tag = "blockchain",
+    params(("address" = String, Path, description = "Filecoin address")),
+    responses(
+        (status = 200, description = "Allowance of the address")
+    )
+)]
 #[get("/blockchain/address_allowance/{address}")]
 pub async fn address_allowance(address: web::Path<String>) -> actix_web::Result<impl Responder> {
     let res = get_allowance_for_address(&address.into_inner())
@@ -47,6 +54,12 @@ pub async fn address_allowance(address: web::Path<String>) -> actix_web::Result<
 /// ]
 /// ```
 
+#[utoipa::path(
+    tag = "blockchain",
+    responses(
+        (status = 200, description = "Verified clients")
+    )
+)]
 #[get("/blockchain/verified_clients")]
 pub async fn verified_clients() -> actix_web::Result<impl Responder> {
     let blockchain = BlockchainData::new();
@@ -1,13 +1,74 @@
-use actix_web::{get, HttpResponse, Responder};
+use actix_web::{
+    error::{ErrorConflict, ErrorInternalServerError, InternalError},
+    get,
+    http::header::RETRY_AFTER,
+    HttpResponse, Responder,
+};
+use fplus_lib::error::LDNError;
+use fplus_lib::external_services::github_cache::{github_cache_stats, GithubCacheStats};
+use fplus_lib::external_services::github_rate_limit::{
+    github_rate_limit_budgets, GithubRateLimitBudget,
+};
 
 pub mod allocator;
 pub mod application;
 pub mod autoallocator;
 pub mod blockchain;
+pub mod openapi;
+pub mod similarity;
 pub mod verifier;
 
 /// Return server health status
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Server is healthy")
+    )
+)]
 #[get("/health")]
 pub async fn health() -> actix_web::Result<impl Responder> {
     Ok(HttpResponse::Ok().body("OK"))
 }
+
+/// Return the hit ratio and usage of the GitHub response cache
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "GitHub response cache statistics", body = GithubCacheStats)
+    )
+)]
+#[get("/github/cache")]
+pub async fn github_cache() -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(github_cache_stats()))
+}
+
+/// Return the GitHub API budget left to each installation
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "GitHub rate limit budgets", body = Vec<GithubRateLimitBudget>)
+    )
+)]
+#[get("/github/rate-limits")]
+pub async fn github_rate_limits() -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(github_rate_limit_budgets()))
+}
+
+/// Maps errors of the lifecycle to a response. Rate limited operations answer `429` with a
+/// `Retry-After` header so that the caller can retry them.
+pub fn ldn_error(e: LDNError) -> actix_web::Error {
+    match e {
+        LDNError::RateLimited(ref limited) => {
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, limited.retry_after))
+                .json(limited);
+            InternalError::from_response(e, response).into()
+        }
+        LDNError::Conflict(ref conflict) => {
+            let response = HttpResponse::Conflict().json(conflict);
+            InternalError::from_response(e, response).into()
+        }
+        LDNError::Replay(_) => ErrorConflict(e),
+        _ => ErrorInternalServerError(e),
+    }
+}
@@ -0,0 +1,127 @@
+use actix_web::{get, HttpResponse, Responder};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use super::{
+    __path_github_cache, __path_github_rate_limits, __path_health, allocator, application,
+    autoallocator, blockchain, similarity, verifier,
+};
+
+/// OpenAPI document generated from the handlers registered in `main.rs` and the request types
+/// of `fplus_lib::core`. New handlers must be added to `paths` to be documented.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Fil+ Backend"),
+    modifiers(&GithubTokenAuth),
+    paths(
+        health,
+        github_cache,
+        github_rate_limits,
+        application::create,
+        application::single,
+        application::application_with_allocation_amount_handler,
+        application::compliance,
+        application::validate_issue,
+        application::next_tranche,
+        application::application_schema,
+        application::upgrade_schema,
+        application::trigger,
+        application::approve_changes,
+        application::propose,
+        application::propose_storage_providers,
+        application::approve_storage_providers,
+        application::approve,
+        application::decline,
+        application::additional_info_required,
+        application::all_applications,
+        application::active,
+        application::merged,
+        application::notify_refill,
+        application::total_dc_reached,
+        application::validate_application_flow,
+        application::validate_application_trigger,
+        application::validate_application_proposal,
+        application::validate_application_approval,
+        application::validate_application_merge,
+        application::delete_branch,
+        application::cache_renewal,
+        application::cache_reconcile,
+        application::update_from_issue,
+        application::check_for_changes,
+        application::submit_kyc,
+        application::request_kyc,
+        application::trigger_ssa,
+        application::remove_pending_allocation,
+        application::allocation_failed,
+        allocator::allocators,
+        allocator::create_allocator_from_json,
+        allocator::sync_allocators,
+        allocator::sync_multisigs,
+        allocator::snapshot_allowances,
+        allocator::allowance_forecast,
+        allocator::validate_allocator,
+        allocator::allocator_schema,
+        allocator::allocator,
+        allocator::delete,
+        allocator::update_allocator_force,
+        allocator::template_drift,
+        autoallocator::last_client_allocation,
+        autoallocator::trigger_autoallocation,
+        blockchain::address_allowance,
+        blockchain::verified_clients,
+        similarity::check,
+        similarity::reports,
+        similarity::rerun,
+        verifier::verifiers,
+    )
+)]
+pub struct ApiDoc;
+
+/// Handlers under `/verifier` require the GitHub token of the verifier.
+struct GithubTokenAuth;
+
+impl Modify for GithubTokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "github_token",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
+
+/// Return the OpenAPI document of the API
+#[get("/openapi.json")]
+pub async fn openapi_json() -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(ApiDoc::openapi()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_handlers_are_documented() {
+        let documented: Vec<String> = ApiDoc::openapi()
+            .paths
+            .paths
+            .values()
+            .flat_map(|item| {
+                [&item.get, &item.post, &item.put, &item.delete, &item.patch]
+                    .into_iter()
+                    .filter_map(|operation| operation.as_ref()?.operation_id.clone())
+            })
+            .collect();
+        let undocumented: Vec<&str> = include_str!("../main.rs")
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix(".service(router::"))
+            .filter_map(|service| service.split(')').next()?.rsplit("::").next())
+            .filter(|handler| *handler != "openapi_json")
+            .filter(|handler| !documented.iter().any(|id| id == handler))
+            .collect();
+        assert_eq!(undocumented, Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{error::ErrorInternalServerError, get, post, web, HttpResponse, Responder};
+use fplus_database::database::similarity_reports as similarity_reports_db;
+use fplus_lib::core::{
+    ApplicationQueryParams, LDNApplication, SimilarityCheckInfo, VerifierActionsQueryParams,
+};
+
+/**
+ * Compare application data with all stored applications
+ *
+ * # Arguments
+ * @param info: web::Json<SimilarityCheckInfo> - The application data to compare
+ *
+ * # Returns
+ * @return HttpResponse - The similarity report, not stored in the database
+ */
+#[utoipa::path(
+    tag = "similarity",
+    responses(
+        (status = 200, description = "Similarity report of the application")
+    )
+)]
+#[post("/similarity/check")]
+pub async fn check(info: web::Json<SimilarityCheckInfo>) -> actix_web::Result<impl Responder> {
+    let report = LDNApplication::check_similarity(info.into_inner())
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/**
+ * Get stored similarity reports of an application, newest first
+ *
+ * # Arguments
+ * @param query: web::Query<ApplicationQueryParams> - The application id, owner and repo
+ *
+ * # Returns
+ * @return HttpResponse - The list of stored reports
+ */
+#[utoipa::path(
+    tag = "similarity",
+    params(ApplicationQueryParams),
+    responses(
+        (status = 200, description = "Similarity reports of the application")
+    )
+)]
+#[get("/similarity/reports")]
+pub async fn reports(
+    query: web::Query<ApplicationQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let ApplicationQueryParams { id, owner, repo } = query.into_inner();
+    let reports = similarity_reports_db::get_similarity_reports(&id, &owner, &repo)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(reports))
+}
+
+/**
+ * Re-run similarity detection for an existing application
+ *
+ * # Arguments
+ * @param query: web::Query<VerifierActionsQueryParams> - The application and the verifier running it
+ *
+ * # Returns
+ * @return HttpResponse - The new similarity report
+ */
+#[utoipa::path(
+    tag = "verifier",
+    params(VerifierActionsQueryParams),
+    context_path = "/verifier", security(("github_token" = [])),
+    responses(
+        (status = 200, description = "Similarity report of the application")
+    )
+)]
+#[post("/similarity/rerun")]
+pub async fn rerun(
+    query: web::Query<VerifierActionsQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let report = LDNApplication::rerun_similarity_detection(&query.id, &query.owner, &query.repo)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -1,6 +1,13 @@
 use actix_web::{error::ErrorInternalServerError, get, web, HttpResponse, Responder};
 use fplus_lib::core::{GithubQueryParams, LDNApplication};
 
+#[utoipa::path(
+    tag = "verifier",
+    params(GithubQueryParams),
+    responses(
+        (status = 200, description = "Verifiers of the allocator")
+    )
+)]
 #[get("/verifiers")]
 pub async fn verifiers(query: web::Query<GithubQueryParams>) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
@@ -2,7 +2,7 @@ use base64;
 use base64::read::DecoderReader;
 use serde_json::from_reader;
 
-use crate::core::{allocator::file::AllocatorModel, application::file::ApplicationFile};
+use crate::core::application::file::ApplicationFile;
 
 pub fn decode_application_file(i: &str) -> Option<ApplicationFile> {
     let mut binding = Cursor::new(i);
@@ -16,14 +16,14 @@ pub fn decode_application_file(i: &str) -> Option<ApplicationFile> {
 
 use std::io::Cursor;
 
-pub fn decode_allocator_model(encoded_str: &str) -> Option<AllocatorModel> {
+pub fn decode_allocator_file(encoded_str: &str) -> Option<serde_json::Value> {
     let mut binding = Cursor::new(encoded_str);
     let decoder = DecoderReader::new(&mut binding, base64::STANDARD);
 
     match from_reader(decoder) {
         Ok(model) => Some(model),
         Err(e) => {
-            log::error!("Error decoding allocator model: {}", e.to_string());
+            log::error!("Error decoding allocator file: {}", e.to_string());
             None
         }
     }
@@ -10,13 +10,20 @@ pub fn default_env_vars() -> &'static HashMap<&'static str, &'static str> {
         m.insert("GITHUB_REPO", "filecoin-plus-falcon");
         m.insert("GITHUB_APP_ID", "826129");
         m.insert("GITHUB_INSTALLATION_ID", "48299904");
+        m.insert("GITHUB_CACHE_MAX_BYTES", "67108864"); // 64 MiB
+        m.insert("GITHUB_CACHE_FRESH_SECS", "0");
+        m.insert("GITHUB_CACHE_MAX_AGE_SECS", "3600");
+        m.insert("GITHUB_RATE_LIMIT_RESERVE", "500");
+        m.insert("GITHUB_RATE_LIMIT_MAX_WAIT_SECS", "900");
         m.insert("RUST_LOG", "info");
         m.insert("RUST_BACKTRACE", "1");
         m.insert("DB_URL", "");
         m.insert("ALLOCATOR_GOVERNANCE_OWNER", "fidlabs");
         m.insert("ALLOCATOR_GOVERNANCE_REPO", "Allocator-Governance-Staging");
+        m.insert("ALLOCATOR_GOVERNANCE_DIRECTORY", "Allocators");
         m.insert("ALLOCATOR_TEMPLATE_OWNER", "fidlabs");
         m.insert("ALLOCATOR_TEMPLATE_REPO", "allocator-template");
+        m.insert("ALLOCATOR_TEMPLATE_HISTORY_DEPTH", "30");
         m.insert("BOT_USER", "filplus-allocators-staging-bot[bot]");
         m.insert(
             "BACKEND_URL",
@@ -25,12 +32,17 @@ pub fn default_env_vars() -> &'static HashMap<&'static str, &'static str> {
         m.insert("FILPLUS_ENV", "staging");
         m.insert("GLIF_NODE_URL", "https://api.node.glif.io/rpc/v1");
         m.insert("ISSUE_TEMPLATE_VERSION", "1.3");
+        m.insert("ISSUE_TEMPLATES", "");
         m.insert(
             "GITCOIN_PASSPORT_DECODER",
             "5558D441779Eca04A329BcD6b47830D2C6607769",
         );
         m.insert("PASSPORT_VERIFIER_CHAIN_ID", "10");
         m.insert("GITCOIN_MINIMUM_SCORE", "30");
+        m.insert(
+            "AUTOALLOCATION_IDENTITY_VERIFIERS",
+            r#"[{"provider":"gitcoin_passport"}]"#,
+        );
         m.insert("KYC_URL", "https://kyc.allocator.tech");
         m.insert("RPC_URL", "https://mainnet.optimism.io");
         m.insert("DMOB_API_URL", "https://api.datacapstats.io");
@@ -43,6 +55,18 @@ pub fn default_env_vars() -> &'static HashMap<&'static str, &'static str> {
         m.insert("AUTOALLOCATION_AMOUNT", "1099511627776"); // 1099511627776 B == 1 TiB
         m.insert("TFIDF_THRESHOLD", "0.4");
         m.insert("LEVENSHTEIN_THRESHOLD", "8");
+        m.insert("SIMILARITY_TOP_K", "100");
+        m.insert("SIMILARITY_BACKEND", "lexical");
+        m.insert("EMBEDDING_MODEL_PATH", "");
+        m.insert("EMBEDDING_SIMILARITY_THRESHOLD", "0.85");
+        m.insert("SEMANTIC_SCORE_WEIGHT", "0.5");
+        m.insert("COMPLIANCE_CHECK_MODE", "warn");
+        m.insert("ALLOWANCE_BURN_RATE_WINDOW_DAYS", "30");
+        m.insert("ALLOWANCE_ALERT_WEBHOOK_URL", "");
+        m.insert("LINKED_CLIENT_SP_OVERLAP_THRESHOLD", "0.5");
+        m.insert("LINKED_CLIENT_SP_OVERLAP_WEIGHT", "1.0");
+        m.insert("LINKED_CLIENT_HANDLE_WEIGHT", "1.0");
+        m.insert("LINKED_CLIENT_ADDRESS_WEIGHT", "1.0");
         m
     })
 }
@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use fplus_database::database::allocator_allowance_snapshots::{
+    create_allowance_snapshot, get_allowance_snapshots,
+};
+use fplus_database::database::allocators::{
+    get_allocator, get_allocators, update_allocator_allowance_shortfall,
+};
+use fplus_database::database::applications::get_applications;
+use fplus_database::models::allocators::Model;
+use fplus_database::models::applications::Model as ApplicationModel;
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_env_var_or_default;
+use crate::core::application::file::{AppState, ApplicationFile};
+use crate::error::LDNError;
+use crate::external_services::filecoin::get_allowance_for_verifier;
+use crate::external_services::github::GithubWrapper;
+use crate::helpers::parse_size_to_bytes;
+
+/// Amounts granted by an allocator with the time they were signed.
+type GrantedAllocations = Vec<(DateTime<Utc>, u64)>;
+
+/// Remaining DataCap of an allocator and its projection from recent snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllowanceForecast {
+    pub owner: String,
+    pub repo: String,
+    pub allowance: u64,
+    pub pending_allocations: u64,
+    pub burn_rate_per_day: f64,
+    pub projected_run_out: Option<DateTime<Utc>>,
+    pub snapshot_at: DateTime<Utc>,
+}
+
+impl AllowanceForecast {
+    pub fn can_cover_pending_allocations(&self) -> bool {
+        self.allowance >= self.pending_allocations
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllowanceSnapshotError {
+    pub owner: String,
+    pub repo: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct AllowanceSnapshotReport {
+    pub forecasts: Vec<AllowanceForecast>,
+    pub errors: Vec<AllowanceSnapshotError>,
+}
+
+/// Average DataCap spent per day between the oldest and the newest allowance snapshot. The
+/// spending of an interval is the decrease of the allowance between its snapshots. When the
+/// allowance increased, a refill landed in the interval and hides its spending, so the
+/// allocations signed in the interval are counted instead. With fewer than two snapshots,
+/// the allocations signed within the window ending at `now` are used.
+pub fn burn_rate_per_day(
+    snapshots: &[(DateTime<Utc>, u64)],
+    granted: &[(DateTime<Utc>, u64)],
+    window: Duration,
+    now: DateTime<Utc>,
+) -> f64 {
+    let (Some((first_at, _)), Some((last_at, _))) = (snapshots.first(), snapshots.last()) else {
+        return granted_per_day(granted, window, now);
+    };
+    let days = (*last_at - *first_at).num_seconds() as f64 / 86_400.0;
+    if days <= 0.0 {
+        return granted_per_day(granted, window, now);
+    }
+    let spent: u64 = snapshots
+        .windows(2)
+        .map(|interval| {
+            let ((from, previous), (to, current)) = (interval[0], interval[1]);
+            if current <= previous {
+                previous - current
+            } else {
+                granted_between(granted, from, to)
+            }
+        })
+        .sum();
+    spent as f64 / days
+}
+
+/// Average DataCap granted per day by the allocations signed within the window ending at `now`.
+fn granted_per_day(granted: &[(DateTime<Utc>, u64)], window: Duration, now: DateTime<Utc>) -> f64 {
+    let days = window.num_seconds() as f64 / 86_400.0;
+    if days <= 0.0 {
+        return 0.0;
+    }
+    granted_between(granted, now - window, now) as f64 / days
+}
+
+fn granted_between(
+    granted: &[(DateTime<Utc>, u64)],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> u64 {
+    granted
+        .iter()
+        .filter(|(granted_at, _)| *granted_at > since && *granted_at <= until)
+        .map(|(_, amount)| amount)
+        .sum()
+}
+
+pub fn projected_run_out(
+    allowance: u64,
+    burn_rate_per_day: f64,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if burn_rate_per_day <= 0.0 {
+        return None;
+    }
+    let seconds = allowance as f64 / burn_rate_per_day * 86_400.0;
+    Duration::try_seconds(seconds.min(i64::MAX as f64 / 1000.0) as i64)
+        .and_then(|duration| now.checked_add_signed(duration))
+}
+
+/// DataCap of the allocation requests signed by the allocator, with the time of the last
+/// signature, per allocator repo.
+pub fn granted_allocations_by_repo(
+    applications: &[ApplicationModel],
+) -> HashMap<(String, String), GrantedAllocations> {
+    let mut granted: HashMap<(String, String), GrantedAllocations> = HashMap::new();
+    for application in applications {
+        let Some(application_file) = application
+            .application
+            .as_ref()
+            .and_then(|app| ApplicationFile::from_str(app).ok())
+        else {
+            continue;
+        };
+        for allocation in application_file.allocation.0.iter() {
+            if allocation.is_active {
+                continue;
+            }
+            let signed_at = allocation
+                .signers
+                .0
+                .iter()
+                .filter_map(|signer| signer.created_at.parse::<DateTime<Utc>>().ok())
+                .max();
+            let (Some(signed_at), Some(amount)) =
+                (signed_at, parse_size_to_bytes(&allocation.amount))
+            else {
+                continue;
+            };
+            granted
+                .entry((application.owner.clone(), application.repo.clone()))
+                .or_default()
+                .push((signed_at, amount));
+        }
+    }
+    granted
+}
+
+/// DataCap requested by active allocation requests waiting for signatures, per allocator repo.
+pub fn pending_allocations_by_repo(
+    applications: &[ApplicationModel],
+) -> HashMap<(String, String), u64> {
+    let mut pending = HashMap::new();
+    for application in applications {
+        let Some(application_file) = application
+            .application
+            .as_ref()
+            .and_then(|app| ApplicationFile::from_str(app).ok())
+        else {
+            continue;
+        };
+        if !application_file.lifecycle.is_active
+            || !matches!(
+                application_file.lifecycle.state,
+                AppState::ReadyToSign | AppState::StartSignDatacap
+            )
+        {
+            continue;
+        }
+        let Some(amount) = application_file
+            .allocation
+            .active()
+            .and_then(|allocation| parse_size_to_bytes(&allocation.amount))
+        else {
+            continue;
+        };
+        *pending
+            .entry((application.owner.clone(), application.repo.clone()))
+            .or_default() += amount;
+    }
+    pending
+}
+
+fn burn_rate_window() -> Result<Duration, LDNError> {
+    let days = get_env_var_or_default("ALLOWANCE_BURN_RATE_WINDOW_DAYS")
+        .parse::<i64>()
+        .map_err(|e| {
+            LDNError::New(format!(
+                "Parse ALLOWANCE_BURN_RATE_WINDOW_DAYS to i64 failed: {}",
+                e
+            ))
+        })?;
+    Duration::try_days(days).ok_or(LDNError::New(
+        "ALLOWANCE_BURN_RATE_WINDOW_DAYS is out of range".to_string(),
+    ))
+}
+
+async fn forecast_from_snapshots(
+    allocator: &Model,
+    granted: &[(DateTime<Utc>, u64)],
+) -> Result<AllowanceForecast, LDNError> {
+    let window = burn_rate_window()?;
+    let snapshots = get_allowance_snapshots(allocator.id, Utc::now() - window)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allowance snapshots: {}", e)))?;
+    let last = snapshots.last().ok_or(LDNError::Load(format!(
+        "No allowance snapshots of {}/{}",
+        allocator.owner, allocator.repo
+    )))?;
+    let snapshot_at = last.created_at.with_timezone(&Utc);
+    let allowances: Vec<(DateTime<Utc>, u64)> = snapshots
+        .iter()
+        .map(|snapshot| {
+            (
+                snapshot.created_at.with_timezone(&Utc),
+                snapshot.allowance as u64,
+            )
+        })
+        .collect();
+    let burn_rate = burn_rate_per_day(&allowances, granted, window, snapshot_at);
+    Ok(AllowanceForecast {
+        owner: allocator.owner.clone(),
+        repo: allocator.repo.clone(),
+        allowance: last.allowance as u64,
+        pending_allocations: last.pending_allocations as u64,
+        burn_rate_per_day: burn_rate,
+        projected_run_out: projected_run_out(last.allowance as u64, burn_rate, snapshot_at),
+        snapshot_at,
+    })
+}
+
+/// Forecast of an allocator computed from the stored snapshots.
+pub async fn get_allowance_forecast(
+    owner: &str,
+    repo: &str,
+) -> Result<AllowanceForecast, LDNError> {
+    let allocator = get_allocator(owner, repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocator from DB: {}", e)))?
+        .ok_or(LDNError::Load(format!(
+            "Allocator {}/{} not found",
+            owner, repo
+        )))?;
+    let applications = get_applications()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get applications from DB: {}", e)))?;
+    let granted = granted_allocations_by_repo(&applications)
+        .remove(&(allocator.owner.clone(), allocator.repo.clone()))
+        .unwrap_or_default();
+    forecast_from_snapshots(&allocator, &granted).await
+}
+
+/// Stores a snapshot of the remaining DataCap of every allocator and raises an alert for
+/// allocators that can no longer cover their pending allocation requests.
+pub async fn snapshot_allocator_allowances() -> Result<AllowanceSnapshotReport, LDNError> {
+    let allocators = get_allocators()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocators from DB: {}", e)))?;
+    let applications = get_applications()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get applications from DB: {}", e)))?;
+    let pending = pending_allocations_by_repo(&applications);
+    let granted = granted_allocations_by_repo(&applications);
+
+    let mut report = AllowanceSnapshotReport::default();
+    for allocator in allocators
+        .iter()
+        .filter(|allocator| allocator.removed_from_registry_at.is_none())
+    {
+        let Some(multisig_address) = &allocator.multisig_address else {
+            continue;
+        };
+        let repo = (allocator.owner.clone(), allocator.repo.clone());
+        let pending_allocations = pending.get(&repo).copied().unwrap_or_default();
+        let granted = granted.get(&repo).map(Vec::as_slice).unwrap_or_default();
+        match snapshot_allocator_allowance(
+            allocator,
+            multisig_address,
+            pending_allocations,
+            granted,
+        )
+        .await
+        {
+            Ok(forecast) => report.forecasts.push(forecast),
+            Err(e) => {
+                log::error!(
+                    "Failed to snapshot allowance of {}/{}: {}",
+                    allocator.owner,
+                    allocator.repo,
+                    e
+                );
+                report.errors.push(AllowanceSnapshotError {
+                    owner: allocator.owner.clone(),
+                    repo: allocator.repo.clone(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+async fn snapshot_allocator_allowance(
+    allocator: &Model,
+    multisig_address: &str,
+    pending_allocations: u64,
+    granted: &[(DateTime<Utc>, u64)],
+) -> Result<AllowanceForecast, LDNError> {
+    let allowance = get_allowance_for_verifier(multisig_address)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to retrieve allowance: {}", e)))?;
+    let allowance = allowance.parse::<i64>().map_err(|e| {
+        LDNError::New(format!(
+            "Parse allowance: {} to i64 failed. {}",
+            allowance, e
+        ))
+    })?;
+    create_allowance_snapshot(allocator.id, allowance, pending_allocations as i64)
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to store allowance snapshot: {}", e)))?;
+
+    let forecast = forecast_from_snapshots(allocator, granted).await?;
+    let shortfall_since = if forecast.can_cover_pending_allocations() {
+        None
+    } else {
+        if allocator.allowance_shortfall_since.is_none() {
+            raise_shortfall_alert(allocator, &forecast).await;
+        }
+        allocator
+            .allowance_shortfall_since
+            .or(Some(Utc::now().into()))
+    };
+    if shortfall_since != allocator.allowance_shortfall_since {
+        update_allocator_allowance_shortfall(&allocator.owner, &allocator.repo, shortfall_since)
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to update allowance shortfall: {}", e)))?;
+    }
+    Ok(forecast)
+}
+
+/// Opens an issue in the allocator repository and notifies `ALLOWANCE_ALERT_WEBHOOK_URL`
+/// if it is set. Failures are only logged so the snapshot is still stored.
+async fn raise_shortfall_alert(allocator: &Model, forecast: &AllowanceForecast) {
+    log::warn!(
+        "Allocator {}/{} cannot cover its pending allocation requests",
+        allocator.owner,
+        allocator.repo
+    );
+    let body = format!(
+        "The remaining DataCap of this allocator ({} bytes) does not cover its pending allocation requests ({} bytes). Pending requests will fail until the allocator is refilled.",
+        forecast.allowance, forecast.pending_allocations
+    );
+    let issue = match GithubWrapper::new(
+        allocator.owner.clone(),
+        allocator.repo.clone(),
+        allocator.installation_id,
+    ) {
+        Ok(gh) => gh
+            .create_issue("Insufficient DataCap for pending allocations", &body)
+            .await
+            .map(|_| ())
+            .map_err(|e| LDNError::New(e.to_string())),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = issue {
+        log::error!("Failed to open allowance shortfall issue: {}", e);
+    }
+
+    let webhook_url = get_env_var_or_default("ALLOWANCE_ALERT_WEBHOOK_URL");
+    if webhook_url.is_empty() {
+        return;
+    }
+    if let Err(e) = reqwest::Client::new()
+        .post(&webhook_url)
+        .json(forecast)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        log::error!("Failed to send allowance shortfall webhook: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(days: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::try_days(days).unwrap()
+    }
+
+    #[test]
+    fn burn_rate_without_snapshots_counts_allocations_granted_in_window() {
+        let granted = vec![(at(-5), 1000), (at(1), 200), (at(2), 300), (at(4), 100)];
+        let window = Duration::try_days(4).unwrap();
+        assert_eq!(burn_rate_per_day(&[], &granted, window, at(4)), 150.0);
+        assert_eq!(
+            burn_rate_per_day(&[(at(4), 500)], &granted, window, at(4)),
+            150.0
+        );
+        assert_eq!(burn_rate_per_day(&[], &granted, window, at(-2)), 250.0);
+        assert_eq!(burn_rate_per_day(&[], &[], window, at(4)), 0.0);
+        assert_eq!(
+            burn_rate_per_day(&[], &granted, Duration::zero(), at(4)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn burn_rate_is_computed_from_snapshot_decreases() {
+        let window = Duration::try_days(4).unwrap();
+        let snapshots = vec![(at(0), 1000), (at(1), 900), (at(2), 700), (at(4), 700)];
+        assert_eq!(burn_rate_per_day(&snapshots, &[], window, at(4)), 75.0);
+
+        // The refill between the second and third snapshot hides the 300 granted meanwhile.
+        let snapshots = vec![(at(0), 1000), (at(1), 900), (at(2), 5600), (at(4), 5300)];
+        let granted = vec![(at(-1), 1000), (at(1), 100), (at(2), 300)];
+        assert_eq!(
+            burn_rate_per_day(&snapshots, &granted, window, at(4)),
+            175.0
+        );
+    }
+
+    #[test]
+    fn run_out_is_projected_from_burn_rate() {
+        assert_eq!(
+            projected_run_out(1600, 150.0, at(4)).unwrap().date_naive(),
+            { (at(4) + Duration::try_hours(256).unwrap()).date_naive() }
+        );
+        assert_eq!(projected_run_out(1600, 0.0, at(4)), None);
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::core::application::identity_verifier::IdentityVerifierConfig;
+use crate::core::application::tranche::TranchePolicy;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AllocatorModel {
     pub application: Application,
@@ -28,6 +31,9 @@ pub struct Application {
     pub required_sps: String,
     pub required_replicas: String,
     pub client_contract_address: Option<String>,
+    pub identity_verifiers: Option<Vec<IdentityVerifierConfig>>,
+    pub kyc_validity_days: Option<i32>,
+    pub tranche_policy: Option<TranchePolicy>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -11,13 +11,15 @@ use octocrab::models::repos::{Content, ContentItems};
 
 use crate::config::get_env_var_or_default;
 use crate::external_services::filecoin::get_multisig_threshold_for_actor;
-use crate::external_services::github::GithubWrapper;
-use crate::{base64::decode_allocator_model, error::LDNError};
+use crate::external_services::github::{GithubResultExt, GithubWrapper};
+use crate::external_services::github_rate_limit::in_background;
+use crate::{base64::decode_allocator_file, error::LDNError};
 
 use self::file::{
     AccessTokenResponse, AllocatorModel, Installation, InstallationRepositories,
     RepositoriesResponse, RepositoryInfo,
 };
+use self::validation::{allocator_file_schema_violations, AllocatorFileValidationReport};
 
 use anyhow::Result;
 use jsonwebtoken::EncodingKey;
@@ -25,24 +27,15 @@ use reqwest::{header, Client};
 
 use super::GithubQueryParams;
 
+pub mod allowance;
 pub mod file;
+pub mod multisig;
+pub mod registry;
+pub mod template;
+pub mod validation;
 
 pub async fn process_allocator_file(file_name: &str) -> Result<AllocatorModel, LDNError> {
-    let owner = get_env_var_or_default("ALLOCATOR_GOVERNANCE_OWNER");
-    let repo = get_env_var_or_default("ALLOCATOR_GOVERNANCE_REPO");
-    let installation_id = get_env_var_or_default("GITHUB_INSTALLATION_ID")
-        .parse::<i64>()
-        .map_err(|e| LDNError::New(format!("Parse installation_id to i64 failed: {}", e)))?;
-    let branch = "main";
-    let path = file_name.to_string();
-
-    let gh = GithubWrapper::new(owner.clone(), repo.clone(), Some(installation_id))?;
-    let content_items: ContentItems = gh
-        .get_files_from_public_repo(&owner, &repo, branch, Some(&path))
-        .await
-        .map_err(|e| LDNError::Load(e.to_string()))?;
-    let mut model: AllocatorModel = content_items_to_allocator_model(content_items)
-        .map_err(|e| LDNError::Load(e.to_string()))?;
+    let mut model = fetch_allocator_file(file_name).await?;
 
     // Get multisig threshold from the blockchain if multisig address is available
     if let Ok(blockchain_threshold) =
@@ -57,6 +50,42 @@ pub async fn process_allocator_file(file_name: &str) -> Result<AllocatorModel, L
     Ok(model)
 }
 
+/// Reads an allocator JSON from the governance repository without querying the chain.
+pub async fn fetch_allocator_file(file_name: &str) -> Result<AllocatorModel, LDNError> {
+    let (gh, owner, repo) = governance_repo_github()?;
+    let content_items: ContentItems = gh
+        .get_files_from_public_repo(&owner, &repo, "main", Some(file_name))
+        .await
+        .map_github_err(|e| LDNError::Load(e.to_string()))?;
+    content_items_to_allocator_model(content_items).map_err(|e| LDNError::Load(e.to_string()))
+}
+
+/// Lists the paths of all allocator JSON files in the governance repository.
+pub async fn list_allocator_files() -> Result<Vec<String>, LDNError> {
+    let (gh, owner, repo) = governance_repo_github()?;
+    let directory = get_env_var_or_default("ALLOCATOR_GOVERNANCE_DIRECTORY");
+    let content_items: ContentItems = gh
+        .get_files_from_public_repo(&owner, &repo, "main", Some(&directory))
+        .await
+        .map_github_err(|e| LDNError::Load(e.to_string()))?;
+    Ok(content_items
+        .items
+        .into_iter()
+        .filter(|item| item.r#type == "file" && item.name.ends_with(".json"))
+        .map(|item| item.path)
+        .collect())
+}
+
+fn governance_repo_github() -> Result<(GithubWrapper, String, String), LDNError> {
+    let owner = get_env_var_or_default("ALLOCATOR_GOVERNANCE_OWNER");
+    let repo = get_env_var_or_default("ALLOCATOR_GOVERNANCE_REPO");
+    let installation_id = get_env_var_or_default("GITHUB_INSTALLATION_ID")
+        .parse::<i64>()
+        .map_err(|e| LDNError::New(format!("Parse installation_id to i64 failed: {}", e)))?;
+    let gh = GithubWrapper::new(owner.clone(), repo.clone(), Some(installation_id))?;
+    Ok((gh, owner, repo))
+}
+
 fn content_items_to_allocator_model(file: ContentItems) -> Result<AllocatorModel, LDNError> {
     let encoded_content = file
         .items
@@ -71,9 +100,18 @@ fn content_items_to_allocator_model(file: ContentItems) -> Result<AllocatorModel
 
     let cleaned_content = encoded_content.replace('\n', "");
     log::info!("Cleaned content: {:?}", cleaned_content);
-    let mut model = decode_allocator_model(&cleaned_content).ok_or(LDNError::Load(
+    let file = decode_allocator_file(&cleaned_content).ok_or(LDNError::Load(
         "Failed to parse allocator model".to_string(),
     ))?;
+    let violations = allocator_file_schema_violations(&file)?;
+    if !violations.is_empty() {
+        return Err(LDNError::Load(format!(
+            "Invalid allocator file: {}",
+            AllocatorFileValidationReport::new(violations).format_error()
+        )));
+    }
+    let mut model: AllocatorModel = serde_json::from_value(file)
+        .map_err(|e| LDNError::Load(format!("Failed to parse allocator model: {}", e)))?;
     let mut owner_repo_parts: Vec<&str> = model
         .application
         .allocation_bookkeeping
@@ -106,12 +144,15 @@ fn content_items_to_allocator_model(file: ContentItems) -> Result<AllocatorModel
 pub async fn is_allocator_repo_initialized(gh: &GithubWrapper) -> Result<bool, LDNError> {
     let repo_flag_file = "invalid.md";
     let applications_directory = "applications";
-    let all_files_result = gh.get_files(applications_directory).await.map_err(|e| {
-        LDNError::Load(format!(
-            "Failed to retrieve all files from GitHub. Reason: {}",
-            e
-        ))
-    });
+    let all_files_result = gh
+        .get_files(applications_directory)
+        .await
+        .map_github_err(|e| {
+            LDNError::Load(format!(
+                "Failed to retrieve all files from GitHub. Reason: {}",
+                e
+            ))
+        });
 
     match all_files_result {
         Ok(content_items) => {
@@ -164,7 +205,7 @@ pub async fn create_file_in_repo(
             log::info!("Creating file in target repo: {}", file_path);
             gh.add_file(&file_path, &file, "first commit", "main")
                 .await
-                .map_err(|e| {
+                .map_github_err(|e| {
                     LDNError::Load(format!(
                         "Failed to create file in GitHub repo {}/{}. Reason: {} in file {}",
                         gh.owner.clone(),
@@ -180,7 +221,7 @@ pub async fn create_file_in_repo(
         log::info!("Creating file in target repo: {}", file_path);
         gh.add_file(&file_path, &file, "first commit", "main")
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::Load(format!(
                     "Failed to create file in GitHub repo {}/{}. Reason: {} in file {}",
                     gh.owner.clone(),
@@ -206,7 +247,7 @@ pub async fn create_file_in_repo(
         let file_sha = target_file.items[0].sha.clone();
         gh.update_file(&file_path, "Update", &file, "main", &file_sha)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::Load(format!(
                     "Failed to update file in GitHub repo {}/{}. Reason: {} in file {}",
                     gh.owner.clone(),
@@ -241,7 +282,7 @@ pub async fn init_allocator_repo(gh: &GithubWrapper) -> Result<(), LDNError> {
                 Some(&dir),
             )
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::Load(format!(
                     "Failed to retrieve all files from GitHub. Reason: {}",
                     e
@@ -417,10 +458,10 @@ pub async fn update_installation_ids_logic() -> Result<(), LDNError> {
     Ok(())
 }
 
-pub async fn force_update_allocators(
-    files: Vec<String>,
+/// Allocators with an installation and a multisig, optionally limited to `affected_allocators`.
+pub async fn allocators_to_update(
     affected_allocators: Option<Vec<GithubQueryParams>>,
-) -> Result<(), LDNError> {
+) -> Result<Vec<Model>, LDNError> {
     // first get all allocators from db and filter by affected_allocators
     let allocators = get_allocators()
         .await
@@ -446,6 +487,31 @@ pub async fn force_update_allocators(
             .collect(),
         None => allocators,
     };
+    Ok(allocators)
+}
+
+/// Branch of the allocator template repository matching the environment.
+pub fn template_branch() -> &'static str {
+    match get_env_var_or_default("FILPLUS_ENV").as_str() {
+        "staging" => "staging",
+        "production" => "main",
+        _ => "main",
+    }
+}
+
+/// Sent as background GitHub work, delayed while an installation is low on budget.
+pub async fn force_update_allocators(
+    files: Vec<String>,
+    affected_allocators: Option<Vec<GithubQueryParams>>,
+) -> Result<(), LDNError> {
+    in_background(update_allocators_from_template(files, affected_allocators)).await
+}
+
+async fn update_allocators_from_template(
+    files: Vec<String>,
+    affected_allocators: Option<Vec<GithubQueryParams>>,
+) -> Result<(), LDNError> {
+    let allocators = allocators_to_update(affected_allocators).await?;
 
     //If no allocators return
     if allocators.is_empty() {
@@ -453,11 +519,7 @@ pub async fn force_update_allocators(
         return Ok(());
     }
 
-    let branch = match get_env_var_or_default("FILPLUS_ENV").as_str() {
-        "staging" => "staging",
-        "production" => "main",
-        _ => "main",
-    };
+    let branch = template_branch();
     let allocator_template_owner = get_env_var_or_default("ALLOCATOR_TEMPLATE_OWNER");
     let allocator_template_repo = get_env_var_or_default("ALLOCATOR_TEMPLATE_REPO");
 
@@ -486,7 +548,7 @@ pub async fn force_update_allocators(
                     Some(file),
                 )
                 .await
-                .map_err(|e| LDNError::Load(format!("Failed to get files: {}", e)))?;
+                .map_github_err(|e| LDNError::Load(format!("Failed to get files: {}", e)))?;
             create_file_in_repo(&gh, &content.items[0], true).await?;
         }
     }
@@ -532,58 +594,102 @@ pub fn is_valid_fixed_option(option: &str) -> bool {
     number_part.parse::<i32>().is_ok() && allowed_units.contains(&unit_part)
 }
 
-pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<(), LDNError> {
-    for file_name in files_changed {
-        log::info!("Starting allocator creation on: {}", file_name);
-        let mut model = process_allocator_file(file_name.as_str()).await?;
-
-        let mut quantity_options: Vec<String>;
-        if let Some(allocation_amount) = model.application.allocation_amount.clone() {
-            if allocation_amount.amount_type.clone().is_none()
-                || allocation_amount.quantity_options.clone().is_none()
-            {
-                return Err(LDNError::New(
-                    "Amount type and quantity options are required".to_string(),
-                ));
-            }
-
-            let amount_type = allocation_amount
-                .amount_type
-                .clone()
-                .ok_or(LDNError::Load("Failed to get amount type".to_string()))?
-                .to_lowercase(); // Assuming you still want to unwrap here
-            quantity_options = allocation_amount
-                .quantity_options
-                .ok_or(LDNError::Load("Failed to get quantity options".to_string()))?;
-
-            for option in quantity_options.iter_mut() {
-                *option = process_amount(option.clone());
-            }
-
-            validate_amount_type_and_options(&amount_type, &quantity_options)
-                .map_err(|e| LDNError::New(e.to_string()))?;
+/// Validates the allocation amount of the allocator file and normalizes its quantity options.
+pub fn normalize_allocation_amount(model: &mut AllocatorModel) -> Result<(), LDNError> {
+    let mut quantity_options: Vec<String>;
+    if let Some(allocation_amount) = model.application.allocation_amount.clone() {
+        if allocation_amount.amount_type.clone().is_none()
+            || allocation_amount.quantity_options.clone().is_none()
+        {
+            return Err(LDNError::New(
+                "Amount type and quantity options are required".to_string(),
+            ));
+        }
 
-            model
-                .application
-                .allocation_amount
-                .as_mut()
-                .ok_or(LDNError::Load(
-                    "Failed to get allocation amount".to_string(),
-                ))?
-                .quantity_options = Some(quantity_options);
+        let amount_type = allocation_amount
+            .amount_type
+            .clone()
+            .ok_or(LDNError::Load("Failed to get amount type".to_string()))?
+            .to_lowercase(); // Assuming you still want to unwrap here
+        quantity_options = allocation_amount
+            .quantity_options
+            .ok_or(LDNError::Load("Failed to get quantity options".to_string()))?;
+
+        for option in quantity_options.iter_mut() {
+            *option = process_amount(option.clone());
         }
 
-        let verifiers_gh_handles = if model.application.verifiers_gh_handles.is_empty() {
+        validate_amount_type_and_options(&amount_type, &quantity_options)
+            .map_err(|e| LDNError::New(e.to_string()))?;
+
+        model
+            .application
+            .allocation_amount
+            .as_mut()
+            .ok_or(LDNError::Load(
+                "Failed to get allocation amount".to_string(),
+            ))?
+            .quantity_options = Some(quantity_options);
+    }
+    Ok(())
+}
+
+impl AllocatorModel {
+    pub fn verifiers_gh_handles(&self) -> Option<String> {
+        if self.application.verifiers_gh_handles.is_empty() {
             None
         } else {
-            Some(model.application.verifiers_gh_handles.join(", ")) // Join verifiers in a string if exists
-        };
+            Some(self.application.verifiers_gh_handles.join(", ")) // Join verifiers in a string if exists
+        }
+    }
 
-        let tooling = if model.application.tooling.is_empty() {
+    pub fn tooling(&self) -> Option<String> {
+        if self.application.tooling.is_empty() {
             None
         } else {
-            Some(model.application.tooling.join(", "))
-        };
+            Some(self.application.tooling.join(", "))
+        }
+    }
+
+    pub fn identity_verifiers(&self) -> Result<Option<serde_json::Value>, LDNError> {
+        self.application
+            .identity_verifiers
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| LDNError::New(format!("Serialize identity verifiers failed: {}", e)))
+    }
+
+    pub fn tranche_policy(&self) -> Result<Option<serde_json::Value>, LDNError> {
+        self.application
+            .tranche_policy
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| LDNError::New(format!("Serialize tranche policy failed: {}", e)))
+    }
+
+    /// Quantity options as stored in `allocation_amounts`.
+    pub fn allocation_amounts(&self) -> Vec<String> {
+        self.application
+            .allocation_amount
+            .as_ref()
+            .and_then(|a| a.quantity_options.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|option| option.replace('%', ""))
+            .collect()
+    }
+}
+
+pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<(), LDNError> {
+    for file_name in files_changed {
+        log::info!("Starting allocator creation on: {}", file_name);
+        let mut model = process_allocator_file(file_name.as_str()).await?;
+        normalize_allocation_amount(&mut model)?;
+
+        let verifiers_gh_handles = model.verifiers_gh_handles();
+        let tooling = model.tooling();
         let owner = model.owner.clone().unwrap_or_default().to_string();
         let repo = model.repo.clone().unwrap_or_default().to_string();
         let gh = GithubWrapper::new(owner.to_string(), repo.to_string(), None)?;
@@ -599,7 +705,7 @@ pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<()
                     .try_into()
                     .expect("Installation Id sucessfully parsed to u64")
             })
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Installation Id not found for a repo: {} /// {}",
                     repo, e
@@ -621,6 +727,9 @@ pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<()
             }
         }
 
+        let identity_verifiers = model.identity_verifiers()?;
+        let tranche_policy = model.tranche_policy()?;
+
         let allocator_creation_result = create_or_update_allocator(
             owner.clone(),
             repo.clone(),
@@ -640,6 +749,9 @@ pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<()
             Some(model.application.required_replicas),
             Some(file_name.to_owned()),
             model.application.client_contract_address,
+            identity_verifiers,
+            model.application.kyc_validity_days,
+            tranche_policy,
         )
         .await
         .map_err(|e| LDNError::New(format!("Create or update allocator failed: {}", e)))?;
@@ -0,0 +1,172 @@
+use fplus_database::database::allocators::{get_allocators, update_allocator_multisig_state};
+use fplus_database::models::allocators::Model;
+use serde::{Deserialize, Serialize};
+
+use super::validation::{compare_multisig_signers, AllocatorFileViolation};
+use super::{fetch_allocator_file, governance_repo_github};
+use crate::error::LDNError;
+use crate::external_services::filecoin::{msig_get_pending, state_get_state};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MultisigSyncEntry {
+    pub owner: String,
+    pub repo: String,
+    pub multisig_address: String,
+    pub threshold: u64,
+    pub signers: Vec<String>,
+    pub pending_transactions: usize,
+    /// Differences between the on-chain signers and the governance record
+    pub signer_divergences: Vec<AllocatorFileViolation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MultisigSyncError {
+    pub owner: String,
+    pub repo: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MultisigSyncReport {
+    pub synced: Vec<MultisigSyncEntry>,
+    pub errors: Vec<MultisigSyncError>,
+}
+
+/// Reads the multisig of every allocator from the chain and stores its threshold, signers and
+/// pending transactions. Signers are compared with `pathway_addresses.signer` of the allocator
+/// file and an alert is raised when they start to diverge.
+pub async fn sync_allocator_multisigs() -> Result<MultisigSyncReport, LDNError> {
+    let allocators = get_allocators()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocators from DB: {}", e)))?;
+
+    let mut report = MultisigSyncReport::default();
+    for allocator in allocators
+        .into_iter()
+        .filter(|allocator| allocator.removed_from_registry_at.is_none())
+    {
+        let Some(multisig_address) = allocator.multisig_address.clone() else {
+            continue;
+        };
+        match sync_allocator_multisig(&allocator, &multisig_address).await {
+            Ok(entry) => report.synced.push(entry),
+            Err(e) => {
+                log::error!(
+                    "Failed to sync multisig of {}/{}: {}",
+                    allocator.owner,
+                    allocator.repo,
+                    e
+                );
+                report.errors.push(MultisigSyncError {
+                    owner: allocator.owner,
+                    repo: allocator.repo,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+async fn sync_allocator_multisig(
+    allocator: &Model,
+    multisig_address: &str,
+) -> Result<MultisigSyncEntry, LDNError> {
+    let state = state_get_state(multisig_address)
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to read multisig state of {}: {}",
+                multisig_address, e
+            ))
+        })?
+        .result
+        .state;
+    let pending_transactions = msig_get_pending(multisig_address)
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to get pending transactions of {}: {}",
+                multisig_address, e
+            ))
+        })?
+        .result
+        .unwrap_or_default();
+
+    let signer_divergences = match &allocator.registry_file_path {
+        Some(registry_file_path) => {
+            let governance_signers = fetch_allocator_file(registry_file_path)
+                .await?
+                .pathway_addresses
+                .signer;
+            compare_multisig_signers(multisig_address, &governance_signers, &state, None).await?
+        }
+        None => vec![],
+    };
+    let signers_diverged = !signer_divergences.is_empty();
+    if signers_diverged && allocator.multisig_signers_diverged_at.is_none() {
+        raise_signers_alert(allocator, multisig_address, &signer_divergences).await;
+    }
+
+    let pending_transactions_json = serde_json::to_value(&pending_transactions)
+        .map_err(|e| LDNError::New(format!("Serialize pending transactions failed: {}", e)))?;
+    update_allocator_multisig_state(
+        &allocator.owner,
+        &allocator.repo,
+        state.num_approvals_threshold as i32,
+        state.signers.clone(),
+        pending_transactions_json,
+        signers_diverged,
+    )
+    .await
+    .map_err(|e| LDNError::New(format!("Failed to update multisig state: {}", e)))?;
+
+    Ok(MultisigSyncEntry {
+        owner: allocator.owner.clone(),
+        repo: allocator.repo.clone(),
+        multisig_address: multisig_address.to_string(),
+        threshold: state.num_approvals_threshold,
+        signers: state.signers,
+        pending_transactions: pending_transactions.len(),
+        signer_divergences,
+    })
+}
+
+/// Opens an issue in the allocator governance repository. Failures are only logged so
+/// the multisig state is still stored.
+async fn raise_signers_alert(
+    allocator: &Model,
+    multisig_address: &str,
+    divergences: &[AllocatorFileViolation],
+) {
+    log::error!(
+        "Multisig signers of {}/{} diverge from the governance record",
+        allocator.owner,
+        allocator.repo
+    );
+    let title = format!(
+        "Multisig signers of {}/{} diverge from the governance record",
+        allocator.owner, allocator.repo
+    );
+    let body = format!(
+        "The signers of multisig {} do not match `pathway_addresses.signer` in `{}`:\n\n{}",
+        multisig_address,
+        allocator.registry_file_path.as_deref().unwrap_or_default(),
+        divergences
+            .iter()
+            .map(|divergence| format!("* {}", divergence.message))
+            .collect::<Vec<String>>()
+            .join("\n")
+    );
+    let result = match governance_repo_github() {
+        Ok((gh, _, _)) => gh
+            .create_issue(&title, &body)
+            .await
+            .map(|_| ())
+            .map_err(|e| LDNError::New(e.to_string())),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        log::error!("Failed to raise multisig signers alert: {}", e);
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::{HashMap, HashSet};
+
+use fplus_database::database::allocation_amounts::get_allocation_amounts;
+use fplus_database::database::allocators::{flag_allocator_removed_from_registry, get_allocators};
+use fplus_database::models::allocators::Model;
+use serde::{Deserialize, Serialize};
+
+use super::file::AllocatorModel;
+use super::{
+    create_allocator_from_file, fetch_allocator_file, list_allocator_files,
+    normalize_allocation_amount,
+};
+use crate::error::LDNError;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllocatorSyncEntry {
+    pub owner: String,
+    pub repo: String,
+    pub registry_file_path: Option<String>,
+    pub changed_fields: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllocatorSyncError {
+    pub registry_file_path: String,
+    pub error: String,
+}
+
+/// Differences between the allocator files in the governance repository and the
+/// `allocators` table. In a dry run nothing is written to the database.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct AllocatorSyncReport {
+    pub dry_run: bool,
+    pub created: Vec<AllocatorSyncEntry>,
+    pub updated: Vec<AllocatorSyncEntry>,
+    pub removed: Vec<AllocatorSyncEntry>,
+    pub unchanged: usize,
+    pub errors: Vec<AllocatorSyncError>,
+}
+
+/// Reconciles the `allocators` table with every allocator JSON in the governance repository.
+/// New and changed allocators are upserted together with their allocation amounts, and rows
+/// whose file no longer exists are flagged with `removed_from_registry_at`.
+pub async fn sync_allocator_registry(dry_run: bool) -> Result<AllocatorSyncReport, LDNError> {
+    let files = list_allocator_files().await?;
+    let db_allocators = get_allocators()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocators from DB: {}", e)))?;
+    let mut db_amounts: HashMap<i32, Vec<String>> = HashMap::new();
+    for amount in get_allocation_amounts()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocation amounts from DB: {}", e)))?
+    {
+        db_amounts
+            .entry(amount.allocator_id)
+            .or_default()
+            .push(amount.quantity_option);
+    }
+
+    let mut report = AllocatorSyncReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut registry_allocators = HashSet::new();
+    let mut failed_files = HashSet::new();
+    for file in files {
+        let mut model = match fetch_allocator_file(&file).await.and_then(|mut model| {
+            normalize_allocation_amount(&mut model)?;
+            Ok(model)
+        }) {
+            Ok(model) => model,
+            Err(e) => {
+                report.errors.push(AllocatorSyncError {
+                    registry_file_path: file.clone(),
+                    error: e.to_string(),
+                });
+                failed_files.insert(file);
+                continue;
+            }
+        };
+        let owner = model.owner.take().unwrap_or_default();
+        let repo = model.repo.take().unwrap_or_default();
+        registry_allocators.insert((owner.clone(), repo.clone()));
+
+        let db_allocator = db_allocators
+            .iter()
+            .find(|a| a.owner == owner && a.repo == repo);
+        let entry = match db_allocator {
+            Some(db_allocator) => {
+                let changed_fields = changed_fields(
+                    &model,
+                    &file,
+                    db_allocator,
+                    db_amounts
+                        .get(&db_allocator.id)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default(),
+                );
+                if changed_fields.is_empty() {
+                    report.unchanged += 1;
+                    continue;
+                }
+                AllocatorSyncEntry {
+                    owner,
+                    repo,
+                    registry_file_path: Some(file.clone()),
+                    changed_fields,
+                }
+            }
+            None => AllocatorSyncEntry {
+                owner,
+                repo,
+                registry_file_path: Some(file.clone()),
+                changed_fields: vec![],
+            },
+        };
+
+        if !dry_run {
+            if let Err(e) = create_allocator_from_file(vec![file.clone()]).await {
+                report.errors.push(AllocatorSyncError {
+                    registry_file_path: file,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        }
+        if db_allocator.is_some() {
+            report.updated.push(entry);
+        } else {
+            report.created.push(entry);
+        }
+    }
+
+    for db_allocator in db_allocators.iter().filter(|a| {
+        !registry_allocators.contains(&(a.owner.clone(), a.repo.clone()))
+            && !a
+                .registry_file_path
+                .as_ref()
+                .is_some_and(|path| failed_files.contains(path))
+    }) {
+        if !dry_run && db_allocator.removed_from_registry_at.is_none() {
+            flag_allocator_removed_from_registry(&db_allocator.owner, &db_allocator.repo)
+                .await
+                .map_err(|e| {
+                    LDNError::New(format!(
+                        "Failed to flag allocator {}/{}: {}",
+                        db_allocator.owner, db_allocator.repo, e
+                    ))
+                })?;
+        }
+        report.removed.push(AllocatorSyncEntry {
+            owner: db_allocator.owner.clone(),
+            repo: db_allocator.repo.clone(),
+            registry_file_path: db_allocator.registry_file_path.clone(),
+            changed_fields: vec![],
+        });
+    }
+
+    Ok(report)
+}
+
+/// Lists the columns `create_or_update_allocator` would change. Optional values missing
+/// from the file are not compared as the upsert keeps the stored ones.
+fn changed_fields(
+    model: &AllocatorModel,
+    registry_file_path: &str,
+    db_allocator: &Model,
+    db_amounts: &[String],
+) -> Vec<String> {
+    let mut changed = Vec::new();
+    let mut compare_if_set = |field: &str, registry: Option<String>, db: &Option<String>| {
+        if registry.is_some() && registry != *db {
+            changed.push(field.to_string());
+        }
+    };
+    compare_if_set(
+        "multisig_address",
+        Some(model.pathway_addresses.msig.clone()),
+        &db_allocator.multisig_address,
+    );
+    compare_if_set(
+        "verifiers_gh_handles",
+        model.verifiers_gh_handles(),
+        &db_allocator.verifiers_gh_handles,
+    );
+    compare_if_set("address", model.address.clone(), &db_allocator.address);
+    compare_if_set("tooling", model.tooling(), &db_allocator.tooling);
+    compare_if_set(
+        "required_sps",
+        Some(model.application.required_sps.clone()),
+        &db_allocator.required_sps,
+    );
+    compare_if_set(
+        "required_replicas",
+        Some(model.application.required_replicas.clone()),
+        &db_allocator.required_replicas,
+    );
+    compare_if_set(
+        "registry_file_path",
+        Some(registry_file_path.to_string()),
+        &db_allocator.registry_file_path,
+    );
+
+    let amount_type = model
+        .application
+        .allocation_amount
+        .as_ref()
+        .and_then(|a| a.amount_type.as_ref())
+        .map(|amount_type| amount_type.to_lowercase());
+    if amount_type != db_allocator.allocation_amount_type {
+        changed.push("allocation_amount_type".to_string());
+    }
+    if Some(&model.application.data_types) != db_allocator.data_types.as_ref() {
+        changed.push("data_types".to_string());
+    }
+    let client_contract_address = model
+        .application
+        .client_contract_address
+        .clone()
+        .filter(|address| !address.is_empty());
+    if client_contract_address != db_allocator.client_contract_address {
+        changed.push("client_contract_address".to_string());
+    }
+    if model.identity_verifiers().ok().flatten() != db_allocator.identity_verifiers {
+        changed.push("identity_verifiers".to_string());
+    }
+    if model.application.kyc_validity_days != db_allocator.kyc_validity_days {
+        changed.push("kyc_validity_days".to_string());
+    }
+    if model.tranche_policy().ok().flatten() != db_allocator.tranche_policy {
+        changed.push("tranche_policy".to_string());
+    }
+    if db_allocator.removed_from_registry_at.is_some() {
+        changed.push("removed_from_registry_at".to_string());
+    }
+
+    let mut registry_amounts = model.allocation_amounts();
+    let mut db_amounts = db_amounts.to_vec();
+    registry_amounts.sort();
+    db_amounts.sort();
+    if registry_amounts != db_amounts {
+        changed.push("allocation_amounts".to_string());
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::allocator::file::{
+        AllocationAmount, AllocatorModelPathwayAddresses, Application,
+    };
+
+    fn registry_allocator() -> AllocatorModel {
+        AllocatorModel {
+            application: Application {
+                verifiers_gh_handles: vec!["alice".to_string(), "bob".to_string()],
+                allocation_bookkeeping: "https://github.com/owner/repo".to_string(),
+                allocation_amount: Some(AllocationAmount {
+                    amount_type: Some("Fixed".to_string()),
+                    quantity_options: Some(vec!["1TiB".to_string(), "5TiB".to_string()]),
+                }),
+                tooling: vec![],
+                data_types: vec!["Public Open Dataset".to_string()],
+                required_sps: "5+".to_string(),
+                required_replicas: "4+".to_string(),
+                client_contract_address: None,
+                identity_verifiers: None,
+                kyc_validity_days: None,
+                tranche_policy: None,
+            },
+            multisig_threshold: None,
+            pathway_addresses: AllocatorModelPathwayAddresses {
+                msig: "f2msig".to_string(),
+                signer: vec![],
+            },
+            owner: Some("owner".to_string()),
+            repo: Some("repo".to_string()),
+            address: Some("f1address".to_string()),
+            client_contract_address: None,
+        }
+    }
+
+    fn db_allocator() -> Model {
+        Model {
+            id: 1,
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            installation_id: Some(1),
+            multisig_address: Some("f2msig".to_string()),
+            verifiers_gh_handles: Some("alice, bob".to_string()),
+            multisig_threshold: Some(2),
+            allocation_amount_type: Some("fixed".to_string()),
+            address: Some("f1address".to_string()),
+            tooling: None,
+            data_types: Some(vec!["Public Open Dataset".to_string()]),
+            required_sps: Some("5+".to_string()),
+            required_replicas: Some("4+".to_string()),
+            registry_file_path: Some("Allocators/1.json".to_string()),
+            client_contract_address: None,
+            identity_verifiers: None,
+            kyc_validity_days: None,
+            removed_from_registry_at: None,
+            multisig_signers: None,
+            pending_multisig_transactions: None,
+            multisig_synced_at: None,
+            multisig_signers_diverged_at: None,
+            allowance_shortfall_since: None,
+            tranche_policy: None,
+        }
+    }
+
+    #[test]
+    fn unchanged_allocator_has_no_changed_fields() {
+        let amounts = vec!["5TiB".to_string(), "1TiB".to_string()];
+        assert!(changed_fields(
+            &registry_allocator(),
+            "Allocators/1.json",
+            &db_allocator(),
+            &amounts
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn changed_allocator_lists_changed_fields() {
+        let mut model = registry_allocator();
+        model.application.required_sps = "4+".to_string();
+        model
+            .application
+            .verifiers_gh_handles
+            .push("carol".to_string());
+        let db_allocator = Model {
+            removed_from_registry_at: Some(chrono::Utc::now().into()),
+            ..db_allocator()
+        };
+        assert_eq!(
+            changed_fields(
+                &model,
+                "Allocators/1.json",
+                &db_allocator,
+                &["1TiB".to_string()]
+            ),
+            vec![
+                "verifiers_gh_handles",
+                "required_sps",
+                "removed_from_registry_at",
+                "allocation_amounts"
+            ]
+        );
+    }
+}
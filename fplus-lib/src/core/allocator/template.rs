@@ -0,0 +1,403 @@
+use std::collections::HashSet;
+
+use fplus_database::models::allocators::Model;
+use octocrab::{Error as OctocrabError, GitHubError};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+
+use super::{allocators_to_update, template_branch};
+use crate::config::get_env_var_or_default;
+use crate::core::GithubQueryParams;
+use crate::error::LDNError;
+use crate::external_services::github::{GithubResultExt, GithubWrapper};
+
+/// Prefix of the branches opened by staged template rollouts.
+pub const TEMPLATE_UPDATE_BRANCH_PREFIX: &str = "filplus-template-update-";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateFileStatus {
+    UpToDate,
+    /// The file does not exist in the allocator repository
+    Missing,
+    /// The file matches a previous version of the template
+    Outdated,
+    /// The file matches no version of the template
+    LocallyModified,
+    /// The file is listed in `.filplusignore`
+    Ignored,
+}
+
+impl TemplateFileStatus {
+    pub fn needs_update(&self) -> bool {
+        matches!(self, Self::Missing | Self::Outdated | Self::LocallyModified)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemplateFileDrift {
+    pub path: String,
+    pub status: TemplateFileStatus,
+    pub template_sha: String,
+    pub allocator_sha: Option<String>,
+    /// Unified diff from the allocator file to the template file
+    pub diff: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllocatorTemplateDrift {
+    pub owner: String,
+    pub repo: String,
+    pub files: Vec<TemplateFileDrift>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StagedTemplateUpdate {
+    pub owner: String,
+    pub repo: String,
+    pub files: Vec<String>,
+    pub pull_request: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Current content of a template file and the blob SHAs of its previous versions.
+pub struct TemplateFile {
+    pub path: String,
+    pub sha: String,
+    pub content: String,
+    pub previous_shas: HashSet<String>,
+}
+
+impl TemplateFile {
+    pub fn status(&self, allocator_sha: Option<&str>) -> TemplateFileStatus {
+        match allocator_sha {
+            None => TemplateFileStatus::Missing,
+            Some(sha) if sha == self.sha => TemplateFileStatus::UpToDate,
+            Some(sha) if self.previous_shas.contains(sha) => TemplateFileStatus::Outdated,
+            Some(_) => TemplateFileStatus::LocallyModified,
+        }
+    }
+}
+
+pub fn content_diff(path: &str, allocator_content: &str, template_content: &str) -> String {
+    TextDiff::from_lines(allocator_content, template_content)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+async fn load_template_files(
+    gh: &GithubWrapper,
+    files: &[String],
+) -> Result<Vec<TemplateFile>, LDNError> {
+    let owner = get_env_var_or_default("ALLOCATOR_TEMPLATE_OWNER");
+    let repo = get_env_var_or_default("ALLOCATOR_TEMPLATE_REPO");
+    let branch = template_branch();
+    let history_depth = get_env_var_or_default("ALLOCATOR_TEMPLATE_HISTORY_DEPTH")
+        .parse::<u8>()
+        .map_err(|e| {
+            LDNError::New(format!(
+                "Parse ALLOCATOR_TEMPLATE_HISTORY_DEPTH to u8 failed: {}",
+                e
+            ))
+        })?;
+
+    let mut template_files = Vec::new();
+    for file in files {
+        let content = gh
+            .get_files_from_public_repo(&owner, &repo, branch, Some(file))
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to get template file {}: {}", file, e))
+            })?
+            .take_items()
+            .pop()
+            .ok_or(LDNError::Load(format!("Template file {} not found", file)))?;
+
+        let commits = gh
+            .list_file_commits(&owner, &repo, branch, file, history_depth)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to get history of template file {}: {}",
+                    file, e
+                ))
+            })?;
+        let mut previous_shas = HashSet::new();
+        for commit in commits {
+            // The file may not exist under this path in every commit that touched it
+            if let Ok(mut items) = gh
+                .get_files_from_public_repo(&owner, &repo, &commit.sha, Some(file))
+                .await
+            {
+                if let Some(previous) = items.take_items().pop() {
+                    previous_shas.insert(previous.sha);
+                }
+            }
+        }
+
+        template_files.push(TemplateFile {
+            path: file.clone(),
+            sha: content.sha.clone(),
+            content: content.decoded_content().unwrap_or_default(),
+            previous_shas,
+        });
+    }
+    Ok(template_files)
+}
+
+async fn allocator_file_drift(
+    gh: &GithubWrapper,
+    template_files: &[TemplateFile],
+) -> Result<Vec<TemplateFileDrift>, LDNError> {
+    let ignored_files = gh.filplus_ignored_files(template_branch()).await?;
+    let mut drift = Vec::new();
+    for template in template_files {
+        if ignored_files.contains(&template.path) {
+            drift.push(TemplateFileDrift {
+                path: template.path.clone(),
+                status: TemplateFileStatus::Ignored,
+                template_sha: template.sha.clone(),
+                allocator_sha: None,
+                diff: None,
+            });
+            continue;
+        }
+
+        let allocator_file = gh
+            .get_file(&template.path, "main")
+            .await
+            .map(|mut items| items.take_items().pop())
+            .or_else(|e| match e {
+                OctocrabError::GitHub {
+                    source: GitHubError { message, .. },
+                    ..
+                } if message == "Not Found" => Ok(None),
+                _ => Err(e),
+            })
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to get {} from {}/{}: {}",
+                    template.path, gh.owner, gh.repo, e
+                ))
+            })?;
+        let allocator_sha = allocator_file.as_ref().map(|file| file.sha.clone());
+        let status = template.status(allocator_sha.as_deref());
+        let diff = status.needs_update().then(|| {
+            let allocator_content = allocator_file
+                .as_ref()
+                .and_then(|file| file.decoded_content())
+                .unwrap_or_default();
+            content_diff(&template.path, &allocator_content, &template.content)
+        });
+        drift.push(TemplateFileDrift {
+            path: template.path.clone(),
+            status,
+            template_sha: template.sha.clone(),
+            allocator_sha,
+            diff,
+        });
+    }
+    Ok(drift)
+}
+
+fn allocator_github(allocator: &Model) -> Result<GithubWrapper, LDNError> {
+    GithubWrapper::new(
+        allocator.owner.clone(),
+        allocator.repo.clone(),
+        allocator.installation_id,
+    )
+}
+
+/// Read-only report of how the given template files differ in each allocator repository.
+pub async fn get_template_drift(
+    files: Vec<String>,
+    affected_allocators: Option<Vec<GithubQueryParams>>,
+) -> Result<Vec<AllocatorTemplateDrift>, LDNError> {
+    let allocators = allocators_to_update(affected_allocators).await?;
+    let Some(first) = allocators.first() else {
+        return Ok(vec![]);
+    };
+    let template_files = load_template_files(&allocator_github(first)?, &files).await?;
+
+    let mut report = Vec::new();
+    for allocator in allocators {
+        let drift = match allocator_github(&allocator) {
+            Ok(gh) => allocator_file_drift(&gh, &template_files).await,
+            Err(e) => Err(e),
+        };
+        let (files, error) = match drift {
+            Ok(files) => (files, None),
+            Err(e) => (vec![], Some(e.to_string())),
+        };
+        report.push(AllocatorTemplateDrift {
+            owner: allocator.owner,
+            repo: allocator.repo,
+            files,
+            error,
+        });
+    }
+    Ok(report)
+}
+
+/// Staged alternative to `force_update_allocators`: instead of committing to the default
+/// branch, opens one pull request per allocator with the template files that drifted.
+/// Allocators that already have an open template update pull request are skipped.
+pub async fn stage_template_update(
+    files: Vec<String>,
+    affected_allocators: Option<Vec<GithubQueryParams>>,
+) -> Result<Vec<StagedTemplateUpdate>, LDNError> {
+    let allocators = allocators_to_update(affected_allocators).await?;
+    let Some(first) = allocators.first() else {
+        log::info!("No allocators to update");
+        return Ok(vec![]);
+    };
+    let template_files = load_template_files(&allocator_github(first)?, &files).await?;
+
+    let mut updates = Vec::new();
+    for allocator in allocators {
+        let mut update = StagedTemplateUpdate {
+            owner: allocator.owner.clone(),
+            repo: allocator.repo.clone(),
+            files: vec![],
+            pull_request: None,
+            error: None,
+        };
+        match open_template_update_pr(&allocator, &template_files).await {
+            Ok((files, pull_request)) => {
+                update.files = files;
+                update.pull_request = pull_request;
+            }
+            Err(e) => update.error = Some(e.to_string()),
+        }
+        updates.push(update);
+    }
+    Ok(updates)
+}
+
+async fn open_template_update_pr(
+    allocator: &Model,
+    template_files: &[TemplateFile],
+) -> Result<(Vec<String>, Option<String>), LDNError> {
+    let gh = allocator_github(allocator)?;
+    let open_pr = gh
+        .list_pull_requests()
+        .await
+        .map_github_err(|e| LDNError::Load(format!("Failed to list pull requests: {}", e)))?
+        .into_iter()
+        .find(|pr| pr.head.ref_field.starts_with(TEMPLATE_UPDATE_BRANCH_PREFIX));
+    if let Some(pr) = open_pr {
+        log::info!(
+            "Template update pull request already open in {}/{}",
+            gh.owner,
+            gh.repo
+        );
+        return Ok((vec![], pr.html_url.map(|url| url.to_string())));
+    }
+
+    let drift: Vec<TemplateFileDrift> = allocator_file_drift(&gh, template_files)
+        .await?
+        .into_iter()
+        .filter(|file| file.status.needs_update())
+        .collect();
+    if drift.is_empty() {
+        return Ok((vec![], None));
+    }
+
+    let branch_name = format!(
+        "{}{}",
+        TEMPLATE_UPDATE_BRANCH_PREFIX,
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    );
+    let head_hash = gh.get_main_branch_sha().await?;
+    let create_ref_request = gh
+        .build_create_ref_request(branch_name.clone(), head_hash)
+        .map_err(|e| LDNError::New(format!("Failed to build create branch request: {}", e)))?;
+    gh.create_branch(create_ref_request)
+        .await
+        .map_github_err(|e| {
+            LDNError::New(format!("Failed to create branch {}: {}", branch_name, e))
+        })?;
+
+    for file in &drift {
+        let template = template_files
+            .iter()
+            .find(|template| template.path == file.path)
+            .ok_or(LDNError::Load(format!(
+                "Template file {} not found",
+                file.path
+            )))?;
+        let message = format!("Update {} from allocator template", file.path);
+        match &file.allocator_sha {
+            Some(sha) => gh
+                .update_file(&file.path, &message, &template.content, &branch_name, sha)
+                .await
+                .map(|_| ()),
+            None => gh
+                .add_file(&file.path, &template.content, &message, &branch_name)
+                .await
+                .map(|_| ()),
+        }
+        .map_github_err(|e| LDNError::New(format!("Failed to update {}: {}", file.path, e)))?;
+    }
+
+    let body = format!(
+        "Updates the following files from the allocator template:\n\n{}",
+        drift
+            .iter()
+            .map(|file| format!("* `{}` ({:?})", file.path, file.status))
+            .collect::<Vec<String>>()
+            .join("\n")
+    );
+    let pr = gh
+        .create_pull_request("Update allocator template files", &branch_name, body)
+        .await
+        .map_github_err(|e| LDNError::New(format!("Failed to create pull request: {}", e)))?;
+    Ok((
+        drift.into_iter().map(|file| file.path).collect(),
+        pr.html_url.map(|url| url.to_string()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template_file() -> TemplateFile {
+        TemplateFile {
+            path: ".github/workflows/ci.yml".to_string(),
+            sha: "new".to_string(),
+            content: "name: CI\non: push\n".to_string(),
+            previous_shas: ["old".to_string(), "new".to_string()].into(),
+        }
+    }
+
+    #[test]
+    fn template_file_status() {
+        let template = template_file();
+        assert_eq!(template.status(None), TemplateFileStatus::Missing);
+        assert_eq!(template.status(Some("new")), TemplateFileStatus::UpToDate);
+        assert_eq!(template.status(Some("old")), TemplateFileStatus::Outdated);
+        assert_eq!(
+            template.status(Some("edited")),
+            TemplateFileStatus::LocallyModified
+        );
+        assert!(!TemplateFileStatus::Ignored.needs_update());
+    }
+
+    #[test]
+    fn content_diff_is_unified() {
+        let template = template_file();
+        let diff = content_diff(
+            &template.path,
+            "name: CI\non: pull_request\n",
+            &template.content,
+        );
+        assert!(
+            diff.starts_with("--- a/.github/workflows/ci.yml\n+++ b/.github/workflows/ci.yml\n")
+        );
+        assert!(diff.contains("-on: pull_request\n+on: push\n"));
+    }
+}
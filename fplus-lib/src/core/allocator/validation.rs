@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+
+use jsonschema::JSONSchema;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{is_valid_fixed_option, validate_percentage_amount_options};
+use crate::error::LDNError;
+use crate::external_services::filecoin::{
+    get_builtin_actor_code, state_lookup_id, state_read_actor_state,
+};
+use crate::helpers::process_amount;
+use crate::models::filecoin::MultisigState;
+
+/// JSON schema of the allocator files in the allocator governance repository.
+pub const ALLOCATOR_FILE_SCHEMA: &str = include_str!("allocator.schema.json");
+
+static COMPILED_SCHEMA: OnceCell<JSONSchema> = OnceCell::new();
+
+/// Problem found in an allocator file. `pointer` is the JSON pointer of the offending value.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllocatorFileViolation {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl AllocatorFileViolation {
+    fn new(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllocatorFileValidationReport {
+    pub valid: bool,
+    pub violations: Vec<AllocatorFileViolation>,
+}
+
+impl AllocatorFileValidationReport {
+    pub fn new(violations: Vec<AllocatorFileViolation>) -> Self {
+        Self {
+            valid: violations.is_empty(),
+            violations,
+        }
+    }
+
+    pub fn format_error(&self) -> String {
+        self.violations
+            .iter()
+            .map(|violation| format!("{}: {}", violation.pointer, violation.message))
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+}
+
+pub fn allocator_file_schema() -> Result<&'static JSONSchema, LDNError> {
+    COMPILED_SCHEMA.get_or_try_init(|| {
+        let schema: Value = serde_json::from_str(ALLOCATOR_FILE_SCHEMA)
+            .map_err(|e| LDNError::Load(format!("Failed to parse allocator schema: {}", e)))?;
+        JSONSchema::compile(&schema)
+            .map_err(|e| LDNError::Load(format!("Failed to compile allocator schema: {}", e)))
+    })
+}
+
+/// Validates an allocator file against the schema only, which accepts every file the
+/// allocator model can be read from.
+pub fn allocator_file_schema_violations(
+    file: &Value,
+) -> Result<Vec<AllocatorFileViolation>, LDNError> {
+    Ok(match allocator_file_schema()?.validate(file) {
+        Ok(()) => vec![],
+        Err(errors) => errors
+            .map(|error| {
+                AllocatorFileViolation::new(error.instance_path.to_string(), error.to_string())
+            })
+            .collect(),
+    })
+}
+
+/// Validates an allocator file against the schema and the allocation amount rules
+/// applied when the allocator is created.
+pub fn validate_allocator_file_schema(
+    file: &Value,
+) -> Result<Vec<AllocatorFileViolation>, LDNError> {
+    let mut violations = allocator_file_schema_violations(file)?;
+    violations.extend(allocation_amount_violations(file));
+    Ok(violations)
+}
+
+fn allocation_amount_violations(file: &Value) -> Vec<AllocatorFileViolation> {
+    let pointer = "/application/allocation_amount";
+    let Some(amount_type) = file
+        .pointer(&format!("{}/amount_type", pointer))
+        .and_then(Value::as_str)
+    else {
+        return vec![];
+    };
+    let Some(options) = file
+        .pointer(&format!("{}/quantity_options", pointer))
+        .and_then(Value::as_array)
+    else {
+        return vec![];
+    };
+    options
+        .iter()
+        .enumerate()
+        .filter_map(|(index, option)| {
+            let option = process_amount(option.as_str()?.to_string());
+            let valid = match amount_type.to_lowercase().as_str() {
+                "fixed" => is_valid_fixed_option(&option),
+                "percentage" => {
+                    validate_percentage_amount_options(std::slice::from_ref(&option)).is_ok()
+                }
+                _ => return None,
+            };
+            (!valid).then(|| {
+                AllocatorFileViolation::new(
+                    format!("{}/quantity_options/{}", pointer, index),
+                    format!("{} is not a valid {} amount option", option, amount_type),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Checks that `msig` is a multisig actor whose signers are exactly the given addresses and,
+/// when set, whose approval threshold matches `threshold`.
+pub async fn validate_allocator_multisig(
+    msig: &str,
+    signers: &[String],
+    threshold: Option<i64>,
+) -> Result<Vec<AllocatorFileViolation>, LDNError> {
+    let actor = state_read_actor_state(msig)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to read state of {}: {}", msig, e)))?;
+    let not_found = || {
+        Ok(vec![AllocatorFileViolation::new(
+            "/pathway_addresses/msig",
+            format!("{} not found on chain", msig),
+        )])
+    };
+    match actor.error {
+        Some(error) if error.is_actor_not_found() => return not_found(),
+        Some(error) => {
+            return Err(LDNError::Load(format!(
+                "Node failed to read state of {}: {}",
+                msig, error
+            )))
+        }
+        None => {}
+    }
+    let Some(actor) = actor.result else {
+        return not_found();
+    };
+    if actor.code.code != get_builtin_actor_code("multisig").await? {
+        return Ok(vec![AllocatorFileViolation::new(
+            "/pathway_addresses/msig",
+            format!("{} is not a multisig actor", msig),
+        )]);
+    }
+    let state = serde_json::from_value::<MultisigState>(actor.state).map_err(|e| {
+        LDNError::Load(format!("Failed to parse multisig state of {}: {}", msig, e))
+    })?;
+
+    compare_multisig_signers(msig, signers, &state, threshold).await
+}
+
+/// Compares the signers and threshold of a governance record with the multisig state read
+/// from the chain. Signers are resolved to ID addresses as the multisig state stores them.
+pub async fn compare_multisig_signers(
+    msig: &str,
+    signers: &[String],
+    state: &MultisigState,
+    threshold: Option<i64>,
+) -> Result<Vec<AllocatorFileViolation>, LDNError> {
+    let mut signer_ids = Vec::new();
+    for signer in signers {
+        let id = state_lookup_id(signer)
+            .await
+            .map_err(|e| {
+                LDNError::Load(format!("Failed to look up actor ID of {}: {}", signer, e))
+            })?
+            .result;
+        signer_ids.push(id);
+    }
+    Ok(multisig_violations(
+        msig,
+        signers,
+        &signer_ids,
+        state,
+        threshold,
+    ))
+}
+
+fn multisig_violations(
+    msig: &str,
+    signers: &[String],
+    signer_ids: &[Option<String>],
+    state: &MultisigState,
+    threshold: Option<i64>,
+) -> Vec<AllocatorFileViolation> {
+    let mut violations = Vec::new();
+    let on_chain: HashSet<&str> = state.signers.iter().map(String::as_str).collect();
+    for (index, (signer, id)) in signers.iter().zip(signer_ids).enumerate() {
+        let pointer = format!("/pathway_addresses/signer/{}", index);
+        match id {
+            None => violations.push(AllocatorFileViolation::new(
+                pointer,
+                format!("{} not found on chain", signer),
+            )),
+            Some(id) if !on_chain.contains(id.as_str()) => {
+                violations.push(AllocatorFileViolation::new(
+                    pointer,
+                    format!("{} ({}) is not a signer of {}", signer, id, msig),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+    let listed: HashSet<&str> = signer_ids.iter().flatten().map(String::as_str).collect();
+    for missing in state
+        .signers
+        .iter()
+        .filter(|id| !listed.contains(id.as_str()))
+    {
+        violations.push(AllocatorFileViolation::new(
+            "/pathway_addresses/signer",
+            format!("{} is a signer of {} but is not listed", missing, msig),
+        ));
+    }
+    if let Some(threshold) = threshold {
+        if threshold as u64 != state.num_approvals_threshold {
+            violations.push(AllocatorFileViolation::new(
+                "/multisig_threshold",
+                format!(
+                    "Threshold is {} but {} requires {} approvals",
+                    threshold, msig, state.num_approvals_threshold
+                ),
+            ));
+        }
+    }
+    violations
+}
+
+/// Validates an allocator file against the schema and, if `check_chain` is set and the
+/// pathway addresses are well formed, against the state of its multisig.
+pub async fn validate_allocator_file(
+    file: &Value,
+    check_chain: bool,
+) -> Result<AllocatorFileValidationReport, LDNError> {
+    let mut violations = validate_allocator_file_schema(file)?;
+    let addresses_valid = !violations.iter().any(|violation| {
+        violation.pointer.starts_with("/pathway_addresses")
+            || violation.pointer.starts_with("/multisig_threshold")
+            || violation.pointer.is_empty()
+    });
+    if check_chain && addresses_valid {
+        let msig = file
+            .pointer("/pathway_addresses/msig")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let signers: Vec<String> = file
+            .pointer("/pathway_addresses/signer")
+            .and_then(Value::as_array)
+            .map(|signers| {
+                signers
+                    .iter()
+                    .filter_map(|signer| signer.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let threshold = file.get("multisig_threshold").and_then(Value::as_i64);
+        violations.extend(validate_allocator_multisig(msig, &signers, threshold).await?);
+    }
+    Ok(AllocatorFileValidationReport::new(violations))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::filecoin::Code;
+
+    fn allocator_file() -> Value {
+        json!({
+            "application": {
+                "github_handles": ["alice"],
+                "allocation_bookkeeping": "https://github.com/owner/repo",
+                "allocation_amount": {
+                    "amount_type": "Fixed",
+                    "quantity_options": ["1TiB", "5TiBs"]
+                },
+                "tooling": [],
+                "data_types": ["Public Open Dataset"],
+                "required_sps": "5+",
+                "required_replicas": "4+"
+            },
+            "pathway_addresses": {
+                "msig": "f2abcdef",
+                "signer": ["f1alice", "f1bob"]
+            }
+        })
+    }
+
+    #[test]
+    fn valid_file_has_no_violations() {
+        assert_eq!(
+            validate_allocator_file_schema(&allocator_file()).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn files_accepted_before_the_schema_stay_valid() {
+        let mut file = allocator_file();
+        file["application"]["github_handles"] = json!([]);
+        file["application"]["allocation_bookkeeping"] = json!("see the allocator repo");
+        file["application"]["allocation_amount"] = json!({ "amount_type": null });
+        file["application"]["required_sps"] = json!("at least 5 in different regions");
+        file["application"]["required_replicas"] = json!("");
+        file["pathway_addresses"]["signer"] = json!(["F1ALICE", "F1ALICE"]);
+        assert_eq!(validate_allocator_file_schema(&file).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn violations_are_reported_by_pointer() {
+        let mut file = allocator_file();
+        file["application"]["allocation_amount"]["quantity_options"][1] = json!("5XB");
+        file["application"]["required_sps"] = json!(5);
+        file["pathway_addresses"]["signer"] = json!([1]);
+        file["application"]
+            .as_object_mut()
+            .unwrap()
+            .remove("github_handles");
+        let pointers: Vec<String> = validate_allocator_file_schema(&file)
+            .unwrap()
+            .into_iter()
+            .map(|violation| violation.pointer)
+            .collect();
+        for pointer in [
+            "/application",
+            "/application/required_sps",
+            "/pathway_addresses/signer/0",
+            "/application/allocation_amount/quantity_options/1",
+        ] {
+            assert!(pointers.contains(&pointer.to_string()), "{:?}", pointers);
+        }
+    }
+
+    #[test]
+    fn signers_are_compared_with_chain_state() {
+        let state = MultisigState {
+            signers: vec!["f01".to_string(), "f03".to_string()],
+            num_approvals_threshold: 2,
+            next_txn_id: 0,
+            initial_balance: "0".to_string(),
+            start_epoch: 0,
+            unlock_duration: 0,
+            pending_txns: Code {
+                code: "bafy".to_string(),
+            },
+        };
+        let violations = multisig_violations(
+            "f2abcdef",
+            &["f1alice".to_string(), "f1bob".to_string()],
+            &[Some("f01".to_string()), Some("f02".to_string())],
+            &state,
+            Some(1),
+        );
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            vec![
+                "/pathway_addresses/signer/1",
+                "/pathway_addresses/signer",
+                "/multisig_threshold"
+            ]
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::file::ApplicationFile;
+use crate::error::LDNError;
+use crate::external_services::dmob::get_client_verified_deals;
+use crate::external_services::filecoin::state_lookup_id;
+use crate::models::dmob::VerifiedDeal;
+
+/// Fil+ guideline: data owners engage at least 4 SPs
+pub const MIN_PROVIDERS: usize = 4;
+/// Fil+ guideline: no single SP receives more than 30% of the client's DataCap
+pub const MAX_PROVIDER_SHARE: f64 = 0.3;
+
+/// DataCap claimed by a single SP for one piece of the client's data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimedData {
+    pub provider: String,
+    pub piece_cid: String,
+    pub size: u64,
+}
+
+impl From<VerifiedDeal> for ClaimedData {
+    fn from(deal: VerifiedDeal) -> Self {
+        Self {
+            provider: format!("f0{}", deal.provider_id),
+            piece_cid: deal.piece_cid,
+            size: deal.piece_size,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProviderShare {
+    pub provider: String,
+    pub bytes: u64,
+    pub share: f64,
+    pub location: Option<String>,
+    pub allowed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComplianceReport {
+    pub client_address: String,
+    pub client_id: String,
+    pub created_at: DateTime<Utc>,
+    pub total_bytes: u64,
+    pub providers: Vec<ProviderShare>,
+    pub declared_replicas: u8,
+    pub average_replicas: f64,
+    pub locations: Vec<String>,
+    pub violations: Vec<String>,
+}
+
+impl ComplianceReport {
+    pub fn is_compliant(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn format_comment(&self) -> String {
+        let status = if self.is_compliant() {
+            "The client follows the Fil+ guideline.".to_string()
+        } else {
+            format!(
+                "The client does not follow the Fil+ guideline:\n\n{}",
+                self.violations
+                    .iter()
+                    .map(|violation| format!("* {}", violation))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            )
+        };
+        let providers = self
+            .providers
+            .iter()
+            .map(|provider| {
+                format!(
+                    "| {} | {} | {:.2}% | {} | {} |",
+                    provider.provider,
+                    provider.bytes,
+                    provider.share * 100.0,
+                    provider.location.as_deref().unwrap_or("-"),
+                    if provider.allowed { "yes" } else { "no" }
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        format!(
+            "## Compliance Report\n\n{}\n\nAverage replicas: {:.2} (declared {})\n\n| SP | Bytes | Share | Location | Allowed |\n|---|---|---|---|---|\n{}",
+            status, self.average_replicas, self.declared_replicas, providers
+        )
+    }
+}
+
+/// Builds the compliance report of the client from all of its verified deals and claims,
+/// attributed to the SP that received the DataCap whether it is listed in the application
+/// or not.
+pub async fn get_compliance_report(
+    application: &ApplicationFile,
+) -> Result<ComplianceReport, LDNError> {
+    let client_address = &application.lifecycle.client_on_chain_address;
+    let client_id = state_lookup_id(client_address)
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to look up actor ID of {}: {}",
+                client_address, e
+            ))
+        })?
+        .result
+        .ok_or(LDNError::Load(format!(
+            "Client address {} not found on chain",
+            client_address
+        )))?;
+
+    let claims: Vec<ClaimedData> = get_client_verified_deals(&client_id)
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to get verified deals of {}: {}",
+                client_id, e
+            ))
+        })?
+        .into_iter()
+        .map(ClaimedData::from)
+        .collect();
+    Ok(build_compliance_report(application, &client_id, &claims))
+}
+
+pub fn build_compliance_report(
+    application: &ApplicationFile,
+    client_id: &str,
+    claims: &[ClaimedData],
+) -> ComplianceReport {
+    let allowed: HashSet<String> = application
+        .allowed_storage_provider_ids()
+        .into_iter()
+        .map(|id| format!("f0{}", id))
+        .collect();
+    let declared_locations: HashMap<String, String> = application
+        .providers()
+        .into_iter()
+        .filter(|provider| !provider.location.is_empty())
+        .map(|provider| (provider.id, provider.location))
+        .collect();
+
+    let mut bytes_by_provider: HashMap<&str, u64> = HashMap::new();
+    let mut providers_by_piece: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for claim in claims {
+        *bytes_by_provider.entry(&claim.provider).or_default() += claim.size;
+        providers_by_piece
+            .entry(&claim.piece_cid)
+            .or_default()
+            .insert(&claim.provider);
+    }
+    let total_bytes: u64 = bytes_by_provider.values().sum();
+
+    let mut providers: Vec<ProviderShare> = bytes_by_provider
+        .into_iter()
+        .map(|(provider, bytes)| ProviderShare {
+            provider: provider.to_string(),
+            bytes,
+            share: bytes as f64 / total_bytes as f64,
+            location: declared_locations.get(provider).cloned(),
+            allowed: allowed.contains(provider),
+        })
+        .collect();
+    providers.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.provider.cmp(&b.provider)));
+
+    let average_replicas = if providers_by_piece.is_empty() {
+        0.0
+    } else {
+        providers_by_piece
+            .values()
+            .map(|providers| providers.len())
+            .sum::<usize>() as f64
+            / providers_by_piece.len() as f64
+    };
+
+    let locations: BTreeSet<String> = providers
+        .iter()
+        .filter_map(|provider| provider.location.clone())
+        .collect();
+    let allowed_locations: BTreeSet<&String> = allowed
+        .iter()
+        .filter_map(|provider| declared_locations.get(provider))
+        .collect();
+
+    let mut violations = Vec::new();
+    if providers.is_empty() {
+        violations.push("No DataCap claims found for the client".to_string());
+    } else {
+        if providers.len() < MIN_PROVIDERS {
+            violations.push(format!(
+                "Data is stored with {} storage providers, at least {} are required",
+                providers.len(),
+                MIN_PROVIDERS
+            ));
+        }
+        for provider in providers
+            .iter()
+            .filter(|provider| provider.share > MAX_PROVIDER_SHARE)
+        {
+            violations.push(format!(
+                "{} received {:.2}% of the DataCap, the maximum is {:.0}%",
+                provider.provider,
+                provider.share * 100.0,
+                MAX_PROVIDER_SHARE * 100.0
+            ));
+        }
+        if average_replicas < application.datacap.replicas as f64 {
+            violations.push(format!(
+                "Data is stored with {:.2} replicas on average, {} were declared",
+                average_replicas, application.datacap.replicas
+            ));
+        }
+        let unlisted: Vec<&str> = providers
+            .iter()
+            .filter(|provider| !provider.allowed)
+            .map(|provider| provider.provider.as_str())
+            .collect();
+        if !unlisted.is_empty() {
+            violations.push(format!(
+                "Data is stored with storage providers that are not allowed: {}",
+                unlisted.join(", ")
+            ));
+        }
+        if locations.len() == 1 && allowed_locations.len() > 1 {
+            violations.push(format!(
+                "All data is stored in {} while the allowed storage providers span {} locations",
+                locations.iter().next().cloned().unwrap_or_default(),
+                allowed_locations.len()
+            ));
+        }
+    }
+
+    ComplianceReport {
+        client_address: application.lifecycle.client_on_chain_address.clone(),
+        client_id: client_id.to_string(),
+        created_at: Utc::now(),
+        total_bytes,
+        providers,
+        declared_replicas: application.datacap.replicas,
+        average_replicas,
+        locations: locations.into_iter().collect(),
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::{
+        Client, Datacap, Project, SpsChangeRequest, SpsChangeRequests,
+        StorageProviderChangeVerifiers, Version,
+    };
+
+    async fn application(providers: &str, replicas: u8) -> ApplicationFile {
+        ApplicationFile::new(
+            "1".to_string(),
+            "f2multisig".to_string(),
+            Version::Number(1),
+            "f1client".to_string(),
+            Client::default(),
+            Project {
+                providers: providers.to_string(),
+                ..Default::default()
+            },
+            Datacap {
+                replicas,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    fn claims(provider: &str, pieces: &[&str]) -> Vec<ClaimedData> {
+        pieces
+            .iter()
+            .map(|piece| ClaimedData {
+                provider: provider.to_string(),
+                piece_cid: piece.to_string(),
+                size: 100,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn compliant_distribution() {
+        let app = application(
+            "| SP | Location | Org |\n|---|---|---|\n| f01 | Germany | A |\n| f02 | Japan | B |\n| f03 | USA | C |\n| f04 | Brazil | D |",
+            2,
+        )
+        .await;
+        let mut data = Vec::new();
+        data.extend(claims("f01", &["a", "b"]));
+        data.extend(claims("f02", &["a", "b"]));
+        data.extend(claims("f03", &["c", "d"]));
+        data.extend(claims("f04", &["c", "d"]));
+        let report = build_compliance_report(&app, "f0100", &data);
+        assert!(report.is_compliant(), "{:?}", report.violations);
+        assert_eq!(report.total_bytes, 800);
+        assert_eq!(report.average_replicas, 2.0);
+        assert_eq!(report.locations.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn guideline_violations_are_reported() {
+        let mut app = application("f01, Germany\nf02, Japan\nf03, USA\nf04, Brazil", 3).await;
+        app.allowed_sps = Some(SpsChangeRequests(vec![SpsChangeRequest {
+            id: "1".to_string(),
+            created_at: "".to_string(),
+            updated_at: "".to_string(),
+            is_active: false,
+            allowed_sps: Some(vec![1, 2, 3]),
+            max_deviation: None,
+            signers: StorageProviderChangeVerifiers(vec![]),
+        }]));
+        let mut data = Vec::new();
+        data.extend(claims("f01", &["a", "b", "c"]));
+        data.extend(claims("f04", &["a"]));
+        let report = build_compliance_report(&app, "f0100", &data);
+        assert_eq!(report.violations.len(), 4, "{:?}", report.violations);
+        assert_eq!(report.providers[0].share, 0.75);
+        assert!(!report.providers[1].allowed);
+        assert!(report
+            .format_comment()
+            .contains("| f01 | 300 | 75.00% | Germany | yes |"));
+
+        let report = build_compliance_report(&app, "f0100", &[]);
+        assert_eq!(
+            report.violations,
+            vec!["No DataCap claims found for the client"]
+        );
+    }
+
+    #[tokio::test]
+    async fn deals_with_unlisted_providers_are_counted() {
+        let app = application("f01, Germany\nf02, Japan\nf03, USA\nf04, Brazil", 1).await;
+        let deals: crate::models::dmob::VerifiedDealsResponse = serde_json::from_str(
+            r#"{"count": "3", "data": [
+                {"providerId": "1", "pieceCid": "a", "pieceSize": "100"},
+                {"providerId": 9, "pieceCid": "b", "pieceSize": 300},
+                {"providerId": "f09", "pieceCid": "c", "pieceSize": "600"}
+            ]}"#,
+        )
+        .unwrap();
+        let data: Vec<ClaimedData> = deals.data.into_iter().map(ClaimedData::from).collect();
+        let report = build_compliance_report(&app, "f0100", &data);
+        assert_eq!(report.total_bytes, 1000);
+        assert_eq!(report.providers[0].provider, "f09");
+        assert_eq!(report.providers[0].share, 0.9);
+        assert!(!report.providers[0].allowed);
+        assert!(report.violations.contains(
+            &"Data is stored with storage providers that are not allowed: f09".to_string()
+        ));
+    }
+}
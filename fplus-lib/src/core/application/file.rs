@@ -1,10 +1,11 @@
 use std::{collections::HashMap, str::FromStr};
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::error::LDNError;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub enum DatacapGroup {
     #[serde(rename = "da")]
     DA,
@@ -27,7 +28,7 @@ impl FromStr for DatacapGroup {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 #[serde(untagged)]
 pub enum Version {
     Number(u8),
@@ -40,10 +41,12 @@ pub enum Version {
 // In occasions where you need to add new question or modify the ISSUE_TEMPLATE
 // you should implemet a new struct, for example `ParsedClient` and then convert
 // `ParsedClient` into `Client`.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct ApplicationFile {
     #[serde(rename = "Version")]
     pub version: Version,
+    #[serde(rename = "Schema Version", default)]
+    pub schema_version: u64,
     #[serde(rename = "ID")]
     pub id: String,
     #[serde(rename = "Issue Number")]
@@ -70,7 +73,7 @@ pub struct ApplicationFile {
     pub allowed_sps: Option<SpsChangeRequests>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct ApplicationResponse {
     #[serde(flatten)]
     pub file: ApplicationFile,
@@ -80,7 +83,7 @@ pub struct ApplicationResponse {
     pub owner: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, ToSchema)]
 pub struct Client {
     #[serde(rename = "Name")]
     pub name: String,
@@ -98,7 +101,7 @@ pub struct Client {
     pub role: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct Datacap {
     #[serde(rename = "Type")]
     pub _group: DatacapGroup,
@@ -133,7 +136,7 @@ impl Default for Datacap {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub enum DataType {
     #[serde(rename = "Slingshot")]
     Slingshot,
@@ -203,7 +206,7 @@ pub enum StorageProviders {
     Other(String),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, ToSchema)]
 pub struct Project {
     #[serde(rename = "Brief history of your project and organization")]
     pub history: String,
@@ -235,6 +238,12 @@ pub struct Project {
         rename = "Please list the provider IDs and location of the storage providers you will be working with. Note that it is a requirement to list a minimum of 5 unique provider IDs, and that your client address will be verified against this list in the future"
     )]
     pub providers: String,
+    #[serde(
+        rename = "Storage Providers",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub storage_providers: Vec<Provider>,
     #[serde(
         rename = "Can you confirm that you will follow the Fil+ guideline (Data owner should engage at least 4 SPs and no single SP ID should receive >30% of a client's allocated DataCap)"
     )]
@@ -251,7 +260,7 @@ pub enum DatasetLifeSpan {
     Permanently,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, ToSchema)]
 pub struct Provider {
     #[serde(rename = "ID")]
     pub id: String,
@@ -261,7 +270,7 @@ pub struct Provider {
     pub spo_org: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, ToSchema)]
 pub enum AppState {
     AdditionalInfoRequired,
     AdditionalInfoSubmitted,
@@ -276,7 +285,7 @@ pub enum AppState {
     Error,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct LifeCycle {
     #[serde(rename = "State")]
     pub state: AppState,
@@ -297,10 +306,10 @@ pub struct LifeCycle {
     pub edited: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
 pub struct Allocations(pub Vec<Allocation>);
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
 pub struct SpsChangeRequests(pub Vec<SpsChangeRequest>);
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -320,7 +329,7 @@ impl std::fmt::Display for AllocationRequestType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Allocation {
     #[serde(rename = "ID")]
     pub id: String,
@@ -338,7 +347,7 @@ pub struct Allocation {
     pub signers: Verifiers,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct SpsChangeRequest {
     #[serde(rename = "ID")]
     pub id: String,
@@ -411,22 +420,25 @@ impl ApplicationFile {
             .map_or(vec![], |alloc| alloc.signers.0.clone())
     }
 
-    pub fn update_lifecycle_after_sign_datacap_proposal(&self, validated_by: &str) -> Self {
-        let lifecycle = self.lifecycle.sign_grant_datacap_proposal(validated_by);
-        Self {
+    pub fn update_lifecycle_after_sign_datacap_proposal(
+        &self,
+        validated_by: &str,
+    ) -> Result<Self, String> {
+        let lifecycle = self.lifecycle.sign_grant_datacap_proposal(validated_by)?;
+        Ok(Self {
             lifecycle,
             ..self.clone()
-        }
+        })
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
 pub struct Verifiers(pub Vec<Verifier>);
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
 pub struct StorageProviderChangeVerifiers(pub Vec<StorageProviderChangeVerifier>);
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct VerifierInput {
     pub github_username: String,
     pub signing_address: String,
@@ -447,7 +459,7 @@ impl From<VerifierInput> for Verifier {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Verifier {
     #[serde(rename = "Github Username")]
     pub github_username: String,
@@ -464,7 +476,7 @@ pub struct Verifier {
     pub increase_allowance_cid: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct StorageProviderChangeVerifier {
     #[serde(rename = "Github Username")]
     pub github_username: String,
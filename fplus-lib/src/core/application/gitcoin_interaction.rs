@@ -1,9 +1,10 @@
 use serde::Deserialize;
 use std::str::FromStr;
+use utoipa::ToSchema;
 
 use alloy::{
     network::TransactionBuilder,
-    primitives::{address, Address, Bytes, U256},
+    primitives::{address, Address, Bytes, B256, U256},
     providers::{Provider, ProviderBuilder},
     rpc::types::eth::{BlockId, TransactionRequest},
     signers::Signature,
@@ -24,7 +25,7 @@ sol! {
     #[allow(missing_docs)]
     function getScore(address user) view returns (uint256);
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, ToSchema)]
     struct KycApproval {
         string message;
         string client_id;
@@ -34,7 +35,7 @@ sol! {
         string expires_at;
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, ToSchema)]
     struct KycAutoallocationApproval {
         string message;
         string client_fil_address;
@@ -63,15 +64,13 @@ impl ExpirableSolStruct for KycAutoallocationApproval {
     }
 }
 
-pub async fn verify_on_gitcoin(address_from_signature: &Address) -> Result<f64, LDNError> {
+pub async fn verify_on_gitcoin(
+    address_from_signature: &Address,
+    minimum_score: f64,
+) -> Result<f64, LDNError> {
     let rpc_url = get_env_var_or_default("RPC_URL");
     let score = get_gitcoin_score_for_address(&rpc_url, *address_from_signature).await?;
 
-    let minimum_score = get_env_var_or_default("GITCOIN_MINIMUM_SCORE");
-    let minimum_score = minimum_score
-        .parse::<f64>()
-        .map_err(|e| LDNError::New(format!("Parse minimum score to f64 failed: {e:?}")))?;
-
     if score <= minimum_score {
         return Err(LDNError::New(format!(
             "For address: {}, Gitcoin passport score is too low ({}). Minimum value is: {}",
@@ -108,17 +107,21 @@ fn calculate_score(response: Bytes) -> Result<f64, LDNError> {
     Ok(score as f64 / 10000.0)
 }
 
-pub fn get_address_from_signature<T: SolStruct>(
-    message: &T,
-    signature: &str,
-) -> Result<Address, LDNError> {
+pub fn get_signing_hash<T: SolStruct>(message: &T) -> Result<B256, LDNError> {
     let domain = eip712_domain! {
         name: "Fil+ KYC",
         version: "1",
         chain_id: get_env_var_or_default("PASSPORT_VERIFIER_CHAIN_ID").parse().map_err(|_| LDNError::New("Parse chain Id to u64 failed".to_string()))?, // Filecoin Chain Id
         verifying_contract: address!("0000000000000000000000000000000000000000"),
     };
-    let hash = message.eip712_signing_hash(&domain);
+    Ok(message.eip712_signing_hash(&domain))
+}
+
+pub fn get_address_from_signature<T: SolStruct>(
+    message: &T,
+    signature: &str,
+) -> Result<Address, LDNError> {
+    let hash = get_signing_hash(message)?;
     let signature = Signature::from_str(signature)
         .map_err(|e| LDNError::New(format!("Signature parsing failed: {e:?}")))?;
     signature
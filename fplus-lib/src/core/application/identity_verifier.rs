@@ -0,0 +1,375 @@
+use std::str::FromStr;
+
+use alloy::{primitives::Address, sol};
+use chrono::{DateTime, Local};
+use fplus_database::models::allocators::Model as AllocatorModel;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::get_env_var_or_default;
+use crate::core::application::gitcoin_interaction::{
+    get_address_from_signature, verify_on_gitcoin, ExpirableSolStruct,
+};
+use crate::error::LDNError;
+use crate::external_services::github::GithubWrapper;
+
+sol! {
+    #[derive(Deserialize, ToSchema)]
+    struct IdentityAttestation {
+        #[schema(value_type = String)]
+        address subject;
+        string score;
+        string issued_at;
+        string expires_at;
+    }
+}
+
+impl ExpirableSolStruct for IdentityAttestation {
+    fn get_expires_at(&self) -> &str {
+        &self.expires_at
+    }
+
+    fn get_issued_at(&self) -> &str {
+        &self.issued_at
+    }
+}
+
+/// Attestation about a client address signed by an attester trusted by the allocator.
+#[derive(Deserialize, ToSchema)]
+pub struct SignedIdentityAttestation {
+    pub message: IdentityAttestation,
+    pub signature: String,
+}
+
+/// Identity provider configured by an allocator, stored in `allocators.identity_verifiers`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum IdentityVerifierConfig {
+    GitcoinPassport {
+        minimum_score: Option<f64>,
+    },
+    Allowlist {
+        file_path: String,
+    },
+    SignedAttestation {
+        attesters: Vec<Address>,
+        minimum_score: Option<f64>,
+    },
+}
+
+/// Data available to identity verifiers besides the address being verified.
+#[derive(Default)]
+pub struct IdentityContext<'a> {
+    pub github: Option<&'a GithubWrapper>,
+    pub attestation: Option<&'a SignedIdentityAttestation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdentityVerification {
+    pub provider: &'static str,
+    pub score: f64,
+}
+
+#[allow(async_fn_in_trait)]
+pub trait IdentityVerifier {
+    fn name(&self) -> &'static str;
+
+    /// Returns the score of the address or an error if the address is not verified.
+    async fn verify(
+        &self,
+        address: &Address,
+        context: &IdentityContext<'_>,
+    ) -> Result<f64, LDNError>;
+}
+
+pub struct GitcoinPassportVerifier {
+    pub minimum_score: f64,
+}
+
+impl IdentityVerifier for GitcoinPassportVerifier {
+    fn name(&self) -> &'static str {
+        "passport"
+    }
+
+    async fn verify(
+        &self,
+        address: &Address,
+        _context: &IdentityContext<'_>,
+    ) -> Result<f64, LDNError> {
+        verify_on_gitcoin(address, self.minimum_score).await
+    }
+}
+
+/// Accepts addresses listed in a file in the allocator repository, one address per line.
+/// Lines starting with `#` are ignored.
+pub struct AllowlistVerifier {
+    pub file_path: String,
+}
+
+impl IdentityVerifier for AllowlistVerifier {
+    fn name(&self) -> &'static str {
+        "allowlist"
+    }
+
+    async fn verify(
+        &self,
+        address: &Address,
+        context: &IdentityContext<'_>,
+    ) -> Result<f64, LDNError> {
+        let gh = context.github.ok_or(LDNError::New(
+            "Allowlist verification requires the allocator repository".to_string(),
+        ))?;
+        let content = gh
+            .get_file(&self.file_path, "main")
+            .await
+            .map_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to load allowlist {} from {}/{}: {}",
+                    self.file_path, gh.owner, gh.repo, e
+                ))
+            })?
+            .take_items()
+            .pop()
+            .and_then(|c| c.decoded_content())
+            .unwrap_or_default();
+        let is_listed = content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| Address::from_str(line).ok())
+            .any(|listed| listed == *address);
+        if !is_listed {
+            return Err(LDNError::New(format!(
+                "Address {} is not on the allowlist {}",
+                address, self.file_path
+            )));
+        }
+        Ok(1.0)
+    }
+}
+
+/// Accepts an EIP-712 attestation about the client address signed by one of the trusted attesters.
+pub struct SignedAttestationVerifier {
+    pub attesters: Vec<Address>,
+    pub minimum_score: f64,
+}
+
+impl IdentityVerifier for SignedAttestationVerifier {
+    fn name(&self) -> &'static str {
+        "attestation"
+    }
+
+    async fn verify(
+        &self,
+        address: &Address,
+        context: &IdentityContext<'_>,
+    ) -> Result<f64, LDNError> {
+        let attestation = context.attestation.ok_or(LDNError::New(
+            "Signed identity attestation is missing".to_string(),
+        ))?;
+        let attester = get_address_from_signature(&attestation.message, &attestation.signature)?;
+        if !self.attesters.contains(&attester) {
+            return Err(LDNError::New(format!(
+                "Attestation signed by {} who is not a trusted attester",
+                attester
+            )));
+        }
+        if attestation.message.subject != *address {
+            return Err(LDNError::New(format!(
+                "Attestation is issued for {}, not for {}",
+                attestation.message.subject, address
+            )));
+        }
+        let current_timestamp = Local::now();
+        let expires_at = DateTime::parse_from_rfc3339(attestation.message.get_expires_at())
+            .map_err(|e| LDNError::New(format!("Parse &str to DateTime failed: {e:?}")))?;
+        let issued_at = DateTime::parse_from_rfc3339(attestation.message.get_issued_at())
+            .map_err(|e| LDNError::New(format!("Parse &str to DateTime failed: {e:?}")))?;
+        if current_timestamp > expires_at || current_timestamp < issued_at {
+            return Err(LDNError::New(format!(
+                "Attestation is valid from {} to {}",
+                issued_at, expires_at
+            )));
+        }
+        let score =
+            attestation.message.score.parse::<f64>().map_err(|e| {
+                LDNError::New(format!("Parse attestation score to f64 failed: {e}"))
+            })?;
+        if score <= self.minimum_score {
+            return Err(LDNError::New(format!(
+                "For address: {}, attested score is too low ({}). Minimum value is: {}",
+                address, score, self.minimum_score
+            )));
+        }
+        Ok(score)
+    }
+}
+
+impl IdentityVerifierConfig {
+    pub async fn verify(
+        &self,
+        address: &Address,
+        context: &IdentityContext<'_>,
+    ) -> Result<IdentityVerification, LDNError> {
+        match self {
+            IdentityVerifierConfig::GitcoinPassport { minimum_score } => {
+                let verifier = GitcoinPassportVerifier {
+                    minimum_score: minimum_score_or_default(*minimum_score)?,
+                };
+                run_verifier(&verifier, address, context).await
+            }
+            IdentityVerifierConfig::Allowlist { file_path } => {
+                let verifier = AllowlistVerifier {
+                    file_path: file_path.clone(),
+                };
+                run_verifier(&verifier, address, context).await
+            }
+            IdentityVerifierConfig::SignedAttestation {
+                attesters,
+                minimum_score,
+            } => {
+                let verifier = SignedAttestationVerifier {
+                    attesters: attesters.clone(),
+                    minimum_score: minimum_score_or_default(*minimum_score)?,
+                };
+                run_verifier(&verifier, address, context).await
+            }
+        }
+    }
+}
+
+async fn run_verifier<V: IdentityVerifier>(
+    verifier: &V,
+    address: &Address,
+    context: &IdentityContext<'_>,
+) -> Result<IdentityVerification, LDNError> {
+    let score = verifier.verify(address, context).await?;
+    Ok(IdentityVerification {
+        provider: verifier.name(),
+        score,
+    })
+}
+
+fn minimum_score_or_default(minimum_score: Option<f64>) -> Result<f64, LDNError> {
+    match minimum_score {
+        Some(minimum_score) => Ok(minimum_score),
+        None => get_env_var_or_default("GITCOIN_MINIMUM_SCORE")
+            .parse::<f64>()
+            .map_err(|e| LDNError::New(format!("Parse minimum score to f64 failed: {e:?}"))),
+    }
+}
+
+/// Verifies the address with the configured providers in order and returns the first
+/// successful verification. Fails with all provider errors when none of them verifies the address.
+pub async fn verify_identity(
+    configs: &[IdentityVerifierConfig],
+    address: &Address,
+    context: &IdentityContext<'_>,
+) -> Result<IdentityVerification, LDNError> {
+    let mut errors = Vec::new();
+    for config in configs {
+        match config.verify(address, context).await {
+            Ok(verification) => return Ok(verification),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    Err(LDNError::New(format!(
+        "Identity verification failed: {}",
+        errors.join("; ")
+    )))
+}
+
+pub fn default_identity_verifiers() -> Vec<IdentityVerifierConfig> {
+    vec![IdentityVerifierConfig::GitcoinPassport {
+        minimum_score: None,
+    }]
+}
+
+pub fn parse_identity_verifiers(
+    identity_verifiers: Option<&serde_json::Value>,
+) -> Result<Vec<IdentityVerifierConfig>, LDNError> {
+    match identity_verifiers {
+        Some(value) if !value.is_null() => {
+            let configs: Vec<IdentityVerifierConfig> = serde_json::from_value(value.clone())
+                .map_err(|e| {
+                    LDNError::New(format!("Failed to parse identity verifiers config: {}", e))
+                })?;
+            if configs.is_empty() {
+                Ok(default_identity_verifiers())
+            } else {
+                Ok(configs)
+            }
+        }
+        _ => Ok(default_identity_verifiers()),
+    }
+}
+
+pub fn identity_verifiers_for_allocator(
+    allocator: Option<&AllocatorModel>,
+) -> Result<Vec<IdentityVerifierConfig>, LDNError> {
+    parse_identity_verifiers(allocator.and_then(|a| a.identity_verifiers.as_ref()))
+}
+
+pub fn identity_verifiers_for_autoallocation() -> Result<Vec<IdentityVerifierConfig>, LDNError> {
+    let config = get_env_var_or_default("AUTOALLOCATION_IDENTITY_VERIFIERS");
+    if config.is_empty() {
+        return Ok(default_identity_verifiers());
+    }
+    let value = serde_json::from_str::<serde_json::Value>(&config).map_err(|e| {
+        LDNError::New(format!(
+            "Failed to parse AUTOALLOCATION_IDENTITY_VERIFIERS: {}",
+            e
+        ))
+    })?;
+    parse_identity_verifiers(Some(&value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    #[test]
+    fn parse_identity_verifiers_defaults_to_gitcoin() {
+        assert_eq!(
+            parse_identity_verifiers(None).unwrap(),
+            default_identity_verifiers()
+        );
+        assert_eq!(
+            parse_identity_verifiers(Some(&serde_json::json!([]))).unwrap(),
+            default_identity_verifiers()
+        );
+    }
+
+    #[test]
+    fn parse_identity_verifiers_reads_all_providers() {
+        let value = serde_json::json!([
+            { "provider": "gitcoin_passport", "minimum_score": 20.0 },
+            { "provider": "allowlist", "file_path": "kyc/allowlist.txt" },
+            {
+                "provider": "signed_attestation",
+                "attesters": ["0x907F988126Fd7e3BB5F46412b6Db6775B3dC3F9b"]
+            }
+        ]);
+        let configs = parse_identity_verifiers(Some(&value)).unwrap();
+        assert_eq!(
+            configs,
+            vec![
+                IdentityVerifierConfig::GitcoinPassport {
+                    minimum_score: Some(20.0)
+                },
+                IdentityVerifierConfig::Allowlist {
+                    file_path: "kyc/allowlist.txt".to_string()
+                },
+                IdentityVerifierConfig::SignedAttestation {
+                    attesters: vec![address!("907F988126Fd7e3BB5F46412b6Db6775B3dC3F9b")],
+                    minimum_score: None
+                },
+            ]
+        );
+        assert!(
+            parse_identity_verifiers(Some(&serde_json::json!([{ "provider": "unknown" }])))
+                .is_err()
+        );
+    }
+}
@@ -46,7 +46,11 @@ impl LifeCycle {
 
     /// Change Application state to Proposal from Governance Review
     /// Actor input is the actor who is changing the state
-    pub fn finish_governance_review(&self, actor: String, current_allocation_id: String) -> Result<Self, String> {
+    pub fn finish_governance_review(
+        &self,
+        actor: String,
+        current_allocation_id: String,
+    ) -> Result<Self, String> {
         let new_lifecycle = LifeCycle {
             state: AppState::ReadyToSign,
             validated_by: actor,
@@ -153,6 +157,14 @@ impl LifeCycle {
         }
     }
 
+    pub fn move_back_to_granted_state(self) -> Self {
+        LifeCycle {
+            state: AppState::Granted,
+            updated_at: Utc::now().to_string(),
+            ..self.clone()
+        }
+    }
+
     pub fn move_back_to_ready_to_sign(self) -> Self {
         LifeCycle {
             state: AppState::ReadyToSign,
@@ -172,10 +184,14 @@ impl LifeCycle {
         match self.state {
             AppState::Granted | AppState::StartSignDatacap => {
                 if self.validated_by.is_empty() {
-                    return Err("Validated by is required for Granted/StartSignDatacap state".to_string());
+                    return Err(
+                        "Validated by is required for Granted/StartSignDatacap state".to_string(),
+                    );
                 }
                 if self.validated_at.is_empty() {
-                    return Err("Validated at is required for Granted/StartSignDatacap state".to_string());
+                    return Err(
+                        "Validated at is required for Granted/StartSignDatacap state".to_string(),
+                    );
                 }
             }
             AppState::ReadyToSign => {
@@ -185,7 +201,8 @@ impl LifeCycle {
                 if self.validated_at.is_empty() {
                     return Err("Validated at is required for ReadyToSign state".to_string());
                 }
-                if self.active_request.is_none() || self.active_request.as_ref().unwrap().is_empty() {
+                if self.active_request.is_none() || self.active_request.as_ref().unwrap().is_empty()
+                {
                     return Err("Active request ID is required for ReadyToSign state".to_string());
                 }
             }
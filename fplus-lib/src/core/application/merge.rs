@@ -0,0 +1,180 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use super::file::{ApplicationFile, DeepCompare};
+use crate::error::LDNError;
+
+/// Field changed both by the backend and on GitHub since the version the backend last wrote.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ConflictingField {
+    /// Path of the field in the application file, e.g. `Client/Name`
+    pub field: String,
+    /// Value the backend tried to write, `null` when it removed the field
+    pub ours: Value,
+    /// Value found on GitHub, `null` when it was removed there
+    pub theirs: Value,
+}
+
+/// Error returned instead of committing when the application file on the pull request branch
+/// was edited in a way that clashes with the change being written.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ApplicationConflict {
+    pub application_id: String,
+    pub fields: Vec<ConflictingField>,
+    /// Every difference between both versions, as reported by `DeepCompare`
+    pub differences: Vec<String>,
+}
+
+impl Display for ApplicationConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<&str> = self.fields.iter().map(|c| c.field.as_str()).collect();
+        write!(
+            f,
+            "Application {} was edited on GitHub in conflicting fields: {}",
+            self.application_id,
+            fields.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ApplicationConflict {}
+
+/// Applies the changes between `base` and `ours` on top of `theirs`, field by field. Lists are
+/// merged as a whole, so concurrent edits of the same allocation request conflict.
+pub fn merge_application_files(
+    base: &ApplicationFile,
+    ours: &ApplicationFile,
+    theirs: &ApplicationFile,
+) -> Result<ApplicationFile, LDNError> {
+    let to_value = |file: &ApplicationFile| {
+        serde_json::to_value(file)
+            .map_err(|e| LDNError::Load(format!("Failed to serialize application file: {}", e)))
+    };
+    let mut conflicts = Vec::new();
+    let merged = merge_values(
+        "",
+        Some(&to_value(base)?),
+        Some(&to_value(ours)?),
+        Some(&to_value(theirs)?),
+        &mut conflicts,
+    );
+    if !conflicts.is_empty() {
+        return Err(LDNError::Conflict(ApplicationConflict {
+            application_id: ours.id.clone(),
+            fields: conflicts,
+            differences: ours.compare(theirs),
+        }));
+    }
+    serde_json::from_value(merged.unwrap_or_default())
+        .map_err(|e| LDNError::Load(format!("Merged application file is invalid: {}", e)))
+}
+
+/// `None` stands for a field missing from that version of the file.
+fn merge_values(
+    path: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<ConflictingField>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+    if let (Some(Value::Object(ours)), Some(Value::Object(theirs))) = (ours, theirs) {
+        let base = match base {
+            Some(Value::Object(base)) => Some(base),
+            _ => None,
+        };
+        let mut merged = Map::new();
+        let keys = ours
+            .keys()
+            .chain(theirs.keys().filter(|key| !ours.contains_key(*key)));
+        for key in keys {
+            let field = format!("{}/{}", path, key);
+            let base = base.and_then(|base| base.get(key));
+            if let Some(value) =
+                merge_values(&field, base, ours.get(key), theirs.get(key), conflicts)
+            {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    conflicts.push(ConflictingField {
+        field: path.trim_start_matches('/').to_string(),
+        ours: ours.cloned().unwrap_or_default(),
+        theirs: theirs.cloned().unwrap_or_default(),
+    });
+    ours.cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::{AppState, Client, Datacap, Project, Version};
+
+    async fn application() -> ApplicationFile {
+        ApplicationFile::new(
+            "1".to_string(),
+            "f2multisig".to_string(),
+            Version::Number(1),
+            "f1client".to_string(),
+            Client {
+                name: "Client".to_string(),
+                ..Default::default()
+            },
+            Project::default(),
+            Datacap::default(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn disjoint_edits_are_merged() {
+        let base = application().await;
+        let mut ours = base.clone();
+        ours.lifecycle.state = AppState::ReadyToSign;
+        let mut theirs = base.clone();
+        theirs.client.website = "https://example.org".to_string();
+
+        let merged = merge_application_files(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged.lifecycle.state, AppState::ReadyToSign);
+        assert_eq!(merged.client.website, "https://example.org");
+        assert_eq!(merged.client.name, "Client");
+    }
+
+    #[tokio::test]
+    async fn clashing_edits_report_the_fields() {
+        let base = application().await;
+        let mut ours = base.clone();
+        ours.client.name = "Ours".to_string();
+        ours.datacap.replicas = 3;
+        let mut theirs = base.clone();
+        theirs.client.name = "Theirs".to_string();
+        theirs.datacap.replicas = 3;
+        theirs.client.region = "Europe".to_string();
+
+        let Err(LDNError::Conflict(conflict)) = merge_application_files(&base, &ours, &theirs)
+        else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(conflict.application_id, "f1client");
+        assert_eq!(
+            conflict.fields,
+            vec![ConflictingField {
+                field: "Client/Name".to_string(),
+                ours: Value::from("Ours"),
+                theirs: Value::from("Theirs"),
+            }]
+        );
+        assert!(conflict
+            .differences
+            .contains(&"Client Name: Ours vs Theirs".to_string()));
+    }
+}
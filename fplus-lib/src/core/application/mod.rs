@@ -1,12 +1,21 @@
 use file::{AppState, SpsChangeRequest, SpsChangeRequests};
+use fplus_database::models::comparable_applications::ApplicationComparableData;
+
+use crate::parsers::providers::ParsedProviders;
 
 use self::file::{AllocationRequest, Allocations, LifeCycle, Verifier, Version};
 
 pub mod allocation;
+pub mod compliance;
 pub mod file;
 pub mod gitcoin_interaction;
+pub mod identity_verifier;
 pub mod lifecycle;
+pub mod merge;
+pub mod reconciliation;
+pub mod schema;
 pub mod sps_change;
+pub mod tranche;
 
 impl file::ApplicationFile {
     #[allow(clippy::too_many_arguments)]
@@ -23,6 +32,7 @@ impl file::ApplicationFile {
         let lifecycle = LifeCycle::submitted(id.clone(), multisig_address.clone());
         Self {
             version,
+            schema_version: schema::CURRENT_SCHEMA_VERSION,
             issue_number,
             id,
             client,
@@ -55,6 +65,7 @@ impl file::ApplicationFile {
         };
         Self {
             version,
+            schema_version: schema::CURRENT_SCHEMA_VERSION,
             issue_number,
             id,
             client,
@@ -91,18 +102,21 @@ impl file::ApplicationFile {
         actor: String,
         request: AllocationRequest,
         client_contract_address: Option<String>,
-    ) -> Self {
+    ) -> Result<Self, String> {
         let new_life_cycle = self
             .lifecycle
             .clone()
-            .finish_governance_review(actor, request.id.clone());
+            .finish_governance_review(actor, request.id.clone())?;
         let allocations = Allocations::init(request.clone());
-        Self {
+        let new_app = Self {
             lifecycle: new_life_cycle,
             allocation: allocations,
             client_contract_address,
             ..self.clone()
-        }
+        };
+
+        new_app.validate()?;
+        Ok(new_app)
     }
 
     pub fn start_refill_request(&mut self, request: AllocationRequest) -> Self {
@@ -125,16 +139,17 @@ impl file::ApplicationFile {
         app_state: &AppState,
         request_id: &String,
     ) -> Result<Self, String> {
-        let new_life_cycle = self.lifecycle
-            .clone()
-            .update_lifecycle_after_sign(app_state, validated_by, request_id)?;
-            
+        let new_life_cycle =
+            self.lifecycle
+                .clone()
+                .update_lifecycle_after_sign(app_state, validated_by, request_id);
+
         let sps_change_requests = self
             .allowed_sps
             .clone()
             .unwrap_or_default()
             .add_change_request(sps_change_request);
-            
+
         let new_app = Self {
             lifecycle: new_life_cycle,
             allowed_sps: Some(sps_change_requests),
@@ -209,6 +224,14 @@ impl file::ApplicationFile {
         }
     }
 
+    pub fn move_back_to_granted_state(self) -> Self {
+        let new_life_cycle = self.lifecycle.clone().move_back_to_granted_state();
+        Self {
+            lifecycle: new_life_cycle,
+            ..self.clone()
+        }
+    }
+
     pub fn kyc_request(&self) -> Self {
         let new_life_cycle = self.lifecycle.clone().kyc_request();
         Self {
@@ -216,16 +239,88 @@ impl file::ApplicationFile {
             ..self.clone()
         }
     }
+    pub fn comparable_data(&self) -> ApplicationComparableData {
+        let addresses = [
+            self.client_contract_address.clone().unwrap_or_default(),
+            self.datacap.custom_multisig.clone(),
+        ]
+        .into_iter()
+        .map(|address| address.trim().to_lowercase())
+        .filter(|address| !address.is_empty())
+        .collect();
+        ApplicationComparableData {
+            project_desc: self.project.history.clone(),
+            stored_data_desc: self.project.stored_data_desc.clone(),
+            data_owner_name: self.client.name.clone(),
+            data_set_sample: self.project.data_sample_link.clone(),
+            storage_providers: self.storage_provider_ids(),
+            website: self.client.website.clone(),
+            social_media: self.client.social_media.clone(),
+            addresses,
+        }
+    }
+
+    /// Returns the SP IDs listed in the project and in the SP change requests,
+    /// normalized to the `f0` form.
+    pub fn storage_provider_ids(&self) -> Vec<String> {
+        let allowed = self
+            .allowed_sps
+            .iter()
+            .flat_map(|requests| requests.0.iter())
+            .filter_map(|request| request.allowed_sps.clone())
+            .flatten();
+        let mut ids: Vec<u64> = self
+            .listed_storage_provider_ids()
+            .into_iter()
+            .chain(allowed)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter().map(|id| format!("f0{}", id)).collect()
+    }
+
+    /// Returns the SPs listed in the project. Application files created before the providers
+    /// answer was parsed are parsed on the fly.
+    pub fn providers(&self) -> Vec<file::Provider> {
+        if self.project.storage_providers.is_empty() {
+            ParsedProviders::from_answer(&self.project.providers).into()
+        } else {
+            self.project.storage_providers.clone()
+        }
+    }
+
+    /// Returns the IDs of the SPs listed in the project.
+    pub fn listed_storage_provider_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self
+            .providers()
+            .iter()
+            .filter_map(|provider| provider.id.strip_prefix("f0")?.parse::<u64>().ok())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Returns the SPs currently allowed to store the client's data: the list of the latest
+    /// completed SP change request, or the SPs listed in the project if there is none.
+    pub fn allowed_storage_provider_ids(&self) -> Vec<u64> {
+        self.allowed_sps
+            .iter()
+            .flat_map(|requests| requests.0.iter())
+            .filter(|request| !request.is_active)
+            .filter_map(|request| request.allowed_sps.clone())
+            .next_back()
+            .unwrap_or_else(|| self.listed_storage_provider_ids())
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         self.lifecycle.validate()?;
 
-        match self.lifecycle.state {
-            AppState::ReadyToSign | AppState::StartSignDatacap | AppState::Granted => {
-                if self.allocation.0.is_empty() {
-                    return Err("Allocations are required for this state".to_string());
-                }
-            }
-            _ => {}
+        if matches!(
+            self.lifecycle.state,
+            AppState::ReadyToSign | AppState::StartSignDatacap | AppState::Granted
+        ) && self.allocation.0.is_empty()
+        {
+            return Err("Allocations are required for this state".to_string());
         }
 
         Ok(())
@@ -234,7 +329,11 @@ impl file::ApplicationFile {
 
 impl std::str::FromStr for file::ApplicationFile {
     type Err = serde_json::Error;
+
+    /// Parses an application file, upgrading it to the current schema version first.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        serde_json::from_str(s)
+        let mut file: serde_json::Value = serde_json::from_str(s)?;
+        schema::upgrade_application_file(&mut file).map_err(serde::de::Error::custom)?;
+        serde_json::from_value(file)
     }
 }
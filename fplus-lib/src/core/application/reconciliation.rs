@@ -0,0 +1,573 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use fplus_database::database::applications::{
+    create_application, delete_application, get_active_applications, get_merged_applications,
+    update_application,
+};
+use fplus_database::database::github_sync_cursors::{
+    get_github_sync_cursor, upsert_github_sync_cursor,
+};
+use fplus_database::models::applications::Model as ApplicationModel;
+use fplus_database::models::github_sync_cursors::Model as GithubSyncCursor;
+use octocrab::models::IssueState;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::file::ApplicationFile;
+use crate::core::LDNApplication;
+use crate::error::LDNError;
+use crate::external_services::github::{
+    github_async_new, CommitComparison, GithubResultExt, GithubWrapper, COMPARE_MAX_FILES,
+};
+use crate::external_services::github_rate_limit::in_background;
+
+const APPLICATIONS_DIRECTORY: &str = "applications";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ReconciledApplication {
+    pub id: String,
+    /// `0` for applications merged into the main branch
+    pub pr_number: u64,
+    pub path: String,
+    /// Blob SHA of the file on GitHub, `None` when the file is gone
+    pub sha: Option<String>,
+}
+
+/// Differences between the applications on GitHub and in the database since the previous
+/// reconciliation. In a dry run nothing is written to the database.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, ToSchema)]
+pub struct ReconciliationReport {
+    pub owner: String,
+    pub repo: String,
+    pub dry_run: bool,
+    /// Commit of the main branch the previous reconciliation stopped at, `None` on the first one
+    pub base_sha: Option<String>,
+    pub head_sha: String,
+    pub added: Vec<ReconciledApplication>,
+    pub updated: Vec<ReconciledApplication>,
+    pub deleted: Vec<ReconciledApplication>,
+    /// Applications whose database row is newer than their different file on GitHub. They are
+    /// left untouched for a full cache renewal or a manual fix.
+    pub conflicting: Vec<ReconciledApplication>,
+    pub unchanged: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Added,
+    Updated,
+    Conflicting,
+    Unchanged,
+}
+
+fn classify(
+    db_app: Option<&ApplicationModel>,
+    github_sha: &str,
+    github_updated_at: DateTime<Utc>,
+) -> Change {
+    match db_app {
+        None => Change::Added,
+        Some(db_app) if db_app.sha.as_deref() == Some(github_sha) => Change::Unchanged,
+        Some(db_app) if db_app.updated_at > github_updated_at => Change::Conflicting,
+        Some(_) => Change::Updated,
+    }
+}
+
+fn is_application_path(path: &str) -> bool {
+    path.strip_prefix(APPLICATIONS_DIRECTORY)
+        .and_then(|name| name.strip_prefix('/'))
+        .is_some_and(|name| !name.contains('/') && name.ends_with(".json"))
+}
+
+/// Application files touched by a comparison, as changed `(path, blob sha)` pairs and removed
+/// paths. A renamed file is removed from its previous path.
+fn changed_application_files(
+    comparison: &CommitComparison,
+) -> (Vec<(String, String)>, Vec<String>) {
+    let mut changed = Vec::new();
+    let mut removed = Vec::new();
+    for file in &comparison.files {
+        if file.status == "renamed" {
+            if let Some(previous) = file
+                .previous_filename
+                .as_ref()
+                .filter(|previous| is_application_path(previous))
+            {
+                removed.push(previous.clone());
+            }
+        }
+        if !is_application_path(&file.filename) {
+            continue;
+        }
+        match (file.status.as_str(), &file.sha) {
+            ("removed", _) => removed.push(file.filename.clone()),
+            (_, Some(sha)) => changed.push((file.filename.clone(), sha.clone())),
+            _ => {}
+        }
+    }
+    (changed, removed)
+}
+
+/// Reconciles the applications of a repository in the database with GitHub, looking only at
+/// what changed since the cursor of the previous run: the files of the main branch changed
+/// between the two commits and the pull requests updated since. The cursor only moves when
+/// the run had no conflicts or errors, so that they are reported again until resolved.
+pub async fn reconcile_applications(
+    owner: String,
+    repo: String,
+    dry_run: bool,
+) -> Result<ReconciliationReport, LDNError> {
+    in_background(reconcile(owner, repo, dry_run)).await
+}
+
+async fn reconcile(
+    owner: String,
+    repo: String,
+    dry_run: bool,
+) -> Result<ReconciliationReport, LDNError> {
+    let cursor = get_github_sync_cursor(&owner, &repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get sync cursor: {}", e)))?;
+    let gh = github_async_new(owner.clone(), repo.clone()).await?;
+    let head_sha = gh.get_main_branch_sha().await?;
+    if head_sha.is_empty() {
+        return Err(LDNError::Load(format!(
+            "Failed to get main branch of {}/{}",
+            owner, repo
+        )));
+    }
+
+    let mut reconciler = Reconciler {
+        gh,
+        dry_run,
+        report: ReconciliationReport {
+            owner,
+            repo,
+            dry_run,
+            base_sha: cursor.as_ref().map(|cursor| cursor.main_sha.clone()),
+            head_sha,
+            ..Default::default()
+        },
+    };
+    reconciler.reconcile_merged().await?;
+    let pull_requests_updated_at = reconciler.reconcile_active(cursor.as_ref()).await?;
+
+    let report = reconciler.report;
+    if !dry_run && report.conflicting.is_empty() && report.errors.is_empty() {
+        upsert_github_sync_cursor(
+            &report.owner,
+            &report.repo,
+            &report.head_sha,
+            pull_requests_updated_at,
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to save sync cursor: {}", e)))?;
+    }
+    Ok(report)
+}
+
+struct Reconciler {
+    gh: GithubWrapper,
+    dry_run: bool,
+    report: ReconciliationReport,
+}
+
+impl Reconciler {
+    async fn reconcile_merged(&mut self) -> Result<(), LDNError> {
+        let db_apps = get_merged_applications(
+            Some(self.report.owner.clone()),
+            Some(self.report.repo.clone()),
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get merged applications: {}", e)))?;
+
+        let (changed, removed) = match self.report.base_sha.clone() {
+            Some(base) if base == self.report.head_sha => return Ok(()),
+            Some(base) => {
+                let comparison = self
+                    .gh
+                    .compare_commits(&base, &self.report.head_sha)
+                    .await
+                    .map_github_err(|e| {
+                        LDNError::Load(format!("Failed to compare {}: {}", base, e))
+                    })?;
+                // A rewritten history or a truncated file list needs a full listing
+                if comparison.status == "ahead" && comparison.files.len() < COMPARE_MAX_FILES {
+                    changed_application_files(&comparison)
+                } else {
+                    self.listed_application_files(&db_apps).await?
+                }
+            }
+            None => self.listed_application_files(&db_apps).await?,
+        };
+
+        let mut reconciled = HashSet::new();
+        for (path, sha) in changed {
+            let db_app = db_apps
+                .iter()
+                .find(|db_app| db_app.path.as_deref() == Some(path.as_str()));
+            if db_app.is_some_and(|db_app| db_app.sha.as_deref() == Some(sha.as_str())) {
+                self.report.unchanged += 1;
+                continue;
+            }
+            let app_file = match self.merged_application_file(&path).await {
+                Ok(Some(app_file)) => app_file,
+                // Inactive applications are not kept in the database
+                Ok(None) => {
+                    if let Some(db_app) = db_app {
+                        self.delete(db_app).await?;
+                        reconciled.insert(db_app.id.clone());
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    self.report.errors.push(format!("{}: {}", path, e));
+                    continue;
+                }
+            };
+            let updated_at = self
+                .gh
+                .get_last_modification_date(&path)
+                .await
+                .unwrap_or_else(|_| Utc::now());
+            let db_app = db_apps.iter().find(|db_app| db_app.id == app_file.id);
+            reconciled.insert(app_file.id.clone());
+            self.apply(classify(db_app, &sha, updated_at), app_file, 0, path, sha)
+                .await?;
+        }
+
+        for path in removed {
+            if let Some(db_app) = db_apps.iter().find(|db_app| {
+                db_app.path.as_deref() == Some(path.as_str()) && !reconciled.contains(&db_app.id)
+            }) {
+                self.delete(db_app).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every application file of the main branch as changed, and the paths of the database
+    /// rows missing from it as removed.
+    async fn listed_application_files(
+        &self,
+        db_apps: &[ApplicationModel],
+    ) -> Result<(Vec<(String, String)>, Vec<String>), LDNError> {
+        let files = self
+            .gh
+            .get_files(APPLICATIONS_DIRECTORY)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to list application files: {}", e))
+            })?;
+        let changed: Vec<(String, String)> = files
+            .items
+            .into_iter()
+            .filter(|item| is_application_path(&item.path))
+            .map(|item| (item.path, item.sha))
+            .collect();
+        let removed = db_apps
+            .iter()
+            .filter_map(|db_app| db_app.path.clone())
+            .filter(|path| !changed.iter().any(|(changed, _)| changed == path))
+            .collect();
+        Ok((changed, removed))
+    }
+
+    /// Active application of a file of the main branch at the reconciled commit.
+    async fn merged_application_file(
+        &self,
+        path: &str,
+    ) -> Result<Option<ApplicationFile>, LDNError> {
+        let content = self
+            .gh
+            .get_file(path, &self.report.head_sha)
+            .await
+            .map_github_err(|e| LDNError::Load(format!("Failed to get file: {}", e)))?
+            .take_items()
+            .pop()
+            .and_then(|content| content.decoded_content())
+            .ok_or(LDNError::Load("File has no content".to_string()))?;
+        Ok(ApplicationFile::from_str(&content)
+            .ok()
+            .filter(|app_file| app_file.lifecycle.is_active))
+    }
+
+    /// Reconciles the pull requests updated since the cursor, or every open one on the first
+    /// run. Returns the last update of the pull requests seen.
+    async fn reconcile_active(
+        &mut self,
+        cursor: Option<&GithubSyncCursor>,
+    ) -> Result<Option<DateTime<Utc>>, LDNError> {
+        let db_apps = get_active_applications(
+            Some(self.report.owner.clone()),
+            Some(self.report.repo.clone()),
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get active applications: {}", e)))?;
+        let since = cursor
+            .and_then(|cursor| cursor.pull_requests_updated_at)
+            .map(|since| since.with_timezone(&Utc));
+        let pull_requests = match since {
+            Some(since) => self.gh.list_pull_requests_updated_since(since).await,
+            None => self.gh.list_pull_requests().await,
+        }
+        .map_github_err(|e| LDNError::Load(format!("Failed to list pull requests: {}", e)))?;
+        let pull_requests_updated_at = pull_requests
+            .iter()
+            .filter_map(|pr| pr.updated_at)
+            .max()
+            .or(since)
+            .or_else(|| Some(Utc::now()));
+
+        let mut open_prs = HashSet::new();
+        for pr in pull_requests {
+            let pr_number = pr.number;
+            if pr.state == Some(IssueState::Closed) {
+                for db_app in db_apps
+                    .iter()
+                    .filter(|db_app| db_app.pr_number as u64 == pr_number)
+                {
+                    self.delete(db_app).await?;
+                }
+                continue;
+            }
+            open_prs.insert(pr_number);
+            let loaded = LDNApplication::load_pr_files(
+                pr,
+                self.report.owner.clone(),
+                self.report.repo.clone(),
+            )
+            .await;
+            let (sha, path, app_file, pr) = match loaded {
+                Ok(Some(loaded)) => loaded,
+                Ok(None) => continue,
+                Err(e) => {
+                    self.report
+                        .errors
+                        .push(format!("Pull request {}: {}", pr_number, e));
+                    continue;
+                }
+            };
+            let db_app = db_apps
+                .iter()
+                .find(|db_app| db_app.id == app_file.id && db_app.pr_number as u64 == pr_number);
+            let updated_at = pr.updated_at.unwrap_or_else(Utc::now);
+            self.apply(
+                classify(db_app, &sha, updated_at),
+                app_file,
+                pr_number,
+                path,
+                sha,
+            )
+            .await?;
+        }
+
+        if since.is_none() {
+            for db_app in db_apps
+                .iter()
+                .filter(|db_app| !open_prs.contains(&(db_app.pr_number as u64)))
+            {
+                self.delete(db_app).await?;
+            }
+        }
+        Ok(pull_requests_updated_at)
+    }
+
+    async fn apply(
+        &mut self,
+        change: Change,
+        app_file: ApplicationFile,
+        pr_number: u64,
+        path: String,
+        sha: String,
+    ) -> Result<(), LDNError> {
+        let reconciled = ReconciledApplication {
+            id: app_file.id.clone(),
+            pr_number,
+            path: path.clone(),
+            sha: Some(sha.clone()),
+        };
+        match change {
+            Change::Unchanged => {
+                self.report.unchanged += 1;
+                return Ok(());
+            }
+            Change::Conflicting => {
+                self.report.conflicting.push(reconciled);
+                return Ok(());
+            }
+            Change::Added => self.report.added.push(reconciled),
+            Change::Updated => self.report.updated.push(reconciled),
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let owner = self.report.owner.clone();
+        let repo = self.report.repo.clone();
+        let parsed_app_file = serde_json::to_string_pretty(&app_file)
+            .map_err(|e| LDNError::Load(format!("Failed to parse into string: {}", e)))?;
+        if change == Change::Updated {
+            return update_application(
+                app_file.id.clone(),
+                owner,
+                repo,
+                pr_number,
+                parsed_app_file,
+                Some(path),
+                Some(sha),
+                app_file.client_contract_address.clone(),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to update application: {} /// {}",
+                    app_file.id, e
+                ))
+            });
+        }
+
+        let issue_number = app_file.issue_number.parse::<i64>().map_err(|e| {
+            LDNError::New(format!(
+                "Parse issue number: {} to i64 failed. {}",
+                app_file.issue_number, e
+            ))
+        })?;
+        let issue_reporter_handle = self
+            .gh
+            .get_issue_reporter_handle(&issue_number.try_into().unwrap_or_default())
+            .await?;
+        create_application(
+            app_file.id.clone(),
+            owner,
+            repo,
+            pr_number,
+            issue_number,
+            parsed_app_file,
+            path,
+            Some(issue_reporter_handle),
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to create application in the database: {}",
+                e
+            ))
+        })
+    }
+
+    async fn delete(&mut self, db_app: &ApplicationModel) -> Result<(), LDNError> {
+        self.report.deleted.push(ReconciledApplication {
+            id: db_app.id.clone(),
+            pr_number: db_app.pr_number as u64,
+            path: db_app.path.clone().unwrap_or_default(),
+            sha: None,
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+        delete_application(
+            db_app.id.clone(),
+            db_app.owner.clone(),
+            db_app.repo.clone(),
+            db_app.pr_number as u64,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to delete application: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external_services::github::ComparedFile;
+    use chrono::Duration;
+
+    fn compared(filename: &str, status: &str, previous_filename: Option<&str>) -> ComparedFile {
+        ComparedFile {
+            filename: filename.to_string(),
+            status: status.to_string(),
+            sha: Some(format!("{}-sha", filename)),
+            previous_filename: previous_filename.map(str::to_string),
+        }
+    }
+
+    fn db_app(sha: &str, updated_at: DateTime<Utc>) -> ApplicationModel {
+        ApplicationModel {
+            id: "f1abc".to_string(),
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            pr_number: 0,
+            issue_number: 1,
+            application: None,
+            updated_at,
+            sha: Some(sha.to_string()),
+            path: Some("applications/f1abc.json".to_string()),
+            client_contract_address: None,
+            issue_reporter_handle: None,
+        }
+    }
+
+    #[test]
+    fn only_application_files_of_the_comparison_are_considered() {
+        let comparison = CommitComparison {
+            status: "ahead".to_string(),
+            files: vec![
+                compared("applications/a.json", "modified", None),
+                compared("applications/b.json", "removed", None),
+                compared(
+                    "applications/d.json",
+                    "renamed",
+                    Some("applications/c.json"),
+                ),
+                compared("applications/nested/e.json", "added", None),
+                compared("README.md", "modified", None),
+            ],
+        };
+        let (changed, removed) = changed_application_files(&comparison);
+        assert_eq!(
+            changed,
+            vec![
+                (
+                    "applications/a.json".to_string(),
+                    "applications/a.json-sha".to_string()
+                ),
+                (
+                    "applications/d.json".to_string(),
+                    "applications/d.json-sha".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            removed,
+            vec![
+                "applications/b.json".to_string(),
+                "applications/c.json".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn newer_database_rows_with_another_sha_conflict() {
+        let now = Utc::now();
+        let earlier = now - Duration::hours(1);
+        assert_eq!(classify(None, "sha", now), Change::Added);
+        assert_eq!(
+            classify(Some(&db_app("sha", now)), "sha", earlier),
+            Change::Unchanged
+        );
+        assert_eq!(
+            classify(Some(&db_app("old", earlier)), "sha", now),
+            Change::Updated
+        );
+        assert_eq!(
+            classify(Some(&db_app("old", now)), "sha", earlier),
+            Change::Conflicting
+        );
+    }
+}
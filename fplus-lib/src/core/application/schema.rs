@@ -0,0 +1,348 @@
+use fplus_database::models::allocators::Model;
+use octocrab::models::repos::Content;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{PartialSchema, ToSchema};
+
+use super::file::{ApplicationFile, Project};
+use crate::core::allocator::allocators_to_update;
+use crate::core::GithubQueryParams;
+use crate::error::LDNError;
+use crate::external_services::github::{GithubResultExt, GithubWrapper};
+use crate::parsers::providers::ParsedProviders;
+
+/// Schema version written to new application files. Bump it together with a new entry in
+/// `UPGRADES` whenever the shape of `ApplicationFile` changes.
+pub const CURRENT_SCHEMA_VERSION: u64 = 1;
+
+/// Key of the schema version in the application file. Files written before schema versioning
+/// do not have it and are version 0.
+pub const SCHEMA_VERSION_KEY: &str = "Schema Version";
+
+/// Prefix of the branches opened by application file upgrades.
+pub const SCHEMA_UPGRADE_BRANCH_PREFIX: &str = "filplus-schema-upgrade-";
+
+type Upgrade = fn(&mut Value) -> Result<(), String>;
+
+/// Upgrade functions, `UPGRADES[n]` upgrades a file from version `n` to `n + 1`.
+const UPGRADES: [Upgrade; CURRENT_SCHEMA_VERSION as usize] = [upgrade_v0_to_v1];
+
+/// v1 stores the SPs of the providers answer as structured `Storage Providers` entries.
+fn upgrade_v0_to_v1(file: &mut Value) -> Result<(), String> {
+    let project = file
+        .get_mut("Project")
+        .ok_or("Application file has no Project")?;
+    let mut parsed: Project =
+        serde_json::from_value(project.clone()).map_err(|e| format!("Invalid Project: {}", e))?;
+    if parsed.storage_providers.is_empty() {
+        parsed.storage_providers = ParsedProviders::from_answer(&parsed.providers).into();
+        *project = serde_json::to_value(parsed).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn schema_version(file: &Value) -> u64 {
+    file.get(SCHEMA_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// Upgrades an application file to `CURRENT_SCHEMA_VERSION`, one version at a time.
+/// Returns the version the file had before the upgrade.
+pub fn upgrade_application_file(file: &mut Value) -> Result<u64, String> {
+    let from_version = schema_version(file);
+    if from_version > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "Application file schema version {} is newer than the supported version {}",
+            from_version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+    if !file.is_object() {
+        return Err("Application file is not a JSON object".to_string());
+    }
+    for version in from_version..CURRENT_SCHEMA_VERSION {
+        UPGRADES[version as usize](file)
+            .map_err(|e| format!("Failed to upgrade from version {}: {}", version, e))?;
+        file[SCHEMA_VERSION_KEY] = Value::from(version + 1);
+    }
+    Ok(from_version)
+}
+
+/// JSON Schema of the current application file, generated from `ApplicationFile`, for
+/// allocator repositories to validate their files against.
+pub fn application_file_json_schema() -> Value {
+    let mut definitions = Vec::new();
+    ApplicationFile::schemas(&mut definitions);
+    let definitions: serde_json::Map<String, Value> = definitions
+        .into_iter()
+        .filter_map(|(name, schema)| Some((name, serde_json::to_value(schema).ok()?)))
+        .collect();
+    let mut schema = serde_json::to_value(ApplicationFile::schema()).unwrap_or_default();
+    schema["$schema"] = Value::from("https://json-schema.org/draft/2020-12/schema");
+    schema["title"] = Value::from(ApplicationFile::name());
+    schema["$defs"] = Value::Object(definitions);
+    // Schemas reference each other as OpenAPI components
+    let schema = schema
+        .to_string()
+        .replace("#/components/schemas/", "#/$defs/");
+    serde_json::from_str(&schema).unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct UpgradedApplicationFile {
+    pub path: String,
+    pub from_version: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ApplicationSchemaUpgrade {
+    pub owner: String,
+    pub repo: String,
+    pub files: Vec<UpgradedApplicationFile>,
+    /// Files that could not be upgraded, with the reason
+    pub failed_files: Vec<(String, String)>,
+    pub pull_request: Option<String>,
+    pub error: Option<String>,
+}
+
+struct OutdatedFile {
+    path: String,
+    sha: String,
+    from_version: u64,
+    content: String,
+}
+
+/// Upgrades the application files in the `applications` directory of each allocator
+/// repository, opening one pull request per repository. With `dry_run` only the files that
+/// would be upgraded are reported.
+pub async fn upgrade_application_files(
+    affected_allocators: Option<Vec<GithubQueryParams>>,
+    dry_run: bool,
+) -> Result<Vec<ApplicationSchemaUpgrade>, LDNError> {
+    let allocators = allocators_to_update(affected_allocators).await?;
+    let mut upgrades = Vec::new();
+    for allocator in allocators {
+        let mut upgrade = ApplicationSchemaUpgrade {
+            owner: allocator.owner.clone(),
+            repo: allocator.repo.clone(),
+            files: vec![],
+            failed_files: vec![],
+            pull_request: None,
+            error: None,
+        };
+        if let Err(e) = upgrade_allocator_files(&allocator, dry_run, &mut upgrade).await {
+            upgrade.error = Some(e.to_string());
+        }
+        upgrades.push(upgrade);
+    }
+    Ok(upgrades)
+}
+
+async fn upgrade_allocator_files(
+    allocator: &Model,
+    dry_run: bool,
+    upgrade: &mut ApplicationSchemaUpgrade,
+) -> Result<(), LDNError> {
+    let gh = GithubWrapper::new(
+        allocator.owner.clone(),
+        allocator.repo.clone(),
+        allocator.installation_id,
+    )?;
+    let open_pr = gh
+        .list_pull_requests()
+        .await
+        .map_github_err(|e| LDNError::Load(format!("Failed to list pull requests: {}", e)))?
+        .into_iter()
+        .find(|pr| pr.head.ref_field.starts_with(SCHEMA_UPGRADE_BRANCH_PREFIX));
+    if let Some(pr) = open_pr {
+        log::info!(
+            "Schema upgrade pull request already open in {}/{}",
+            gh.owner,
+            gh.repo
+        );
+        upgrade.pull_request = pr.html_url.map(|url| url.to_string());
+        return Ok(());
+    }
+
+    let outdated = outdated_files(&gh, upgrade).await?;
+    upgrade.files = outdated
+        .iter()
+        .map(|file| UpgradedApplicationFile {
+            path: file.path.clone(),
+            from_version: file.from_version,
+        })
+        .collect();
+    if dry_run || outdated.is_empty() {
+        return Ok(());
+    }
+
+    let branch_name = format!(
+        "{}{}",
+        SCHEMA_UPGRADE_BRANCH_PREFIX,
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    );
+    let head_hash = gh.get_main_branch_sha().await?;
+    let create_ref_request = gh
+        .build_create_ref_request(branch_name.clone(), head_hash)
+        .map_err(|e| LDNError::New(format!("Failed to build create branch request: {}", e)))?;
+    gh.create_branch(create_ref_request)
+        .await
+        .map_github_err(|e| {
+            LDNError::New(format!("Failed to create branch {}: {}", branch_name, e))
+        })?;
+
+    for file in &outdated {
+        let message = format!(
+            "Upgrade {} to schema version {}",
+            file.path, CURRENT_SCHEMA_VERSION
+        );
+        gh.update_file(&file.path, &message, &file.content, &branch_name, &file.sha)
+            .await
+            .map_github_err(|e| LDNError::New(format!("Failed to update {}: {}", file.path, e)))?;
+    }
+
+    let body = format!(
+        "Upgrades the following application files to schema version {}:\n\n{}",
+        CURRENT_SCHEMA_VERSION,
+        outdated
+            .iter()
+            .map(|file| format!("* `{}` (version {})", file.path, file.from_version))
+            .collect::<Vec<String>>()
+            .join("\n")
+    );
+    let pr = gh
+        .create_pull_request("Upgrade application files schema", &branch_name, body)
+        .await
+        .map_github_err(|e| LDNError::New(format!("Failed to create pull request: {}", e)))?;
+    upgrade.pull_request = pr.html_url.map(|url| url.to_string());
+    Ok(())
+}
+
+/// Reads the application files of the main branch and upgrades the outdated ones. Files that
+/// fail to upgrade are recorded in `upgrade.failed_files` and left untouched.
+async fn outdated_files(
+    gh: &GithubWrapper,
+    upgrade: &mut ApplicationSchemaUpgrade,
+) -> Result<Vec<OutdatedFile>, LDNError> {
+    let items = gh
+        .get_files("applications")
+        .await
+        .map_github_err(|e| LDNError::Load(format!("Failed to list application files: {}", e)))?
+        .items;
+    let mut outdated = Vec::new();
+    for item in items
+        .into_iter()
+        .filter(|item| item.name.ends_with(".json"))
+    {
+        match outdated_file(gh, &item).await {
+            Ok(Some(file)) => outdated.push(file),
+            Ok(None) => {}
+            Err(e) => upgrade.failed_files.push((item.path, e)),
+        }
+    }
+    Ok(outdated)
+}
+
+async fn outdated_file(gh: &GithubWrapper, item: &Content) -> Result<Option<OutdatedFile>, String> {
+    let content = gh
+        .get_file(&item.path, "main")
+        .await
+        .map_err(|e| e.to_string())?
+        .take_items()
+        .pop()
+        .and_then(|file| file.decoded_content())
+        .ok_or("File has no content")?;
+    let mut file: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let from_version = upgrade_application_file(&mut file)?;
+    if from_version == CURRENT_SCHEMA_VERSION {
+        return Ok(None);
+    }
+    let app: ApplicationFile = serde_json::from_value(file).map_err(|e| e.to_string())?;
+    Ok(Some(OutdatedFile {
+        path: item.path.clone(),
+        sha: item.sha.clone(),
+        from_version,
+        content: serde_json::to_string_pretty(&app).map_err(|e| e.to_string())?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::{Client, Datacap, Version};
+    use serde_json::json;
+
+    fn v0_file() -> Value {
+        json!({
+            "Project": {
+                "Brief history of your project and organization": "",
+                "Is this project associated with other projects/ecosystem stakeholders?": "",
+                "Describe the data being stored onto Filecoin": "",
+                "Where was the data currently stored in this dataset sourced from": "",
+                "How do you plan to prepare the dataset": "",
+                "Please share a sample of the data (a link to a file, an image, a table, etc., are good ways to do this.)": "",
+                "Confirm that this is a public dataset that can be retrieved by anyone on the network (i.e., no specific permissions or access rights are required to view the data)": "",
+                "What is the expected retrieval frequency for this data": "",
+                "For how long do you plan to keep this dataset stored on Filecoin": "",
+                "In which geographies do you plan on making storage deals": "",
+                "How will you be distributing your data to storage providers": "",
+                "Please list the provider IDs and location of the storage providers you will be working with. Note that it is a requirement to list a minimum of 5 unique provider IDs, and that your client address will be verified against this list in the future": "f01 Germany, f02 Japan",
+                "Can you confirm that you will follow the Fil+ guideline (Data owner should engage at least 4 SPs and no single SP ID should receive >30% of a client's allocated DataCap)": ""
+            }
+        })
+    }
+
+    #[test]
+    fn v0_file_is_upgraded() {
+        let mut file = v0_file();
+        assert_eq!(upgrade_application_file(&mut file), Ok(0));
+        assert_eq!(schema_version(&file), CURRENT_SCHEMA_VERSION);
+        let providers = file["Project"]["Storage Providers"].as_array().unwrap();
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[1]["ID"], "f02");
+        assert_eq!(providers[1]["Location"], "Japan");
+    }
+
+    #[test]
+    fn current_file_is_unchanged() {
+        let mut file = v0_file();
+        file[SCHEMA_VERSION_KEY] = Value::from(CURRENT_SCHEMA_VERSION);
+        let before = file.clone();
+        assert_eq!(
+            upgrade_application_file(&mut file),
+            Ok(CURRENT_SCHEMA_VERSION)
+        );
+        assert_eq!(file, before);
+    }
+
+    #[test]
+    fn newer_file_is_rejected() {
+        let mut file = json!({ SCHEMA_VERSION_KEY: CURRENT_SCHEMA_VERSION + 1 });
+        assert!(upgrade_application_file(&mut file).is_err());
+    }
+
+    #[tokio::test]
+    async fn application_file_matches_json_schema() {
+        let schema = jsonschema::JSONSchema::compile(&application_file_json_schema()).unwrap();
+        let app = ApplicationFile::new(
+            "1".to_string(),
+            "f2multisig".to_string(),
+            Version::Number(1),
+            "f1client".to_string(),
+            Client::default(),
+            Project {
+                providers: "f01 Germany".to_string(),
+                storage_providers: ParsedProviders::from_answer("f01 Germany").into(),
+                ..Default::default()
+            },
+            Datacap::default(),
+        )
+        .await;
+        let file = serde_json::to_value(&app).unwrap();
+        assert!(schema.is_valid(&file));
+
+        let mut invalid = file.clone();
+        invalid["Datacap"]["Replicas"] = Value::from("three");
+        assert!(!schema.is_valid(&invalid));
+    }
+}
@@ -1,9 +1,195 @@
+use std::{collections::HashSet, str::FromStr};
+
 use chrono::Utc;
+use futures::future::join_all;
 
 use super::file::{
     SpsChangeRequest, SpsChangeRequests, StorageProviderChangeVerifier,
     StorageProviderChangeVerifiers,
 };
+use crate::error::LDNError;
+use crate::external_services::filecoin::{
+    get_builtin_actor_code, state_miner_power, state_read_actor_state,
+};
+
+/// Number of SPs required by the allocator, written as `5+`, `3-5` or `4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredSps {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl FromStr for RequiredSps {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("{} is not a valid number of required SPs", s))
+        };
+        if let Some(min) = s.strip_suffix('+') {
+            return Ok(Self {
+                min: parse(min)?,
+                max: None,
+            });
+        }
+        if let Some((min, max)) = s.split_once('-') {
+            let (min, max) = (parse(min)?, parse(max)?);
+            if min > max {
+                return Err(format!("{} is not a valid number of required SPs", s));
+            }
+            return Ok(Self {
+                min,
+                max: Some(max),
+            });
+        }
+        let count = parse(s)?;
+        Ok(Self {
+            min: count,
+            max: Some(count),
+        })
+    }
+}
+
+impl RequiredSps {
+    pub fn contains(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+/// Parses the maximum deviation from fair distribution, a percentage between 0 and 100
+/// with an optional `%` suffix.
+pub fn parse_max_deviation(max_deviation: &str) -> Result<f64, String> {
+    let value = max_deviation.trim();
+    let value = value.strip_suffix('%').unwrap_or(value).trim();
+    let deviation = value
+        .parse::<f64>()
+        .map_err(|_| format!("Max deviation {} is not a number", max_deviation))?;
+    if !(0.0..=100.0).contains(&deviation) {
+        return Err(format!(
+            "Max deviation {} must be between 0% and 100%",
+            max_deviation
+        ));
+    }
+    Ok(deviation)
+}
+
+/// Checks the proposed SP list and max deviation against the allocator rules.
+/// An unparsable `required_sps` rule is skipped: the allocator schema accepts free text for it,
+/// as older allocator files describe the rule in words.
+pub fn validate_sps_change_request(
+    allowed_sps: Option<&[u64]>,
+    max_deviation: Option<&str>,
+    required_sps: Option<&str>,
+) -> Result<(), LDNError> {
+    let mut errors = Vec::new();
+    if let Some(max_deviation) = max_deviation {
+        if let Err(e) = parse_max_deviation(max_deviation) {
+            errors.push(e);
+        }
+    }
+    if let Some(allowed_sps) = allowed_sps {
+        let mut unique_sps = HashSet::new();
+        let duplicates: Vec<String> = allowed_sps
+            .iter()
+            .filter(|sp| !unique_sps.insert(**sp))
+            .map(|sp| format!("f0{}", sp))
+            .collect();
+        if !duplicates.is_empty() {
+            errors.push(format!(
+                "Duplicated storage providers: {}",
+                duplicates.join(", ")
+            ));
+        }
+        match required_sps.map(RequiredSps::from_str) {
+            Some(Ok(required)) if !required.contains(unique_sps.len()) => {
+                errors.push(format!(
+                    "Allocator requires {} storage providers, got {}",
+                    required_sps.unwrap_or_default(),
+                    unique_sps.len()
+                ));
+            }
+            Some(Err(e)) => log::warn!("Skipping required SPs rule: {}", e),
+            _ => {}
+        }
+    }
+    if !errors.is_empty() {
+        return Err(LDNError::New(format!(
+            "Invalid storage providers change request: {}",
+            errors.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+/// Confirms that every SP is an existing miner actor with non-zero power. Errors returned by
+/// the node are reported as such rather than as invalid SPs.
+pub async fn validate_sps_on_chain(allowed_sps: &[u64]) -> Result<(), LDNError> {
+    let miner_code = get_builtin_actor_code("storageminer").await?;
+    let miner_code = &miner_code;
+    let results = join_all(allowed_sps.iter().map(|sp| async move {
+        let address = format!("f0{}", sp);
+        let actor = state_read_actor_state(&address)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to read state of {}: {}", address, e)))?;
+        match actor.error {
+            Some(error) if error.is_actor_not_found() => {
+                return Ok(Some(format!("{} not found on chain", address)));
+            }
+            Some(error) => {
+                return Err(LDNError::Load(format!(
+                    "Node failed to read state of {}: {}",
+                    address, error
+                )));
+            }
+            None => {}
+        }
+        let Some(actor) = actor.result else {
+            return Ok(Some(format!("{} not found on chain", address)));
+        };
+        if actor.code.code != *miner_code {
+            return Ok(Some(format!("{} is not a miner actor", address)));
+        }
+        let miner_power = state_miner_power(&address).await.map_err(|e| {
+            LDNError::Load(format!("Failed to get miner power of {}: {}", address, e))
+        })?;
+        if let Some(error) = miner_power.error {
+            return Err(LDNError::Load(format!(
+                "Node failed to get miner power of {}: {}",
+                address, error
+            )));
+        }
+        let has_power = miner_power.result.is_some_and(|power| {
+            power
+                .miner_power
+                .quality_adj_power
+                .parse::<u128>()
+                .is_ok_and(|power| power > 0)
+        });
+        if !has_power {
+            return Ok(Some(format!("{} has no power", address)));
+        }
+        Ok(None)
+    }))
+    .await;
+
+    let mut errors = Vec::new();
+    for result in results {
+        if let Some(error) = result? {
+            errors.push(error);
+        }
+    }
+    if !errors.is_empty() {
+        return Err(LDNError::New(format!(
+            "Invalid storage providers: {}",
+            errors.join("; ")
+        )));
+    }
+    Ok(())
+}
 
 impl StorageProviderChangeVerifiers {
     pub fn add_signer(&mut self, signer: &StorageProviderChangeVerifier) -> Self {
@@ -86,3 +272,62 @@ impl SpsChangeRequests {
             .map(|active_request| active_request.signers.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_sps_formats() {
+        assert_eq!(
+            RequiredSps::from_str("5+").unwrap(),
+            RequiredSps { min: 5, max: None }
+        );
+        assert_eq!(
+            RequiredSps::from_str(" 3-5 ").unwrap(),
+            RequiredSps {
+                min: 3,
+                max: Some(5)
+            }
+        );
+        assert!(RequiredSps::from_str("4").unwrap().contains(4));
+        assert!(RequiredSps::from_str("5-3").is_err());
+        assert!(RequiredSps::from_str("at least five").is_err());
+    }
+
+    #[test]
+    fn max_deviation_is_range_checked() {
+        assert_eq!(parse_max_deviation("10%").unwrap(), 10.0);
+        assert_eq!(parse_max_deviation("0").unwrap(), 0.0);
+        assert!(parse_max_deviation("150%").is_err());
+        assert!(parse_max_deviation("-1").is_err());
+        assert!(parse_max_deviation("ten").is_err());
+    }
+
+    #[test]
+    fn sps_change_request_follows_allocator_rules() {
+        assert!(
+            validate_sps_change_request(Some(&[1, 2, 3, 4, 5]), Some("10"), Some("5+")).is_ok()
+        );
+        assert!(validate_sps_change_request(Some(&[1, 2, 3]), None, Some("5+")).is_err());
+        assert!(validate_sps_change_request(Some(&[1, 1, 2, 3, 4, 5]), None, None).is_err());
+        assert!(validate_sps_change_request(Some(&[1]), None, Some("free text")).is_ok());
+        assert!(validate_sps_change_request(None, Some("200"), Some("5+")).is_err());
+    }
+
+    #[test]
+    fn node_errors_are_not_reported_as_missing_actors() {
+        let response: crate::models::filecoin::StateMinerPowerResponse = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"resolution lookup failed (f01234): actor not found"}}"#,
+        )
+        .unwrap();
+        assert!(response.result.is_none());
+        assert!(response.error.unwrap().is_actor_not_found());
+
+        let response: crate::models::filecoin::StateMinerPowerResponse = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32603,"message":"too many requests"}}"#,
+        )
+        .unwrap();
+        assert!(!response.error.unwrap().is_actor_not_found());
+    }
+}
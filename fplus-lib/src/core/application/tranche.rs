@@ -0,0 +1,183 @@
+use fplus_database::models::allocators::Model as AllocatorModel;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::file::ApplicationFile;
+use crate::error::LDNError;
+use crate::helpers::parse_size_to_bytes;
+
+/// Sizing of the allocations of a client, stored in `allocators.tranche_policy`. The n-th
+/// element applies to the n-th allocation and the last one to every allocation after it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranchePolicy {
+    /// Percentages of the total requested amount, e.g. `[5, 10, 20]`
+    TotalRequestedPercentage { percentages: Vec<f64> },
+    /// Multiples of the weekly allocation declared by the client
+    WeeklyAllocation { multipliers: Vec<f64> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Tranche {
+    /// Zero-based index of the allocation the tranche applies to
+    pub index: usize,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+impl TranchePolicy {
+    fn factor(&self, index: usize) -> Option<f64> {
+        let factors = match self {
+            TranchePolicy::TotalRequestedPercentage { percentages } => percentages,
+            TranchePolicy::WeeklyAllocation { multipliers } => multipliers,
+        };
+        factors.get(index).or(factors.last()).copied()
+    }
+
+    /// Next tranche of the application, capped by the DataCap it has not requested yet.
+    pub fn next_tranche(&self, application_file: &ApplicationFile) -> Result<Tranche, LDNError> {
+        let index = application_file.allocation.0.len();
+        let total_requested = parse_size_to_bytes(&application_file.datacap.total_requested_amount)
+            .ok_or(LDNError::Load(
+                "Can not parse total requested amount to bytes".into(),
+            ))?;
+        let remaining =
+            total_requested.saturating_sub(application_file.allocation.total_requested());
+        let factor = self
+            .factor(index)
+            .ok_or(LDNError::Load("Tranche policy has no tranches".into()))?;
+        let base = match self {
+            TranchePolicy::TotalRequestedPercentage { .. } => total_requested as f64 / 100.0,
+            TranchePolicy::WeeklyAllocation { .. } => {
+                parse_size_to_bytes(&application_file.datacap.weekly_allocation).ok_or(
+                    LDNError::Load("Can not parse weekly allocation to bytes".into()),
+                )? as f64
+            }
+        };
+        Ok(Tranche {
+            index,
+            amount: ((base * factor) as u64).min(remaining),
+            remaining,
+        })
+    }
+
+    /// Fails if `amount` is bigger than the next tranche of the application.
+    pub fn check_amount(
+        &self,
+        application_file: &ApplicationFile,
+        amount: &str,
+    ) -> Result<Tranche, LDNError> {
+        let tranche = self.next_tranche(application_file)?;
+        let amount_bytes = parse_size_to_bytes(amount).ok_or(LDNError::Load(format!(
+            "Can not parse requested amount {} to bytes",
+            amount
+        )))?;
+        if amount_bytes > tranche.amount {
+            return Err(LDNError::Load(format!(
+                "Requested amount {} ({} bytes) exceeds tranche {} of the allocator policy ({} bytes)",
+                amount,
+                amount_bytes,
+                tranche.index + 1,
+                tranche.amount
+            )));
+        }
+        Ok(tranche)
+    }
+}
+
+/// Tranche policy of the allocator, `None` if the allocator does not limit its allocations.
+pub fn tranche_policy_for_allocator(
+    allocator: Option<&AllocatorModel>,
+) -> Result<Option<TranchePolicy>, LDNError> {
+    match allocator.and_then(|a| a.tranche_policy.as_ref()) {
+        Some(value) if !value.is_null() => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| LDNError::New(format!("Failed to parse tranche policy: {}", e))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::{
+        Allocation, AllocationRequest, AllocationRequestType, Allocations, Client, Datacap,
+        Project, Version,
+    };
+
+    const TIB: u64 = 1024 * 1024 * 1024 * 1024;
+
+    async fn application(allocated: &[&str]) -> ApplicationFile {
+        let mut application_file = ApplicationFile::new(
+            "1".to_string(),
+            "f2multisig".to_string(),
+            Version::Number(1),
+            "f1client".to_string(),
+            Client::default(),
+            Project::default(),
+            Datacap {
+                total_requested_amount: "100TiB".to_string(),
+                weekly_allocation: "4TiB".to_string(),
+                ..Default::default()
+            },
+        )
+        .await;
+        application_file.allocation = Allocations(
+            allocated
+                .iter()
+                .map(|amount| {
+                    Allocation::new(AllocationRequest::new(
+                        "actor".to_string(),
+                        "id".to_string(),
+                        AllocationRequestType::Refill(0),
+                        amount.to_string(),
+                    ))
+                })
+                .collect(),
+        );
+        application_file
+    }
+
+    #[tokio::test]
+    async fn percentage_tranches_repeat_the_last_step() {
+        let policy = TranchePolicy::TotalRequestedPercentage {
+            percentages: vec![5.0, 10.0, 20.0],
+        };
+        let mut amounts = Vec::new();
+        for allocated in [vec![], vec!["5TiB"], vec!["5TiB", "10TiB"], vec!["5TiB"; 4]] {
+            amounts.push(
+                policy
+                    .next_tranche(&application(&allocated).await)
+                    .unwrap()
+                    .amount,
+            );
+        }
+        assert_eq!(amounts, vec![5 * TIB, 10 * TIB, 20 * TIB, 20 * TIB]);
+    }
+
+    #[tokio::test]
+    async fn tranche_is_capped_by_remaining_datacap() {
+        let policy = TranchePolicy::WeeklyAllocation {
+            multipliers: vec![1.0, 2.0],
+        };
+        assert_eq!(
+            policy.next_tranche(&application(&[]).await).unwrap().amount,
+            4 * TIB
+        );
+        let tranche = policy
+            .next_tranche(&application(&["90TiB", "7TiB"]).await)
+            .unwrap();
+        assert_eq!((tranche.amount, tranche.remaining), (3 * TIB, 3 * TIB));
+    }
+
+    #[tokio::test]
+    async fn amounts_above_the_tranche_are_rejected() {
+        let policy = TranchePolicy::TotalRequestedPercentage {
+            percentages: vec![5.0],
+        };
+        assert!(policy.check_amount(&application(&[]).await, "5TiB").is_ok());
+        assert!(policy
+            .check_amount(&application(&[]).await, "6TiB")
+            .is_err());
+    }
+}
@@ -1,6 +1,8 @@
+use crate::core::application::identity_verifier::{
+    identity_verifiers_for_autoallocation, verify_identity, IdentityContext,
+};
 use crate::core::autoallocator::metaallocator_interaction::add_verified_client;
 use crate::core::get_env_var_or_default;
-use crate::core::verify_on_gitcoin;
 use crate::core::{LDNApplication, TriggerAutoallocationInfo};
 use crate::error::LDNError;
 use alloy::primitives::Address;
@@ -11,8 +13,13 @@ pub mod metaallocator_interaction;
 
 pub async fn trigger_autoallocation(info: &TriggerAutoallocationInfo) -> Result<(), LDNError> {
     let evm_address_from_signature =
-        LDNApplication::verify_kyc_data_and_get_eth_address(&info.message, &info.signature)?;
-    verify_on_gitcoin(&evm_address_from_signature).await?;
+        LDNApplication::verify_kyc_data_and_get_eth_address(&info.message, &info.signature).await?;
+    let identity_verifiers = identity_verifiers_for_autoallocation()?;
+    let context = IdentityContext {
+        github: None,
+        attestation: info.identity_attestation.as_ref(),
+    };
+    verify_identity(&identity_verifiers, &evm_address_from_signature, &context).await?;
     let fil_client_address = &info.message.client_fil_address;
     let client_applications = get_applications_by_client_id(fil_client_address)
         .await
@@ -30,11 +37,17 @@ pub async fn trigger_autoallocation(info: &TriggerAutoallocationInfo) -> Result<
                 e
             ))
         })?;
-    upsert_autoallocation_if_eligible(&evm_address_from_signature).await?;
+    let message_hash =
+        LDNApplication::consume_signed_message(&info.message, &evm_address_from_signature).await?;
+    if let Err(e) = upsert_autoallocation_if_eligible(&evm_address_from_signature).await {
+        LDNApplication::release_signed_message(&message_hash).await?;
+        return Err(e);
+    }
     if let Err(e) = add_verified_client(fil_client_address, &amount).await {
         autoallocations_db::delete_autoallocation(evm_address_from_signature)
             .await
             .map_err(|err| LDNError::New(format!("Delete autoallocation failed: {}", err)))?;
+        LDNApplication::release_signed_message(&message_hash).await?;
         return Err(LDNError::New(format!("Add verified client failed: {}", e)));
     }
     Ok(())
@@ -4,8 +4,10 @@ use std::{collections::HashMap, str::FromStr};
 use alloy::primitives::Address;
 
 use application::file::{SpsChangeRequest, StorageProviderChangeVerifier};
-use chrono::{DateTime, Local, Utc};
-use fplus_database::database::comparable_applications::create_comparable_application;
+use chrono::{DateTime, Duration, FixedOffset, Local, Utc};
+use fplus_database::database::comparable_applications::{
+    create_comparable_application, create_or_update_comparable_application,
+};
 use fplus_database::models::comparable_applications::ApplicationComparableData;
 use futures::future;
 use octocrab::models::{
@@ -15,31 +17,51 @@ use octocrab::models::{
 use rayon::prelude::*;
 use reqwest::Response;
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::external_services::dmob::get_client_allocation;
-use crate::external_services::similarity_detection::detect_similar_applications;
+use crate::external_services::similarity_detection::{
+    add_to_linked_client_index, add_to_similarity_index, detect_similar_applications,
+    get_similarity_report, SimilarityReport,
+};
 use crate::{
     base64,
     config::get_env_var_or_default,
     core::application::{
+        compliance::{get_compliance_report, ComplianceReport},
         file::Allocations,
         gitcoin_interaction::{
-            get_address_from_signature, verify_on_gitcoin, ExpirableSolStruct, KycApproval,
+            get_address_from_signature, get_signing_hash, ExpirableSolStruct, KycApproval,
             KycAutoallocationApproval,
         },
+        identity_verifier::{
+            identity_verifiers_for_allocator, verify_identity, IdentityContext,
+            IdentityVerification, SignedIdentityAttestation,
+        },
+        merge::merge_application_files,
+        sps_change::{validate_sps_change_request, validate_sps_on_chain},
+        tranche::{tranche_policy_for_allocator, Tranche},
     },
     error::LDNError,
     external_services::{
         filecoin::{get_allowance_for_address, get_multisig_threshold_for_actor},
         github::{
-            github_async_new, CreateMergeRequestData, CreateRefillMergeRequestData, GithubWrapper,
+            github_async_new, CreateMergeRequestData, CreateRefillMergeRequestData,
+            GithubResultExt, GithubWrapper,
         },
+        github_rate_limit::in_background,
     },
     helpers::{
         is_allocator_allowance_bigger_than_allocation_amount, parse_size_to_bytes, process_amount,
     },
-    parsers::ParsedIssue,
+    parsers::{
+        forms::ISSUE_FORMS_DIR,
+        templates::IssueTemplate,
+        validation::{
+            min_required_sps, validate_issue_body, IssueValidationReport, VALIDATION_COMMENT_MARKER,
+        },
+        ParsedIssue,
+    },
 };
 use fplus_database::database::allocation_amounts::get_allocation_quantity_options;
 use fplus_database::database::{
@@ -47,6 +69,7 @@ use fplus_database::database::{
     allocators::{get_allocator, update_allocator_threshold},
 };
 
+use fplus_database::models::allocators::Model as AllocatorModel;
 use fplus_database::models::applications::Model as ApplicationModel;
 
 use self::application::file::{
@@ -61,20 +84,28 @@ pub mod allocator;
 pub mod application;
 pub mod autoallocator;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateApplicationInfo {
     pub issue_number: String,
     pub owner: String,
     pub repo: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+pub struct IssueValidationQueryParams {
+    pub issue_number: String,
+    pub owner: String,
+    pub repo: String,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct TriggerSSAInfo {
     pub amount: String,
     pub amount_type: String,
+    pub override_tranche_policy: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct BranchDeleteInfo {
     pub owner: String,
     pub repo: String,
@@ -84,27 +115,27 @@ pub struct BranchDeleteInfo {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct VerifierList(pub Vec<String>);
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct ApplicationProposalApprovalSignerInfo {
     pub signing_address: String,
     pub created_at: String,
     pub message_cids: GrantDataCapCids,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct GrantDataCapCids {
     pub message_cid: String,
     pub increase_allowance_cid: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct CompleteNewApplicationProposalInfo {
     pub signer: ApplicationProposalApprovalSignerInfo,
     pub request_id: String,
     pub new_allocation_amount: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct StorageProvidersChangeSignerInfo {
     pub signing_address: String,
     pub max_deviation_cid: Option<String>,
@@ -112,26 +143,26 @@ pub struct StorageProvidersChangeSignerInfo {
     pub removed_allowed_sps_cids: Option<HashMap<String, Vec<String>>>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct StorageProvidersChangeProposalInfo {
     pub signer: StorageProvidersChangeSignerInfo,
     pub allowed_sps: Option<Vec<u64>>,
     pub max_deviation: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct StorageProvidersChangeApprovalInfo {
     pub signer: StorageProvidersChangeSignerInfo,
     pub request_id: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct CompleteNewApplicationApprovalInfo {
     pub signer: ApplicationProposalApprovalSignerInfo,
     pub request_id: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct MoreInfoNeeded {
     pub verifier_message: String,
 }
@@ -152,23 +183,24 @@ pub struct RefillInfo {
     pub amount_type: String,
     pub owner: String,
     pub repo: String,
+    pub override_tranche_policy: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct NotifyRefillInfo {
     pub owner: String,
     pub repo: String,
     pub issue_number: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct DcReachedInfo {
     pub id: String,
     pub owner: String,
     pub repo: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ValidationPullRequestData {
     pub pr_number: String,
     pub user_handle: String,
@@ -191,47 +223,81 @@ pub struct Allocator {
     pub verifiers_gh_handles: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct ChangedAllocators {
     pub files_changed: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct AllocatorSyncQueryParams {
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct ReconciliationQueryParams {
+    pub owner: String,
+    pub repo: String,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct AllocatorValidateQueryParams {
+    pub check_chain: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct AllocatorUpdateForceInfo {
     pub files: Vec<String>,
     pub allocators: Option<Vec<GithubQueryParams>>,
+    /// Open a pull request per allocator instead of committing to the default branch
+    pub staged: Option<bool>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, ToSchema)]
+pub struct AllocatorTemplateDriftInfo {
+    pub files: Vec<String>,
+    pub allocators: Option<Vec<GithubQueryParams>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ApplicationSchemaUpgradeInfo {
+    pub allocators: Option<Vec<GithubQueryParams>>,
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
 pub struct LastAutoallocationQueryParams {
+    #[param(value_type = String)]
     pub evm_wallet_address: Address,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TriggerAutoallocationInfo {
     pub message: KycAutoallocationApproval,
     pub signature: String,
+    pub identity_attestation: Option<SignedIdentityAttestation>,
 }
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, IntoParams)]
 pub struct GithubQueryParams {
     pub owner: String,
     pub repo: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct ApplicationQueryParams {
     pub id: String,
     pub owner: String,
     pub repo: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CompleteGovernanceReviewInfo {
     pub allocation_amount: String,
     pub client_contract_address: Option<String>,
+    pub override_tranche_policy: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct VerifierActionsQueryParams {
     pub github_username: String,
     pub id: String,
@@ -239,10 +305,18 @@ pub struct VerifierActionsQueryParams {
     pub repo: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct SimilarityCheckInfo {
+    pub client_address: Option<String>,
+    #[schema(value_type = Object)]
+    pub application: ApplicationComparableData,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct SubmitKYCInfo {
     pub message: KycApproval,
     pub signature: String,
+    pub identity_attestation: Option<SignedIdentityAttestation>,
 }
 
 #[derive(Debug, Clone)]
@@ -272,6 +346,24 @@ pub struct AllocationObject {
     allocation_amount_quantity_options: Vec<String>,
 }
 
+/// Why a client has to repeat KYC before the next allocation
+#[derive(Debug, Clone, PartialEq)]
+enum KycReverification {
+    Expired(DateTime<Utc>),
+    /// No verification is recorded for the client, e.g. because it completed KYC before
+    /// verifications were stored
+    NotRecorded,
+}
+
+impl std::fmt::Display for KycReverification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KycReverification::Expired(expired_at) => write!(f, "expired at {}", expired_at),
+            KycReverification::NotRecorded => write!(f, "has no recorded verification"),
+        }
+    }
+}
+
 impl LDNApplication {
     pub async fn single_active(
         pr_number: u64,
@@ -282,7 +374,9 @@ impl LDNApplication {
         let (_, pull_request) = gh
             .get_pull_request_files(pr_number)
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request files: {}", e)))?;
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to get pull request files: {}", e))
+            })?;
         let pull_request = pull_request.first().ok_or(LDNError::Load(
             "Failed to get first pull request file.".to_string(),
         ))?;
@@ -295,13 +389,13 @@ impl LDNApplication {
             .text()
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get pull request files /// {}", e)))?;
-        if let Ok(app) = serde_json::from_str::<ApplicationFile>(&pull_request) {
+        if let Ok(app) = ApplicationFile::from_str(&pull_request) {
             Ok(app)
         } else {
             Err(LDNError::Load(format!(
                 "Pull Request {} Application file is corrupted or invalid format: {}",
                 pr_number,
-                serde_json::from_str::<ApplicationFile>(&pull_request).unwrap_err()
+                ApplicationFile::from_str(&pull_request).unwrap_err()
             )))
         }
     }
@@ -465,10 +559,9 @@ impl LDNApplication {
         repo: String,
     ) -> Result<Self, LDNError> {
         let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-        let pull_requests = gh
-            .list_pull_requests()
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get list of pull requests: {}", e)))?;
+        let pull_requests = gh.list_pull_requests().await.map_github_err(|e| {
+            LDNError::Load(format!("Failed to get list of pull requests: {}", e))
+        })?;
         let pull_requests = future::try_join_all(
             pull_requests
                 .into_iter()
@@ -555,7 +648,7 @@ impl LDNApplication {
 
             // Try to deserialize the `application` field to `ApplicationFile`.
             if let Some(app_json) = app_model.application {
-                match from_str::<ApplicationFile>(&app_json) {
+                match ApplicationFile::from_str(&app_json) {
                     Ok(app) => {
                         apps.push(ApplicationResponse {
                             file: app,
@@ -582,10 +675,9 @@ impl LDNApplication {
     ) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
         let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
         let mut apps: Vec<ApplicationFileWithDate> = Vec::new();
-        let pull_requests = gh
-            .list_pull_requests()
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get list of pull requests: {}", e)))?;
+        let pull_requests = gh.list_pull_requests().await.map_github_err(|e| {
+            LDNError::Load(format!("Failed to get list of pull requests: {}", e))
+        })?;
         let pull_requests = future::try_join_all(
             pull_requests
                 .into_iter()
@@ -622,7 +714,7 @@ impl LDNApplication {
         let gh = Arc::new(github_async_new(owner.to_string(), repo.to_string()).await?);
 
         let applications_path = "applications";
-        let mut all_files_result = gh.get_files(applications_path).await.map_err(|e| {
+        let mut all_files_result = gh.get_files(applications_path).await.map_github_err(|e| {
             LDNError::Load(format!(
                 "Failed to retrieve all files from GitHub. Reason: {}",
                 e
@@ -844,7 +936,7 @@ impl LDNApplication {
                 let prs = gh
                     .get_pull_request_by_head(&branch_name)
                     .await
-                    .map_err(|e| {
+                    .map_github_err(|e| {
                         LDNError::Load(format!("Failed to get pull request by head: {}", e))
                     })?;
 
@@ -873,12 +965,7 @@ impl LDNApplication {
                             application_id, e
                         ))
                     })?;
-                    let comparable_data = ApplicationComparableData {
-                        project_desc: parsed_ldn.project.history.clone(),
-                        stored_data_desc: parsed_ldn.project.stored_data_desc.clone(),
-                        data_owner_name: parsed_ldn.client.name.clone(),
-                        data_set_sample: parsed_ldn.project.data_sample_link.clone(),
-                    };
+                    let comparable_data = application_file.comparable_data();
 
                     detect_similar_applications(
                         &parsed_ldn.id,
@@ -888,19 +975,12 @@ impl LDNApplication {
                         &(issue_number as u64),
                     )
                     .await?;
-                    create_comparable_application(
-                        &application_id,
-                        &ApplicationComparableData {
-                            project_desc: application_file.project.history,
-                            stored_data_desc: application_file.project.stored_data_desc,
-                            data_owner_name: application_file.client.name,
-                            data_set_sample: application_file.project.data_sample_link,
-                        },
-                    )
-                    .await
-                    .map_err(|e| {
-                        LDNError::New(format!("Failed to create application in DB: {}", e))
-                    })?;
+                    create_comparable_application(&application_id, &comparable_data)
+                        .await
+                        .map_err(|e| {
+                            LDNError::New(format!("Failed to create application in DB: {}", e))
+                        })?;
+                    add_to_similarity_index(&application_id, &comparable_data).await?;
                 }
 
                 Ok(LDNApplication {
@@ -951,6 +1031,7 @@ impl LDNApplication {
         repo: String,
         allocation_amount: String,
         client_contract_address: Option<String>,
+        override_tranche_policy: bool,
     ) -> Result<ApplicationFile, LDNError> {
         let app_state = self.app_state().await?;
         if app_state != AppState::KYCRequested
@@ -970,6 +1051,12 @@ impl LDNApplication {
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {}", e)))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
+        Self::check_tranche_policy(
+            &app_file,
+            &db_allocator,
+            &allocation_amount_parsed,
+            override_tranche_policy,
+        )?;
         let address_to_check_allowance_address = db_allocator
             .address
             .ok_or(LDNError::Load("Failed to get address.".to_string()))?;
@@ -987,21 +1074,19 @@ impl LDNApplication {
             allocation_amount_parsed,
         );
 
-        let app_file = app_file.complete_governance_review(
-            actor.clone(),
-            request,
-            client_contract_address.clone(),
-        );
+        let app_file = app_file
+            .complete_governance_review(actor.clone(), request, client_contract_address.clone())
+            .map_err(LDNError::New)?;
         let file_content = serde_json::to_string_pretty(&app_file)
             .map_err(|e| LDNError::Load(format!("Failed to pare into string: {}", e)))?;
         let app_path = &self.file_name.clone();
         let app_branch = self.branch_name.clone();
         Self::issue_datacap_request_trigger(app_file.clone(), owner.clone(), repo.clone()).await?;
-        LDNPullRequest::add_commit_to(
+        let file_content = LDNPullRequest::add_commit_to(
             app_path.to_string(),
             app_branch.clone(),
             LDNPullRequest::application_move_to_proposal_commit(&actor),
-            file_content.clone(),
+            file_content,
             self.file_sha.clone(),
             owner.clone(),
             repo.clone(),
@@ -1011,7 +1096,9 @@ impl LDNApplication {
             .github
             .get_pull_request_by_head(&app_branch)
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request by head: {}", e)))?;
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to get pull request by head: {}", e))
+            })?;
         if let Some(pr) = prs.first() {
             database::applications::update_application(
                 app_file.id.clone(),
@@ -1077,11 +1164,9 @@ impl LDNApplication {
         ))?;
 
         // Get multisig threshold from blockchain
-        let blockchain_threshold =
-            match get_multisig_threshold_for_actor(&db_multisig_address).await {
-                Ok(threshold) => Some(threshold),
-                Err(_) => None,
-            };
+        let blockchain_threshold = get_multisig_threshold_for_actor(&db_multisig_address)
+            .await
+            .ok();
 
         let db_threshold: u64 = db_allocator.multisig_threshold.unwrap_or(2) as u64;
 
@@ -1123,7 +1208,9 @@ impl LDNApplication {
                 request_id
             )));
         }
-        app_file = app_file.update_lifecycle_after_sign_datacap_proposal(&signer.github_username);
+        app_file = app_file
+            .update_lifecycle_after_sign_datacap_proposal(&signer.github_username)
+            .map_err(LDNError::New)?;
         app_file = app_file.add_signer_to_allocation(signer.clone().into(), &request_id);
 
         if let Some(new_allocation_amount) = new_allocation_amount {
@@ -1146,11 +1233,11 @@ impl LDNApplication {
         let file_content = serde_json::to_string_pretty(&app_file)
             .map_err(|e| LDNError::Load(format!("Failed to pare into string: {}", e)))?;
 
-        LDNPullRequest::add_commit_to(
+        let file_content = LDNPullRequest::add_commit_to(
             self.file_name.to_string(),
             self.branch_name.clone(),
             LDNPullRequest::application_move_to_approval_commit(&signer.signing_address),
-            file_content.clone(),
+            file_content,
             self.file_sha.clone(),
             owner.clone(),
             repo.clone(),
@@ -1161,7 +1248,9 @@ impl LDNApplication {
             .github
             .get_pull_request_by_head(&self.branch_name)
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request by head: {}", e)))?;
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to get pull request by head: {}", e))
+            })?;
 
         if let Some(pr) = prs.first() {
             database::applications::update_application(
@@ -1221,6 +1310,15 @@ impl LDNApplication {
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {}", e)))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
 
+        validate_sps_change_request(
+            allowed_sps.as_deref(),
+            max_deviation.as_deref(),
+            db_allocator.required_sps.as_deref(),
+        )?;
+        if let Some(allowed_sps) = &allowed_sps {
+            validate_sps_on_chain(allowed_sps).await?;
+        }
+
         let db_multisig_address = db_allocator.multisig_address.ok_or(LDNError::Load(
             "Multisig address for the allocator not found.".to_string(),
         ))?;
@@ -1261,33 +1359,39 @@ impl LDNApplication {
                 SpsChangeRequest::new(&request_id, allowed_sps, max_deviation, &signer, false);
             if let Some(active_allocation) = app_file.allocation.active() {
                 app_state = AppState::ReadyToSign;
-                app_file = app_file.handle_changing_sps_request(
-                    &signer.github_username,
-                    &sps_change_request,
-                    &app_state,
-                    &active_allocation.id,
-                );
+                app_file = app_file
+                    .handle_changing_sps_request(
+                        &signer.github_username,
+                        &sps_change_request,
+                        &app_state,
+                        &active_allocation.id,
+                    )
+                    .map_err(LDNError::New)?;
             } else {
                 app_state = AppState::Granted;
                 let request_id = uuidv4::uuid::v4();
-                app_file = app_file.handle_changing_sps_request(
-                    &signer.github_username,
-                    &sps_change_request,
-                    &app_state,
-                    &request_id,
-                );
+                app_file = app_file
+                    .handle_changing_sps_request(
+                        &signer.github_username,
+                        &sps_change_request,
+                        &app_state,
+                        &request_id,
+                    )
+                    .map_err(LDNError::New)?;
             }
             comment = "Storage Providers have been changed successfully";
         } else {
             app_state = AppState::ChangingSP;
             let sps_change_request: SpsChangeRequest =
                 SpsChangeRequest::new(&request_id, allowed_sps, max_deviation, &signer, true);
-            app_file = app_file.handle_changing_sps_request(
-                &signer.github_username,
-                &sps_change_request,
-                &app_state,
-                &request_id,
-            );
+            app_file = app_file
+                .handle_changing_sps_request(
+                    &signer.github_username,
+                    &sps_change_request,
+                    &app_state,
+                    &request_id,
+                )
+                .map_err(LDNError::New)?;
             comment =
                 "Application is in the Changing Storage Providers state. Waiting for approval.";
         }
@@ -1332,6 +1436,16 @@ impl LDNApplication {
             .await?;
         }
 
+        // Proposed SPs are part of the linked client signals used by similarity detection
+        let comparable_data = app_file.comparable_data();
+        if let Err(e) =
+            create_or_update_comparable_application(&app_file.id, &comparable_data).await
+        {
+            log::error!("Failed to update comparable application: {}", e);
+        } else if let Err(e) = add_to_linked_client_index(&app_file.id, &comparable_data).await {
+            log::error!("Failed to index linked client signals: {}", e);
+        }
+
         self.issue_updates(&app_file.issue_number, comment, app_state.as_str())
             .await?;
         Ok(())
@@ -1506,8 +1620,9 @@ impl LDNApplication {
             comment = "Application is Granted";
             label = AppState::Granted.as_str();
         } else {
-            app_file =
-                app_file.update_lifecycle_after_sign_datacap_proposal(&signer.github_username);
+            app_file = app_file
+                .update_lifecycle_after_sign_datacap_proposal(&signer.github_username)
+                .map_err(LDNError::New)?;
             app_file = app_file.add_signer_to_allocation(signer.clone().into(), &request_id);
             commit_message = LDNPullRequest::application_signed(&signer.signing_address);
             signature_step = "signed".to_string();
@@ -1539,23 +1654,26 @@ impl LDNApplication {
         Ok(app_file)
     }
 
-    async fn parse_application_issue(
-        issue_number: String,
-        owner: String,
-        repo: String,
-    ) -> Result<(ParsedIssue, String), LDNError> {
+    async fn get_issue(
+        issue_number: &str,
+        owner: &str,
+        repo: &str,
+    ) -> Result<(String, String), LDNError> {
         let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
         let parsed_issue_number = issue_number
             .parse::<u64>()
             .map_err(|e| LDNError::New(format!("Parse issue number to u64 failed: {}", e)))?;
-        let issue = gh.list_issue(parsed_issue_number).await.map_err(|e| {
-            LDNError::Load(format!(
-                "Failed to retrieve issue {} from GitHub. Reason: {}",
-                issue_number, e
-            ))
-        })?;
+        let issue = gh
+            .list_issue(parsed_issue_number)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to retrieve issue {} from GitHub. Reason: {}",
+                    issue_number, e
+                ))
+            })?;
         if let Some(issue_body) = issue.body {
-            Ok((ParsedIssue::from_issue_body(&issue_body)?, issue.user.login))
+            Ok((issue_body, issue.user.login))
         } else {
             Err(LDNError::Load(format!(
                 "Failed to retrieve issue {} from GitHub. Reason: {}",
@@ -1564,6 +1682,131 @@ impl LDNApplication {
         }
     }
 
+    /// Application issue forms of the allocator repository. Forms that cannot be parsed are
+    /// skipped so a broken form does not block issues opened with the other ones.
+    async fn repo_issue_templates(owner: &str, repo: &str) -> Result<Vec<IssueTemplate>, LDNError> {
+        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        let forms = gh.yaml_files(ISSUE_FORMS_DIR).await?;
+        Ok(forms
+            .iter()
+            .filter_map(|form| match IssueTemplate::from_repo_issue_form(form) {
+                Ok(template) => template,
+                Err(e) => {
+                    log::warn!("Skipping issue form of {}/{}: {}", owner, repo, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn issue_validation_report(
+        issue_body: &str,
+        repo_templates: &[IssueTemplate],
+        owner: &str,
+        repo: &str,
+    ) -> Result<IssueValidationReport, LDNError> {
+        let allocator = get_allocator(owner, repo)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get allocator from DB: {}", e)))?;
+        let min_providers =
+            min_required_sps(allocator.as_ref().and_then(|a| a.required_sps.as_deref()));
+        Ok(validate_issue_body(
+            issue_body,
+            repo_templates,
+            min_providers,
+        ))
+    }
+
+    /// Returns every problem of the application issue without commenting on it.
+    pub async fn validate_application_issue(
+        issue_number: String,
+        owner: String,
+        repo: String,
+    ) -> Result<IssueValidationReport, LDNError> {
+        let (issue_body, _) = Self::get_issue(&issue_number, &owner, &repo).await?;
+        let repo_templates = Self::repo_issue_templates(&owner, &repo).await?;
+        Self::issue_validation_report(&issue_body, &repo_templates, &owner, &repo).await
+    }
+
+    /// Posts the report as a checklist on the issue, editing the checklist of earlier runs.
+    /// Nothing is posted for a clean report unless an earlier checklist has to be cleared.
+    async fn post_validation_report(
+        issue_number: &str,
+        owner: &str,
+        repo: &str,
+        report: &IssueValidationReport,
+    ) -> Result<(), LDNError> {
+        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        let number = issue_number.parse::<u64>().map_err(|e| {
+            LDNError::New(format!(
+                "Parse issue number: {} to u64 failed. {}",
+                issue_number, e
+            ))
+        })?;
+        let existing = gh
+            .find_issue_comment(number, VALIDATION_COMMENT_MARKER)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to list comments of issue {}: {}",
+                    issue_number, e
+                ))
+            })?;
+        let comment = report.format_comment();
+        match existing {
+            Some(existing) if existing.body.as_deref() == Some(comment.as_str()) => {}
+            Some(existing) => {
+                gh.update_issue_comment(existing.id, &comment)
+                    .await
+                    .map_github_err(|e| {
+                        LDNError::New(format!(
+                            "Error updating comment on issue {}: {}",
+                            issue_number, e
+                        ))
+                    })?;
+            }
+            None if report.is_clean() => {}
+            None => {
+                gh.add_comment_to_issue(number, &comment)
+                    .await
+                    .map_github_err(|e| {
+                        LDNError::New(format!(
+                            "Error adding comment to issue {}: {}",
+                            issue_number, e
+                        ))
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the application issue. Its problems and warnings are posted on the issue as a
+    /// checklist; problems also add the error label and stop the application.
+    async fn parse_application_issue(
+        issue_number: String,
+        owner: String,
+        repo: String,
+    ) -> Result<(ParsedIssue, String), LDNError> {
+        let (issue_body, issue_reporter_handle) =
+            Self::get_issue(&issue_number, &owner, &repo).await?;
+        let repo_templates = Self::repo_issue_templates(&owner, &repo).await?;
+        let report =
+            Self::issue_validation_report(&issue_body, &repo_templates, &owner, &repo).await?;
+        Self::post_validation_report(&issue_number, &owner, &repo, &report).await?;
+        if !report.valid {
+            Self::add_error_label(issue_number.clone(), String::new(), owner, repo).await?;
+            return Err(LDNError::Load(format!(
+                "Issue {} is not valid: {}",
+                issue_number,
+                report.format_error()
+            )));
+        }
+        Ok((
+            ParsedIssue::from_issue_body(&issue_body, &repo_templates)?,
+            issue_reporter_handle,
+        ))
+    }
+
     pub async fn check_application_exists(
         app_model: ApplicationModel,
         application_id: String,
@@ -1612,7 +1855,7 @@ impl LDNApplication {
                 let ContentItems { items } = gh
                     .get_file(&ldn_app.file_name, "main")
                     .await
-                    .map_err(|e| LDNError::Load(format!("Failed to get file: {}", e)))?;
+                    .map_github_err(|e| LDNError::Load(format!("Failed to get file: {}", e)))?;
                 Self::add_comment_to_issue(
                     app.issue_number.clone(),
                     owner.clone(),
@@ -1675,7 +1918,7 @@ impl LDNApplication {
             .github
             .get_file(&self.file_name, &self.branch_name)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::Load(format!(
                     "Application issue {} file does not exist /// {}",
                     self.application_id, e
@@ -1779,7 +2022,7 @@ impl LDNApplication {
         for app_model in merged_app_models {
             // Try to deserialize the `application` field to `ApplicationFile`.
             if let Some(app_json) = app_model.application {
-                if let Ok(app) = from_str::<ApplicationFile>(&app_json) {
+                if let Ok(app) = ApplicationFile::from_str(&app_json) {
                     let sha = app_model
                         .sha
                         .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
@@ -1819,6 +2062,34 @@ impl LDNApplication {
             .into_iter()
             .find(|(_, app)| app.file.id == refill_info.id)
         {
+            if let Some(reverification) =
+                Self::kyc_reverification(&app.file.id, &refill_info.owner, &refill_info.repo)
+                    .await?
+            {
+                Self::request_kyc_reverification(
+                    &content,
+                    &app.file,
+                    &refill_info.owner,
+                    &refill_info.repo,
+                    &reverification,
+                )
+                .await?;
+                return Err(LDNError::Load(format!(
+                    "KYC of client {} {}. Re-verification has been requested",
+                    app.file.id, reverification
+                )));
+            }
+            Self::check_compliance(&app.file, &refill_info.owner, &refill_info.repo).await?;
+            let db_allocator = get_allocator(&refill_info.owner, &refill_info.repo)
+                .await
+                .map_err(|e| LDNError::Load(format!("Failed to get allocator from DB: {}", e)))?
+                .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
+            Self::check_tranche_policy(
+                &app.file,
+                &db_allocator,
+                &format!("{}{}", refill_info.amount, refill_info.amount_type),
+                refill_info.override_tranche_policy.unwrap_or(false),
+            )?;
             let uuid = uuidv4::uuid::v4();
             let request_id = uuid.clone();
             let new_request = AllocationRequest::new(
@@ -1856,6 +2127,240 @@ impl LDNApplication {
         Err(LDNError::Load("Failed to get application file".to_string()))
     }
 
+    /// Rejects an allocation bigger than the next tranche of the allocator's tranche policy
+    /// unless the policy is explicitly overridden.
+    fn check_tranche_policy(
+        application_file: &ApplicationFile,
+        allocator: &AllocatorModel,
+        amount: &str,
+        override_policy: bool,
+    ) -> Result<(), LDNError> {
+        let Some(policy) = tranche_policy_for_allocator(Some(allocator))? else {
+            return Ok(());
+        };
+        match policy.check_amount(application_file, amount) {
+            Err(e) if override_policy => {
+                log::warn!(
+                    "Tranche policy of {}/{} overridden for {}: {}",
+                    allocator.owner,
+                    allocator.repo,
+                    application_file.id,
+                    e
+                );
+                Ok(())
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Next tranche of the application according to the tranche policy of its allocator,
+    /// `None` if the allocator has no tranche policy.
+    pub async fn next_tranche(
+        id: &str,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Option<Tranche>, LDNError> {
+        let app_model = Self::get_application_model(id.into(), owner.into(), repo.into()).await?;
+        let app_str = app_model.application.ok_or_else(|| {
+            LDNError::Load(format!(
+                "Application {} does not have an application field",
+                id
+            ))
+        })?;
+        let application_file = ApplicationFile::from_str(&app_str).map_err(|e| {
+            LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
+        })?;
+        let allocator = get_allocator(owner, repo)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get allocator from DB: {}", e)))?;
+        tranche_policy_for_allocator(allocator.as_ref())?
+            .map(|policy| policy.next_tranche(&application_file))
+            .transpose()
+    }
+
+    /// Checks the client's DataCap distribution before the next tranche. Depending on
+    /// `COMPLIANCE_CHECK_MODE` violations are only posted on the issue (`warn`) or also
+    /// block the allocation (`enforce`). In `warn` mode failures to build the report, e.g.
+    /// when DMOB or the Lotus node are unavailable, or to post it are logged and the
+    /// allocation goes on; in `enforce` mode they block it.
+    async fn check_compliance(
+        application_file: &ApplicationFile,
+        owner: &str,
+        repo: &str,
+    ) -> Result<(), LDNError> {
+        let mode = get_env_var_or_default("COMPLIANCE_CHECK_MODE");
+        if mode == "off" {
+            return Ok(());
+        }
+        let checked = Self::post_compliance_report(application_file, owner, repo).await;
+        Self::compliance_check_outcome(&mode, &application_file.id, checked)
+    }
+
+    /// Builds the compliance report and posts it on the issue when the client does not
+    /// follow the guideline.
+    async fn post_compliance_report(
+        application_file: &ApplicationFile,
+        owner: &str,
+        repo: &str,
+    ) -> Result<ComplianceReport, LDNError> {
+        let report = get_compliance_report(application_file).await.map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to check compliance of {}: {}",
+                application_file.id, e
+            ))
+        })?;
+        if report.is_compliant() {
+            return Ok(report);
+        }
+        let issue_number = application_file.issue_number.parse::<u64>().map_err(|e| {
+            LDNError::New(format!(
+                "Parse issue number: {} to u64 failed. {}",
+                application_file.issue_number, e
+            ))
+        })?;
+        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        gh.add_comment_to_issue(issue_number, &report.format_comment())
+            .await
+            .map_github_err(|e| {
+                LDNError::New(format!("Failed to add comment to the issue: {}", e))
+            })?;
+        Ok(report)
+    }
+
+    fn compliance_check_outcome(
+        mode: &str,
+        client_address: &str,
+        checked: Result<ComplianceReport, LDNError>,
+    ) -> Result<(), LDNError> {
+        let enforce = mode == "enforce";
+        match checked {
+            Ok(report) if enforce && !report.is_compliant() => Err(LDNError::Load(format!(
+                "Client {} does not follow the Fil+ guideline: {}",
+                client_address,
+                report.violations.join("; ")
+            ))),
+            Ok(_) => Ok(()),
+            Err(e) if enforce => Err(e),
+            Err(e) => {
+                log::warn!("Skipping compliance check of {}: {}", client_address, e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the current Fil+ guideline compliance report of the client.
+    pub async fn compliance_report(
+        id: String,
+        owner: String,
+        repo: String,
+    ) -> Result<ComplianceReport, LDNError> {
+        let application_file = Self::load_from_db(id, owner, repo).await?;
+        get_compliance_report(&application_file).await
+    }
+
+    /// Returns why the client has to repeat KYC, if the allocator has a KYC validity period and
+    /// the client's last verification is older than it or was never recorded.
+    async fn kyc_reverification(
+        client_id: &str,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Option<KycReverification>, LDNError> {
+        let allocator = get_allocator(owner, repo)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get allocator from DB: {}", e)))?;
+        let Some(validity_days) = allocator.and_then(|a| a.kyc_validity_days) else {
+            return Ok(None);
+        };
+        let kyc = database::kyc_verifications::get_kyc_verification(client_id, owner, repo)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get KYC verification: {}", e)))?;
+        let Some(kyc) = kyc else {
+            return Ok(Some(KycReverification::NotRecorded));
+        };
+        let expires_at = kyc.verified_at.with_timezone(&Utc) + Duration::days(validity_days.into());
+        if Utc::now() > expires_at {
+            return Ok(Some(KycReverification::Expired(expires_at)));
+        }
+        Ok(None)
+    }
+
+    /// Whether KYC was already requested again, either in the merged application file or in
+    /// an open pull request of the application.
+    async fn kyc_reverification_pending(
+        application_file: &ApplicationFile,
+        owner: &str,
+        repo: &str,
+    ) -> Result<bool, LDNError> {
+        if application_file.lifecycle.state == AppState::KYCRequested {
+            return Ok(true);
+        }
+        let active = Self::active(
+            owner.to_string(),
+            repo.to_string(),
+            Some(application_file.id.clone()),
+        )
+        .await?;
+        Ok(active.iter().any(|app| {
+            app.file.id == application_file.id && app.file.lifecycle.state == AppState::KYCRequested
+        }))
+    }
+
+    /// Opens a pull request moving the application back to KYC. Nothing is done when a
+    /// re-verification is already pending, so retried refills do not pile up pull requests.
+    async fn request_kyc_reverification(
+        content: &ApplicationGithubInfo,
+        application_file: &ApplicationFile,
+        owner: &str,
+        repo: &str,
+        reverification: &KycReverification,
+    ) -> Result<(), LDNError> {
+        if Self::kyc_reverification_pending(application_file, owner, repo).await? {
+            return Ok(());
+        }
+        let app_file = application_file.kyc_request();
+        let parsed_app_file = serde_json::to_string_pretty(&app_file)
+            .map_err(|e| LDNError::Load(format!("Failed to pare into string: {}", e)))?;
+        LDNPullRequest::create_pr_for_existing_application(
+            app_file.id.clone(),
+            parsed_app_file,
+            content.path.clone(),
+            uuidv4::uuid::v4(),
+            content.sha.clone(),
+            owner.to_string(),
+            repo.to_string(),
+            true,
+            app_file.issue_number.clone(),
+            format!("KYC re-verification for {}", app_file.client.name),
+        )
+        .await?;
+
+        let comment = format!(
+            "KYC of client address `{}` {}. Please complete KYC again at {}/?owner={}&repo={}&client={}&issue={} before the next allocation.",
+            app_file.id,
+            reverification,
+            get_env_var_or_default("KYC_URL"),
+            owner,
+            repo,
+            app_file.id,
+            app_file.issue_number,
+        );
+        Self::add_comment_to_issue(
+            app_file.issue_number.clone(),
+            owner.to_string(),
+            repo.to_string(),
+            comment,
+        )
+        .await?;
+        Self::update_issue_labels(
+            app_file.issue_number.clone(),
+            &[AppState::KYCRequested.as_str()],
+            owner.to_string(),
+            repo.to_string(),
+        )
+        .await?;
+        Ok(())
+    }
+
     pub async fn notify_refill(info: NotifyRefillInfo) -> Result<(), LDNError> {
         let label = "Refill needed";
 
@@ -1863,12 +2368,15 @@ impl LDNApplication {
         let issue_number = info.issue_number.parse().map_err(|e| {
             LDNError::Load(format!("Failed to parse issue number to number: {:?}", e))
         })?;
-        let has_label = gh.issue_has_label(issue_number, label).await.map_err(|e| {
-            LDNError::Load(format!(
-                "Failed to check if issue has refill label: {:?}",
-                e
-            ))
-        })?;
+        let has_label = gh
+            .issue_has_label(issue_number, label)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to check if issue has refill label: {:?}",
+                    e
+                ))
+            })?;
         if has_label {
             return Err(LDNError::Load(format!(
                 "'{}' label present - already notified about refill!",
@@ -1947,7 +2455,7 @@ impl LDNApplication {
     ) -> Result<bool, LDNError> {
         let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
 
-        gh.merge_pull_request(pr_number).await.map_err(|e| {
+        gh.merge_pull_request(pr_number).await.map_github_err(|e| {
             LDNError::Load(format!(
                 "Failed to merge pull request {}. Reason: {}",
                 pr_number, e
@@ -2010,17 +2518,20 @@ impl LDNApplication {
             return Ok(false);
         }
 
-        let branch_name = gh.get_branch_name_from_pr(pr_number).await.map_err(|e| {
-            LDNError::Load(format!(
-                "Failed to get branch name from pull request: {}",
-                e
-            ))
-        })?;
+        let branch_name = gh
+            .get_branch_name_from_pr(pr_number)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to get branch name from pull request: {}",
+                    e
+                ))
+            })?;
 
         let file = gh
             .get_file(&files[0].filename, &branch_name)
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get file content: {}", e)))?;
+            .map_github_err(|e| LDNError::Load(format!("Failed to get file content: {}", e)))?;
         let application = LDNApplication::content_items_to_app_file(file)?;
 
         // Check if application is in Submitted state
@@ -2229,11 +2740,11 @@ impl LDNApplication {
                 LDNApplication::load(app_file.id.clone(), owner.clone(), repo.clone()).await?;
             let parsed_app_file = serde_json::to_string_pretty(&app_file)
                 .map_err(|e| LDNError::Load(format!("Failed to pare into string: {}", e)))?;
-            LDNPullRequest::add_commit_to(
+            let parsed_app_file = LDNPullRequest::add_commit_to(
                 ldn_application.file_name.clone(),
                 ldn_application.branch_name.clone(),
                 "Move application back to review".to_string(),
-                parsed_app_file.clone(),
+                parsed_app_file,
                 ldn_application.file_sha.clone(),
                 owner.clone(),
                 repo.clone(),
@@ -2245,7 +2756,7 @@ impl LDNApplication {
             let prs = gh
                 .get_pull_request_by_head(&ldn_application.branch_name)
                 .await
-                .map_err(|e| {
+                .map_github_err(|e| {
                     LDNError::Load(format!("Failed to get pull request by head: {}", e))
                 })?;
 
@@ -2303,24 +2814,27 @@ impl LDNApplication {
             }
         };
 
-        // Commit the changes to the branch
-        LDNPullRequest::add_commit_to(
+        // Commit the changes to the branch, merged with edits made on GitHub meanwhile
+        let file_content = LDNPullRequest::add_commit_to(
             filename.clone(),
             branch_name.clone(),
             commit_message,
-            file_content.clone(),
+            file_content,
             sha.clone(),
             owner.clone(),
             repo.clone(),
         )
         .await?;
+        let committed_application_file = ApplicationFile::from_str(&file_content).map_err(|e| {
+            LDNError::Load(format!("Failed to parse committed application file: {}", e))
+        })?;
 
         // Retrieve and update the pull request
         let prs = self
             .github
             .get_pull_request_by_head(&branch_name)
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request: {}", e)))?;
+            .map_github_err(|e| LDNError::Load(format!("Failed to get pull request: {}", e)))?;
 
         if let Some(pr) = prs.first() {
             database::applications::update_application(
@@ -2341,7 +2855,7 @@ impl LDNApplication {
                 ))
             })?;
 
-            Ok(db_application_file)
+            Ok(committed_application_file)
         } else {
             Err(LDNError::New(
                 "No pull request found for the given branch".to_string(),
@@ -2367,10 +2881,9 @@ impl LDNApplication {
         let app_str = &db_application_model
             .application
             .ok_or(LDNError::Load("Failed to get application".to_string()))?;
-        let mut db_application_file = serde_json::from_str::<ApplicationFile>(&app_str.clone())
-            .map_err(|e| {
-                LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
-            })?;
+        let mut db_application_file = ApplicationFile::from_str(&app_str.clone()).map_err(|e| {
+            LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
+        })?;
         let application_state = db_application_file.lifecycle.state.clone();
 
         if application_state != AppState::ChangesRequested {
@@ -2542,8 +3055,8 @@ impl LDNApplication {
                 "Active change request not found. Please propose change firstly".to_string(),
             ))?;
 
-        let db_application_file = serde_json::from_str::<ApplicationFile>(&db_application_file_str)
-            .map_err(|e| {
+        let db_application_file =
+            ApplicationFile::from_str(&db_application_file_str).map_err(|e| {
                 LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
             })?;
 
@@ -2584,12 +3097,15 @@ impl LDNApplication {
             }
         };
 
-        let branch_name = gh.get_branch_name_from_pr(pr_number).await.map_err(|e| {
-            LDNError::Load(format!(
-                "Failed to get branch name from pull request: {}",
-                e
-            ))
-        })?;
+        let branch_name = gh
+            .get_branch_name_from_pr(pr_number)
+            .await
+            .map_github_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to get branch name from pull request: {}",
+                    e
+                ))
+            })?;
 
         match database::applications::get_application_by_pr_number(
             owner.clone(),
@@ -2651,7 +3167,7 @@ impl LDNApplication {
 
         gh.update_file(&filename, commit_message, &file_content, &branch_name, &sha)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::Load(format!(
                     "Failed to update file in GitHub repo {}/{}. Reason: {} in file {}",
                     gh.owner.clone(),
@@ -2794,7 +3310,7 @@ impl LDNApplication {
                 Ok(false)
             } else {
                 log::warn!("Val Proposal (SSD)- No active request");
-                return Ok(false);
+                Ok(false)
             }
         } else {
             log::info!("Val Proposal (> SSD) - State is greater than StartSignDatacap");
@@ -2891,7 +3407,7 @@ impl LDNApplication {
             let information = "The requested DataCap has been reached for this application. Updates on this issue will no longer be processed. Please create a new application.";
             gh.add_comment_to_issue(application_model.issue_number as u64, information)
                 .await
-                .map_err(|e| {
+                .map_github_err(|e| {
                     LDNError::New(format!(
                         "Error adding comment to issue {} /// {}",
                         application_model.issue_number, e
@@ -2953,7 +3469,9 @@ impl LDNApplication {
         let branch_name = gh
             .get_branch_name_from_pr(application_model.pr_number as u64)
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get branch name from PR: {}", e)))?;
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to get branch name from PR: {}", e))
+            })?;
 
         let path = application_model
             .path
@@ -2963,14 +3481,14 @@ impl LDNApplication {
             .sha
             .clone()
             .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
-        LDNPullRequest::add_commit_to(
+        let file_content = LDNPullRequest::add_commit_to(
             path.clone(),
             branch_name.clone(),
             format!(
                 "Update application from issue #{}",
                 pr_application.issue_number
             ),
-            file_content.clone(),
+            file_content,
             sha.clone(),
             application_model.owner.clone(),
             application_model.repo.clone(),
@@ -2981,7 +3499,7 @@ impl LDNApplication {
             let prs = gh
                 .get_pull_request_by_head(&branch_name)
                 .await
-                .map_err(|e| {
+                .map_github_err(|e| {
                     LDNError::Load(format!("Failed to get pull request by head: {}", e))
                 })?;
 
@@ -3063,7 +3581,7 @@ impl LDNApplication {
             let information = "The requested DataCap has been reached for this application. Updates on this issue will no longer be processed. Please create a new application.";
             gh.add_comment_to_issue(application_model.issue_number as u64, information)
                 .await
-                .map_err(|e| {
+                .map_github_err(|e| {
                     LDNError::New(format!(
                         "Error adding comment to issue {} /// {}",
                         application_model.issue_number, e
@@ -3189,7 +3707,7 @@ impl LDNApplication {
                 LDNError::New(format!("build_remove_ref_request function failed: {}", e))
             })?;
 
-        gh.remove_branch(request).await.map_err(|e| {
+        gh.remove_branch(request).await.map_github_err(|e| {
             LDNError::New(format!("Error deleting branch {} /// {}", branch_name, e))
         })?;
 
@@ -3213,7 +3731,7 @@ impl LDNApplication {
 
         gh.add_comment_to_issue(issue_number, &comment)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Error adding comment to issue {} /// {}",
                     issue_number, e
@@ -3562,7 +4080,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         let num: u64 = issue_number.parse().expect("Not a valid integer");
         gh.add_error_label(num, comment)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Error adding labels to issue {} /// {}",
                     issue_number, e
@@ -3584,7 +4102,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         let new_labels: Vec<String> = new_labels.iter().map(|&s| s.to_string()).collect();
         gh.replace_issue_labels(num, &new_labels)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Error adding labels t to issue {} /// {}",
                     issue_number, e
@@ -3595,7 +4113,12 @@ _The initial issue can be edited in order to solve the request of the verifier.
         Ok(())
     }
 
+    /// Sent as background GitHub work, delayed while the installation is low on budget.
     pub async fn cache_renewal_active(owner: String, repo: String) -> Result<(), LDNError> {
+        in_background(Self::renew_active_applications(owner, repo)).await
+    }
+
+    async fn renew_active_applications(owner: String, repo: String) -> Result<(), LDNError> {
         let active_from_gh: Vec<ApplicationFileWithDate> =
             LDNApplication::active_apps_with_last_update(owner.clone(), repo.clone(), None).await?;
         let active_from_db: Vec<ApplicationModel> =
@@ -3701,7 +4224,12 @@ _The initial issue can be edited in order to solve the request of the verifier.
         Ok(())
     }
 
+    /// Sent as background GitHub work, delayed while the installation is low on budget.
     pub async fn cache_renewal_merged(owner: String, repo: String) -> Result<(), LDNError> {
+        in_background(Self::renew_merged_applications(owner, repo)).await
+    }
+
+    async fn renew_merged_applications(owner: String, repo: String) -> Result<(), LDNError> {
         let merged_from_gh: Vec<ApplicationFileWithDate> =
             LDNApplication::merged_apps_with_last_update(owner.clone(), repo.clone(), None).await?;
         let merged_from_db: Vec<ApplicationModel> =
@@ -3826,10 +4354,9 @@ _The initial issue can be edited in order to solve the request of the verifier.
             .application
             .ok_or(LDNError::Load("Failed to get application".to_string()))?;
 
-        let db_application_file =
-            serde_json::from_str::<ApplicationFile>(app_str).map_err(|e| {
-                LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
-            })?;
+        let db_application_file = ApplicationFile::from_str(app_str).map_err(|e| {
+            LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
+        })?;
 
         if db_application_file.lifecycle.get_state() > AppState::Submitted {
             return Err(LDNError::New(
@@ -3910,10 +4437,10 @@ _The initial issue can be edited in order to solve the request of the verifier.
             .application
             .ok_or(LDNError::Load("Failed to get appliction".to_string()))?;
 
-        let mut db_application_file = serde_json::from_str::<ApplicationFile>(
-            &db_application_file_str.clone(),
-        )
-        .map_err(|e| LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e)))?;
+        let mut db_application_file = ApplicationFile::from_str(&db_application_file_str.clone())
+            .map_err(|e| {
+            LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
+        })?;
 
         db_application_file.lifecycle.state = AppState::AdditionalInfoRequired;
 
@@ -3959,7 +4486,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
                 id
             ))
         })?;
-        let application_file = serde_json::from_str::<ApplicationFile>(&app_str).map_err(|e| {
+        let application_file = ApplicationFile::from_str(&app_str).map_err(|e| {
             LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
         })?;
         if application_file.lifecycle.state != AppState::Submitted {
@@ -4041,7 +4568,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         self.github
             .replace_issue_labels(parsed_issue_number, &["kyc requested".to_string()])
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Error adding labels to issue {} /// {}",
                     issue_number, e
@@ -4066,7 +4593,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
             ))
         })?;
 
-        let application_file = serde_json::from_str::<ApplicationFile>(&app_str).map_err(|e| {
+        let application_file = ApplicationFile::from_str(&app_str).map_err(|e| {
             LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
         })?;
 
@@ -4102,6 +4629,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
             amount_type: info.amount_type,
             owner: app_model.owner,
             repo: app_model.repo,
+            override_tranche_policy: info.override_tranche_policy,
         };
         Self::refill(verifier, refill_info).await?;
         Ok(())
@@ -4121,7 +4649,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
             ))
         })?;
 
-        let application_file = serde_json::from_str::<ApplicationFile>(&app_str).map_err(|e| {
+        let application_file = ApplicationFile::from_str(&app_str).map_err(|e| {
             LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
         })?;
 
@@ -4133,10 +4661,43 @@ _The initial issue can be edited in order to solve the request of the verifier.
         }
 
         let address_from_signature =
-            LDNApplication::verify_kyc_data_and_get_eth_address(&info.message, &info.signature)?;
+            LDNApplication::verify_kyc_data_and_get_eth_address(&info.message, &info.signature)
+                .await?;
 
-        let score = verify_on_gitcoin(&address_from_signature).await?;
-        let application_file = application_file.move_back_to_submit_state();
+        let allocator = get_allocator(owner, repo)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get allocator from DB: {}", e)))?;
+        let identity_verifiers = identity_verifiers_for_allocator(allocator.as_ref())?;
+        let context = IdentityContext {
+            github: Some(&self.github),
+            attestation: info.identity_attestation.as_ref(),
+        };
+        let verification =
+            verify_identity(&identity_verifiers, &address_from_signature, &context).await?;
+        // The message is consumed together with the verification, a failed verification
+        // leaves it usable
+        let message_hash = get_signing_hash(&info.message)?.to_string();
+        let recorded = database::kyc_verifications::upsert_kyc_verification(
+            client_id,
+            owner,
+            repo,
+            &address_from_signature,
+            verification.provider,
+            verification.score,
+            &message_hash,
+            &Self::signed_message_expiry(&info.message)?,
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to record KYC verification: {}", e)))?;
+        if !recorded {
+            return Err(Self::replayed_message(&message_hash));
+        }
+        // Clients re-verifying after their KYC expired already have granted allocations
+        let application_file = if application_file.allocation.0.is_empty() {
+            application_file.move_back_to_submit_state()
+        } else {
+            application_file.move_back_to_granted_state()
+        };
         let parsed_app_file = serde_json::to_string_pretty(&application_file)
             .map_err(|e| LDNError::Load(format!("Failed to pare into string: {}", e)))?;
         database::applications::update_application(
@@ -4169,8 +4730,13 @@ _The initial issue can be edited in order to solve the request of the verifier.
             ))
         })?;
 
-        self.issue_updates_for_kyc_submit(parsed_issue_number, &score, &address_from_signature)
-            .await?;
+        self.issue_updates_for_kyc_submit(
+            parsed_issue_number,
+            &verification,
+            &address_from_signature,
+            &application_file.lifecycle.state,
+        )
+        .await?;
 
         let path = app_model
             .path
@@ -4180,12 +4746,26 @@ _The initial issue can be edited in order to solve the request of the verifier.
             .sha
             .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
 
+        let branch_name = if application_file.lifecycle.state == AppState::Granted {
+            self.github
+                .get_branch_name_from_pr(app_model.pr_number as u64)
+                .await
+                .map_github_err(|e| {
+                    LDNError::Load(format!(
+                        "Failed to get branch name from PR {}: {}",
+                        app_model.pr_number, e
+                    ))
+                })?
+        } else {
+            LDNPullRequest::application_branch_name(&application_file.id)
+        };
+
         self.update_and_commit_application_state(
             application_file.clone(),
             owner.clone(),
             repo.clone(),
             sha,
-            LDNPullRequest::application_branch_name(&application_file.id),
+            branch_name,
             path,
             "KYC submitted".to_string(),
         )
@@ -4196,11 +4776,16 @@ _The initial issue can be edited in order to solve the request of the verifier.
     async fn issue_updates_for_kyc_submit(
         &self,
         issue_number: &u64,
-        score: &f64,
+        verification: &IdentityVerification,
         eth_address: &Address,
+        app_state: &AppState,
     ) -> Result<(), LDNError> {
         let comment = format!(
-            "KYC completed for client address `{}` with Optimism address `{}` and passport score `{}`.", &self.application_id, eth_address, score.round() as i64
+            "KYC completed for client address `{}` with Optimism address `{}` and {} score `{}`.",
+            &self.application_id,
+            eth_address,
+            verification.provider,
+            verification.score.round() as i64
         );
 
         Self::add_comment_to_issue(
@@ -4211,16 +4796,19 @@ _The initial issue can be edited in order to solve the request of the verifier.
         )
         .await?;
 
+        let labels = if *app_state == AppState::Granted {
+            vec![AppState::Granted.as_str().into()]
+        } else {
+            vec![
+                AppState::Submitted.as_str().into(),
+                "waiting for allocator review".into(),
+            ]
+        };
+
         self.github
-            .replace_issue_labels(
-                *issue_number,
-                &[
-                    AppState::Submitted.as_str().into(),
-                    "waiting for allocator review".into(),
-                ],
-            )
+            .replace_issue_labels(*issue_number, &labels)
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Error adding labels to issue {} /// {}",
                     issue_number, e
@@ -4247,7 +4835,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         Ok(current_timestamp < &issued_date_to_datetime)
     }
 
-    fn verify_kyc_data_and_get_eth_address<T: ExpirableSolStruct>(
+    async fn verify_kyc_data_and_get_eth_address<T: ExpirableSolStruct>(
         message: &T,
         signature: &str,
     ) -> Result<Address, LDNError> {
@@ -4266,9 +4854,88 @@ _The initial issue can be edited in order to solve the request of the verifier.
                 message.get_issued_at()
             )));
         }
+        let message_hash = get_signing_hash(message)?.to_string();
+        let consumed = database::consumed_signatures::is_signature_consumed(&message_hash)
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to check consumed signature: {}", e)))?;
+        if consumed {
+            return Err(Self::replayed_message(&message_hash));
+        }
         Ok(address_from_signature)
     }
 
+    fn replayed_message(message_hash: &str) -> LDNError {
+        LDNError::Replay(format!("Message {} has already been used", message_hash))
+    }
+
+    fn signed_message_expiry<T: ExpirableSolStruct>(
+        message: &T,
+    ) -> Result<DateTime<FixedOffset>, LDNError> {
+        DateTime::parse_from_rfc3339(message.get_expires_at())
+            .map_err(|e| LDNError::New(format!("Parse &str to DateTime failed: {e:?}")))
+    }
+
+    /// Stores the signing hash of a verified message so the same message cannot be
+    /// submitted twice within its validity window. Call it once the message was acted upon,
+    /// a failure before that must not cost the client their signature.
+    async fn consume_signed_message<T: ExpirableSolStruct>(
+        message: &T,
+        address_from_signature: &Address,
+    ) -> Result<String, LDNError> {
+        let message_hash = get_signing_hash(message)?.to_string();
+        let consumed = database::consumed_signatures::consume_signature(
+            &message_hash,
+            address_from_signature,
+            &Self::signed_message_expiry(message)?,
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to store consumed signature: {}", e)))?;
+        if !consumed {
+            return Err(Self::replayed_message(&message_hash));
+        }
+        Ok(message_hash)
+    }
+
+    /// Makes a consumed message usable again after the operation it authorized failed.
+    async fn release_signed_message(message_hash: &str) -> Result<(), LDNError> {
+        database::consumed_signatures::release_signature(message_hash)
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to release consumed signature: {}", e)))
+    }
+
+    /// Compares arbitrary application data with stored applications without saving the result.
+    pub async fn check_similarity(info: SimilarityCheckInfo) -> Result<SimilarityReport, LDNError> {
+        let client_address = info.client_address.unwrap_or_default();
+        get_similarity_report(&client_address, &info.application).await
+    }
+
+    /// Re-runs similarity detection with the current content of the application,
+    /// e.g. after the client edited the issue.
+    pub async fn rerun_similarity_detection(
+        id: &str,
+        owner: &str,
+        repo: &str,
+    ) -> Result<SimilarityReport, LDNError> {
+        let application_file =
+            Self::load_from_db(id.to_string(), owner.to_string(), repo.to_string()).await?;
+        let comparable_data = application_file.comparable_data();
+        let issue_number = application_file.issue_number.parse::<u64>().map_err(|e| {
+            LDNError::New(format!(
+                "Parse issue number: {} to u64 failed. {}",
+                application_file.issue_number, e
+            ))
+        })?;
+        let report =
+            detect_similar_applications(id, &comparable_data, owner, repo, &issue_number).await?;
+        create_or_update_comparable_application(id, &comparable_data)
+            .await
+            .map_err(|e| {
+                LDNError::New(format!("Failed to update comparable application: {}", e))
+            })?;
+        add_to_similarity_index(id, &comparable_data).await?;
+        Ok(report)
+    }
+
     pub async fn remove_pending_allocation(
         &self,
         client_id: &str,
@@ -4427,7 +5094,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         self.github
             .replace_issue_labels(issue_number, &[label.into()])
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Error adding labels to issue {} /// {}",
                     issue_number, e
@@ -4450,7 +5117,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
             ))
         })?;
 
-        let application_file = serde_json::from_str::<ApplicationFile>(app_str).map_err(|e| {
+        let application_file = ApplicationFile::from_str(app_str).map_err(|e| {
             LDNError::New(format!("Failed to parse string to ApplicationFile: {}", e))
         })?;
 
@@ -4463,17 +5130,6 @@ _The initial issue can be edited in order to solve the request of the verifier.
 
         Ok(application_file)
     }
-
-    pub fn reached_total_datacap(self) -> Self {
-        let empty = "".to_string();
-
-        LifeCycle {
-            is_active: false,
-            updated_at: Utc::now().to_string(),
-            active_request: Some(empty),
-            ..self
-        }
-    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -4525,7 +5181,7 @@ impl LDNPullRequest {
                 commit: initial_commit,
             })
             .await
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::New(format!(
                     "Application issue {} cannot create merge request /// {}",
                     issue_number, e
@@ -4549,6 +5205,8 @@ impl LDNPullRequest {
         pr_title: String,
     ) -> Result<u64, LDNError> {
         let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        let (file_content, file_sha) =
+            Self::rebase_on_branch(&gh, &file_name, "main", file_content, file_sha).await?;
         let head_hash = gh
             .get_main_branch_sha()
             .await
@@ -4578,7 +5236,9 @@ impl LDNPullRequest {
                 commit: pr_title,
             })
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to get list of pull requests: {}", e)))?;
+            .map_github_err(|e| {
+                LDNError::Load(format!("Failed to get list of pull requests: {}", e))
+            })?;
 
         if should_create_in_db {
             let issue_number = issue_number
@@ -4618,8 +5278,10 @@ impl LDNPullRequest {
         file_sha: String,
         owner: String,
         repo: String,
-    ) -> Result<(), LDNError> {
+    ) -> Result<String, LDNError> {
         let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        let (new_content, file_sha) =
+            Self::rebase_on_branch(&gh, &path, &branch_name, new_content, file_sha).await?;
         gh.update_file_content(
             &path,
             &commit_message,
@@ -4628,8 +5290,67 @@ impl LDNPullRequest {
             &file_sha,
         )
         .await
-        .map_err(|e| LDNError::Load(format!("Failed to add commit: {}", e)))?;
-        Ok(())
+        .map_github_err(|e| LDNError::Load(format!("Failed to add commit: {}", e)))?;
+        Ok(new_content)
+    }
+
+    /// `file_sha` is the blob the new content was derived from. When the file on the branch
+    /// moved on since, the change is merged into it and committed against its current blob.
+    async fn rebase_on_branch(
+        gh: &GithubWrapper,
+        path: &str,
+        branch_name: &str,
+        new_content: String,
+        file_sha: String,
+    ) -> Result<(String, String), LDNError> {
+        let remote = gh
+            .get_file(path, branch_name)
+            .await
+            .map_github_err(|e| LDNError::Load(format!("Failed to get file {}: {}", path, e)))?
+            .take_items()
+            .pop()
+            .ok_or(LDNError::Load(format!(
+                "File {} not found on branch {}",
+                path, branch_name
+            )))?;
+        if remote.sha == file_sha {
+            return Ok((new_content, file_sha));
+        }
+
+        let base = gh
+            .get_blob(&file_sha)
+            .await
+            .map_github_err(|e| LDNError::Load(format!("Failed to get blob {}: {}", file_sha, e)))?
+            .decoded_content();
+        let parse = |content: Option<String>, version: &str| {
+            content
+                .ok_or(LDNError::Load(format!(
+                    "Failed to decode {} version of {}",
+                    version, path
+                )))
+                .and_then(|content| {
+                    ApplicationFile::from_str(&content).map_err(|e| {
+                        LDNError::Load(format!(
+                            "Failed to parse {} version of {}: {}",
+                            version, path, e
+                        ))
+                    })
+                })
+        };
+        let merged = merge_application_files(
+            &parse(base, "cached")?,
+            &parse(Some(new_content), "new")?,
+            &parse(remote.decoded_content(), "remote")?,
+        )?;
+        log::info!(
+            "File {} on branch {} was edited on GitHub, merged the change into blob {}",
+            path,
+            branch_name,
+            remote.sha
+        );
+        let merged = serde_json::to_string_pretty(&merged)
+            .map_err(|e| LDNError::Load(format!("Failed to pare into string: {}", e)))?;
+        Ok((merged, remote.sha))
     }
 
     pub async fn close_pull_request(
@@ -4638,7 +5359,7 @@ impl LDNPullRequest {
         pr_number: u64,
     ) -> Result<(), LDNError> {
         let gh = github_async_new(owner.clone(), repo.clone()).await?;
-        gh.close_pull_request(pr_number).await.map_err(|e| {
+        gh.close_pull_request(pr_number).await.map_github_err(|e| {
             LDNError::New(format!(
                 "Error closing pull request {} /// {}",
                 pr_number, e
@@ -4659,7 +5380,7 @@ impl LDNPullRequest {
             .map_err(|e| {
                 LDNError::New(format!("build_remove_ref_request function failed: {}", e))
             })?;
-        gh.remove_branch(request).await.map_err(|e| {
+        gh.remove_branch(request).await.map_github_err(|e| {
             LDNError::New(format!("Error deleting branch {} /// {}", branch_name, e))
         })?;
         Ok(())
@@ -4761,7 +5482,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_update_app_state_to_submitted_after_kyc() {
-        let mut application_file = ApplicationFile::new(
+        let application_file = ApplicationFile::new(
             "1".into(),
             "adres".into(),
             application::file::Version::Text("1.3".to_string()),
@@ -4823,6 +5544,36 @@ mod tests {
         );
         assert!(!is_from_future.unwrap())
     }
+
+    #[test]
+    fn compliance_check_failures_only_block_when_enforced() {
+        let unavailable = || Err(LDNError::Load("DMOB is unavailable".into()));
+        assert!(
+            LDNApplication::compliance_check_outcome("warn", "f1client", unavailable()).is_ok()
+        );
+        assert!(
+            LDNApplication::compliance_check_outcome("enforce", "f1client", unavailable()).is_err()
+        );
+
+        let report = ComplianceReport {
+            client_address: "f1client".into(),
+            client_id: "f01".into(),
+            created_at: Utc::now(),
+            total_bytes: 0,
+            providers: vec![],
+            declared_replicas: 1,
+            average_replicas: 0.0,
+            locations: vec![],
+            violations: vec!["No DataCap claims found for the client".into()],
+        };
+        assert!(
+            LDNApplication::compliance_check_outcome("warn", "f1client", Ok(report.clone()))
+                .is_ok()
+        );
+        assert!(
+            LDNApplication::compliance_check_outcome("enforce", "f1client", Ok(report)).is_err()
+        );
+    }
 }
 
 // #[cfg(test)]
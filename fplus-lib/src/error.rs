@@ -10,10 +10,19 @@ use actix_web::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::core::application::merge::ApplicationConflict;
+use crate::external_services::github_rate_limit::GithubRateLimited;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum LDNError {
     New(String),
     Load(String),
+    /// A signed message that was already consumed was submitted again
+    Replay(String),
+    /// The GitHub installation ran out of API budget, the operation can be retried later
+    RateLimited(GithubRateLimited),
+    /// The application file was edited on GitHub in fields the operation changes too
+    Conflict(ApplicationConflict),
 }
 
 impl Display for LDNError {
@@ -25,6 +34,15 @@ impl Display for LDNError {
             LDNError::New(e) => {
                 write!(f, "New: {}", e)
             }
+            LDNError::Replay(e) => {
+                write!(f, "Replay: {}", e)
+            }
+            LDNError::RateLimited(e) => {
+                write!(f, "RateLimited: {}", e)
+            }
+            LDNError::Conflict(e) => {
+                write!(f, "Conflict: {}", e)
+            }
         }
     }
 }
@@ -36,6 +54,9 @@ impl MessageBody for LDNError {
         match self {
             LDNError::Load(e) => BodySize::Sized(e.len() as u64),
             LDNError::New(e) => BodySize::Sized(e.len() as u64),
+            LDNError::Replay(e) => BodySize::Sized(e.len() as u64),
+            LDNError::RateLimited(e) => BodySize::Sized(e.to_string().len() as u64),
+            LDNError::Conflict(e) => BodySize::Sized(e.to_string().len() as u64),
         }
     }
 
@@ -46,6 +67,9 @@ impl MessageBody for LDNError {
         match Pin::<&mut LDNError>::into_inner(self) {
             LDNError::Load(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
             LDNError::New(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
+            LDNError::Replay(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
+            LDNError::RateLimited(e) => Poll::Ready(Some(Ok(Bytes::from(e.to_string())))),
+            LDNError::Conflict(e) => Poll::Ready(Some(Ok(Bytes::from(e.to_string())))),
         }
     }
 }
@@ -1,5 +1,8 @@
 use crate::config::get_env_var_or_default;
-use crate::models::dmob::VerifiedClientResponse;
+use crate::models::dmob::{VerifiedClientResponse, VerifiedDeal, VerifiedDealsResponse};
+
+/// Number of deals fetched per page of `get_client_verified_deals`.
+const VERIFIED_DEALS_PAGE_SIZE: u64 = 1000;
 
 pub async fn get_client_allocation(
     address: &str,
@@ -17,3 +20,35 @@ pub async fn get_client_allocation(
         .await?;
     Ok(response)
 }
+
+/// Returns all verified deals and DDO claims made by the client, with any SP.
+pub async fn get_client_verified_deals(
+    client_id: &str,
+) -> Result<Vec<VerifiedDeal>, reqwest::Error> {
+    let api_url = get_env_var_or_default("DMOB_API_URL");
+    let api_key = get_env_var_or_default("DMOB_API_KEY");
+    let client = reqwest::Client::new();
+
+    let mut deals = Vec::new();
+    let mut page = 1;
+    loop {
+        let url = format!(
+            "{}/public/api/getVerifiedDeals/{}?page={}&limit={}",
+            api_url, client_id, page, VERIFIED_DEALS_PAGE_SIZE
+        );
+        let response = client
+            .get(&url)
+            .header("X-api-key", &api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<VerifiedDealsResponse>()
+            .await?;
+        let fetched = response.data.len() as u64;
+        deals.extend(response.data);
+        if fetched < VERIFIED_DEALS_PAGE_SIZE || deals.len() as u64 >= response.count {
+            return Ok(deals);
+        }
+        page += 1;
+    }
+}
@@ -0,0 +1,276 @@
+#[cfg(feature = "semantic")]
+use std::{fs, path::Path};
+use std::{str::FromStr, sync::Arc};
+
+#[cfg(feature = "semantic")]
+use candle_core::{Device, Tensor, D};
+#[cfg(feature = "semantic")]
+use candle_nn::VarBuilder;
+#[cfg(feature = "semantic")]
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+#[cfg(feature = "semantic")]
+use once_cell::sync::OnceCell;
+#[cfg(feature = "semantic")]
+use tokenizers::{Tokenizer, TruncationParams};
+
+use crate::{config::get_env_var_or_default, error::LDNError};
+
+/// Backend used to compare the descriptions of applications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityBackend {
+    /// TF-IDF cosine similarity only
+    Lexical,
+    /// Embedding cosine similarity only
+    Semantic,
+    /// Weighted combination of TF-IDF and embedding similarity
+    Hybrid,
+}
+
+impl FromStr for SimilarityBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lexical" => Ok(Self::Lexical),
+            "semantic" => Ok(Self::Semantic),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => Err(format!("{} is not a valid similarity backend", s)),
+        }
+    }
+}
+
+impl SimilarityBackend {
+    /// Fails for the backends using embeddings if fplus-lib is built without the `semantic`
+    /// feature, so a misconfigured server reports it instead of skipping the comparison.
+    pub fn from_env() -> Result<Self, LDNError> {
+        let backend =
+            Self::from_str(&get_env_var_or_default("SIMILARITY_BACKEND")).map_err(LDNError::New)?;
+        if backend.uses_semantic() && !cfg!(feature = "semantic") {
+            return Err(semantic_feature_disabled());
+        }
+        Ok(backend)
+    }
+
+    pub fn uses_lexical(&self) -> bool {
+        matches!(self, Self::Lexical | Self::Hybrid)
+    }
+
+    pub fn uses_semantic(&self) -> bool {
+        matches!(self, Self::Semantic | Self::Hybrid)
+    }
+}
+
+/// Dimensions of the `comparable_application_embeddings.embedding` column.
+pub const EMBEDDING_DIMENSIONS: usize = 384;
+
+fn semantic_feature_disabled() -> LDNError {
+    LDNError::New(
+        "Semantic similarity requires fplus-lib to be built with the `semantic` feature"
+            .to_string(),
+    )
+}
+
+/// BERT sentence encoder loaded from a sentence-transformers model directory containing
+/// `config.json`, `tokenizer.json` and `model.safetensors`, e.g.
+/// `paraphrase-multilingual-MiniLM-L12-v2` to match translated descriptions. Texts are
+/// embedded as the mean of the token states of the last layer, like sentence-transformers
+/// does for these models, on CPU and without network access.
+#[cfg(feature = "semantic")]
+pub struct EmbeddingModel {
+    name: String,
+    model: BertModel,
+    tokenizer: Tokenizer,
+}
+
+/// Stand-in for the model when fplus-lib is built without the `semantic` feature. It cannot be
+/// constructed, as `get_embedding_model` always fails.
+#[cfg(not(feature = "semantic"))]
+pub struct EmbeddingModel {
+    never: std::convert::Infallible,
+}
+
+#[cfg(feature = "semantic")]
+static EMBEDDING_MODEL: OnceCell<Arc<EmbeddingModel>> = OnceCell::new();
+
+/// Returns the model configured with `EMBEDDING_MODEL_PATH`, loading it on first use.
+#[cfg(feature = "semantic")]
+pub fn get_embedding_model() -> Result<Arc<EmbeddingModel>, LDNError> {
+    EMBEDDING_MODEL
+        .get_or_try_init(|| {
+            let path = get_env_var_or_default("EMBEDDING_MODEL_PATH");
+            if path.is_empty() {
+                return Err(LDNError::New(
+                    "EMBEDDING_MODEL_PATH is required for semantic similarity".to_string(),
+                ));
+            }
+            EmbeddingModel::load(Path::new(&path)).map(Arc::new)
+        })
+        .cloned()
+}
+
+#[cfg(not(feature = "semantic"))]
+pub fn get_embedding_model() -> Result<Arc<EmbeddingModel>, LDNError> {
+    Err(semantic_feature_disabled())
+}
+
+#[cfg(not(feature = "semantic"))]
+impl EmbeddingModel {
+    pub fn name(&self) -> &str {
+        match self.never {}
+    }
+
+    pub async fn embed(self: &Arc<Self>, _text: &str) -> Result<Option<Vec<f32>>, LDNError> {
+        match self.never {}
+    }
+}
+
+#[cfg(feature = "semantic")]
+impl EmbeddingModel {
+    pub fn load(path: &Path) -> Result<Self, LDNError> {
+        let load_error = |file: &str, e: &dyn std::fmt::Display| {
+            LDNError::Load(format!(
+                "Failed to load {} of embedding model {}: {}",
+                file,
+                path.display(),
+                e
+            ))
+        };
+        let config = fs::read_to_string(path.join("config.json"))
+            .map_err(|e| load_error("config.json", &e))?;
+        let config: Config =
+            serde_json::from_str(&config).map_err(|e| load_error("config.json", &e))?;
+        if config.hidden_size != EMBEDDING_DIMENSIONS {
+            return Err(LDNError::Load(format!(
+                "Embedding model {} has {} dimensions, expected {}",
+                path.display(),
+                config.hidden_size,
+                EMBEDDING_DIMENSIONS
+            )));
+        }
+        let mut tokenizer = Tokenizer::from_file(path.join("tokenizer.json"))
+            .map_err(|e| load_error("tokenizer.json", &e))?;
+        tokenizer
+            .with_padding(None)
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..Default::default()
+            }))
+            .map_err(|e| load_error("tokenizer.json", &e))?;
+        // SAFETY: the weights file is not modified while the server is running.
+        let weights = unsafe {
+            VarBuilder::from_mmaped_safetensors(
+                &[path.join("model.safetensors")],
+                DTYPE,
+                &Device::Cpu,
+            )
+        }
+        .map_err(|e| load_error("model.safetensors", &e))?;
+        let model =
+            BertModel::load(weights, &config).map_err(|e| load_error("model.safetensors", &e))?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(Self {
+            name,
+            model,
+            tokenizer,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the unit-length embedding of the text or `None` if it has no tokens. The forward
+    /// pass runs on the blocking thread pool, so it does not stall the async runtime.
+    pub async fn embed(self: &Arc<Self>, text: &str) -> Result<Option<Vec<f32>>, LDNError> {
+        let model = Arc::clone(self);
+        let text = text.to_string();
+        tokio::task::spawn_blocking(move || model.encode(&text))
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to embed application text: {}", e)))?
+    }
+
+    fn encode(&self, text: &str) -> Result<Option<Vec<f32>>, LDNError> {
+        let embed_error = |e: &dyn std::fmt::Display| {
+            LDNError::New(format!("Failed to embed application text: {}", e))
+        };
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        let encoding = self
+            .tokenizer
+            .encode(text, true)
+            .map_err(|e| embed_error(&e))?;
+        let tensor = |values: &[u32]| {
+            Tensor::new(values, &Device::Cpu).and_then(|tensor| tensor.unsqueeze(0))
+        };
+        let input_ids = tensor(encoding.get_ids()).map_err(|e| embed_error(&e))?;
+        let token_type_ids = tensor(encoding.get_type_ids()).map_err(|e| embed_error(&e))?;
+        let attention_mask = tensor(encoding.get_attention_mask()).map_err(|e| embed_error(&e))?;
+        let token_states = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))
+            .map_err(|e| embed_error(&e))?;
+        let embedding = mean_pool(&token_states, &attention_mask)
+            .and_then(|embedding| embedding.squeeze(0)?.to_vec1::<f32>())
+            .map_err(|e| embed_error(&e))?;
+        Ok(Some(embedding))
+    }
+}
+
+#[cfg(feature = "semantic")]
+/// Averages the token states of shape `(batch, tokens, dimensions)` over the tokens that are
+/// not masked out and normalizes the result to unit length.
+fn mean_pool(token_states: &Tensor, attention_mask: &Tensor) -> candle_core::Result<Tensor> {
+    let mask = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+    let sum = token_states.broadcast_mul(&mask)?.sum(1)?;
+    let mean = sum.broadcast_div(&mask.sum(1)?)?;
+    let norm = mean.sqr()?.sum_keepdim(D::Minus1)?.sqrt()?;
+    mean.broadcast_div(&norm)
+}
+
+#[cfg(all(test, feature = "semantic"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_pool_ignores_masked_tokens() {
+        let token_states =
+            Tensor::new(&[[[6.0f32, 0.0], [2.0, 6.0], [100.0, 100.0]]], &Device::Cpu).unwrap();
+        let attention_mask = Tensor::new(&[[1u32, 1, 0]], &Device::Cpu).unwrap();
+        let embedding = mean_pool(&token_states, &attention_mask)
+            .unwrap()
+            .to_vec2::<f32>()
+            .unwrap();
+        let expected = [0.8f32, 0.6];
+        for (value, expected) in embedding[0].iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    /// Requires `EMBEDDING_MODEL_PATH` to point at a multilingual sentence-transformers model.
+    #[test]
+    #[ignore]
+    fn model_embeds_paraphrases_and_translations_close_to_each_other() {
+        let model =
+            EmbeddingModel::load(Path::new(&std::env::var("EMBEDDING_MODEL_PATH").unwrap()))
+                .unwrap();
+        let similarity = |a: &str, b: &str| {
+            let a = model.encode(a).unwrap().unwrap();
+            let b = model.encode(b).unwrap().unwrap();
+            a.iter().zip(&b).map(|(x, y)| x * y).sum::<f32>()
+        };
+        let description = "We archive satellite images of the Amazon rainforest for research.";
+        let paraphrase =
+            "Our project stores pictures of the Amazon jungle taken from orbit for scientists.";
+        let translation =
+            "Archivamos imágenes satelitales de la selva amazónica para investigación.";
+        let unrelated = "A collection of music videos from independent artists.";
+        assert!(similarity(description, paraphrase) > similarity(description, unrelated));
+        assert!(similarity(description, translation) > similarity(description, unrelated));
+        assert!(similarity(description, translation) > 0.7);
+        assert!(model.encode("   ").unwrap().is_none());
+    }
+}
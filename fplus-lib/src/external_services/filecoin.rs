@@ -2,7 +2,10 @@ use serde_json::json;
 
 use crate::{
     config::get_env_var_or_default,
+    error::LDNError,
     models::filecoin::{
+        MsigGetPendingResponse, StateActorCodeCIDsResponse, StateLookupIDResponse,
+        StateMinerPowerResponse, StateNetworkVersionResponse, StateReadActorStateResponse,
         StateReadStateResponse, StateVerifiedClientStatusResponse, StateVerifierStatusResponse,
     },
 };
@@ -30,6 +33,175 @@ pub async fn state_get_state(
     Ok(response)
 }
 
+pub async fn state_read_actor_state(
+    actor_address: &str,
+) -> Result<StateReadActorStateResponse, reqwest::Error> {
+    let node_url = get_env_var_or_default("GLIF_NODE_URL");
+
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateReadState",
+        "params": [actor_address, null],
+        "id": 1
+    });
+
+    let request = client.post(&node_url).json(&body);
+
+    let response = request
+        .send()
+        .await?
+        .json::<StateReadActorStateResponse>()
+        .await?;
+    Ok(response)
+}
+
+pub async fn state_miner_power(
+    miner_address: &str,
+) -> Result<StateMinerPowerResponse, reqwest::Error> {
+    let node_url = get_env_var_or_default("GLIF_NODE_URL");
+
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateMinerPower",
+        "params": [miner_address, null],
+        "id": 1
+    });
+
+    let request = client.post(&node_url).json(&body);
+
+    let response = request
+        .send()
+        .await?
+        .json::<StateMinerPowerResponse>()
+        .await?;
+    Ok(response)
+}
+
+pub async fn state_lookup_id(address: &str) -> Result<StateLookupIDResponse, reqwest::Error> {
+    let node_url = get_env_var_or_default("GLIF_NODE_URL");
+
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateLookupID",
+        "params": [address, null],
+        "id": 1
+    });
+
+    let request = client.post(&node_url).json(&body);
+
+    let response = request
+        .send()
+        .await?
+        .json::<StateLookupIDResponse>()
+        .await?;
+    Ok(response)
+}
+
+pub async fn state_network_version() -> Result<StateNetworkVersionResponse, reqwest::Error> {
+    let node_url = get_env_var_or_default("GLIF_NODE_URL");
+
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateNetworkVersion",
+        "params": [null],
+        "id": 1
+    });
+
+    let request = client.post(&node_url).json(&body);
+
+    let response = request
+        .send()
+        .await?
+        .json::<StateNetworkVersionResponse>()
+        .await?;
+    Ok(response)
+}
+
+pub async fn state_actor_code_cids(
+    network_version: u32,
+) -> Result<StateActorCodeCIDsResponse, reqwest::Error> {
+    let node_url = get_env_var_or_default("GLIF_NODE_URL");
+
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateActorCodeCIDs",
+        "params": [network_version],
+        "id": 1
+    });
+
+    let request = client.post(&node_url).json(&body);
+
+    let response = request
+        .send()
+        .await?
+        .json::<StateActorCodeCIDsResponse>()
+        .await?;
+    Ok(response)
+}
+
+/// Returns the code CID of the built-in actor in the current network version, e.g. of
+/// `storageminer` or `multisig`.
+pub async fn get_builtin_actor_code(actor_name: &str) -> Result<String, LDNError> {
+    let network_version = state_network_version()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get network version: {}", e)))?;
+    if let Some(error) = network_version.error {
+        return Err(LDNError::Load(format!(
+            "Node failed to get network version: {}",
+            error
+        )));
+    }
+    let network_version = network_version.result.ok_or(LDNError::Load(
+        "Node returned no network version".to_string(),
+    ))?;
+    let code_cids = state_actor_code_cids(network_version)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get actor code CIDs: {}", e)))?;
+    if let Some(error) = code_cids.error {
+        return Err(LDNError::Load(format!(
+            "Node failed to get actor code CIDs: {}",
+            error
+        )));
+    }
+    code_cids
+        .result
+        .unwrap_or_default()
+        .remove(actor_name)
+        .map(|code| code.code)
+        .ok_or(LDNError::Load(format!(
+            "Node returned no code CID of the {} actor",
+            actor_name
+        )))
+}
+
+pub async fn msig_get_pending(
+    msig_address: &str,
+) -> Result<MsigGetPendingResponse, reqwest::Error> {
+    let node_url = get_env_var_or_default("GLIF_NODE_URL");
+
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.MsigGetPending",
+        "params": [msig_address, null],
+        "id": 1
+    });
+
+    let request = client.post(&node_url).json(&body);
+
+    let response = request
+        .send()
+        .await?
+        .json::<MsigGetPendingResponse>()
+        .await?;
+    Ok(response)
+}
+
 pub async fn get_multisig_threshold_for_actor(actor_address: &str) -> Result<u64, reqwest::Error> {
     let actor_state_info = state_get_state(actor_address).await?;
     Ok(actor_state_info.result.state.num_approvals_threshold)
@@ -7,9 +7,9 @@ use hyper_rustls::HttpsConnectorBuilder;
 use octocrab::auth::AppAuth;
 use octocrab::models::issues::{Comment, Issue};
 use octocrab::models::pulls::PullRequest;
-use octocrab::models::repos::{Branch, ContentItems, FileDeletion, FileUpdate};
-use octocrab::models::{IssueState, Label};
-use octocrab::params::{pulls::State as PullState, State};
+use octocrab::models::repos::{Branch, ContentItems, FileDeletion, FileUpdate, RepoCommit};
+use octocrab::models::{CommentId, IssueState, Label};
+use octocrab::params::{pulls::Sort as PullSort, pulls::State as PullState, Direction, State};
 use octocrab::service::middleware::base_uri::BaseUriLayer;
 use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
 use octocrab::{AuthState, Error as OctocrabError, GitHubError, Octocrab, OctocrabBuilder, Page};
@@ -20,6 +20,8 @@ use std::sync::Arc;
 use crate::config::get_env_var_or_default;
 use crate::core::application::file::AppState;
 use crate::error::LDNError;
+use crate::external_services::github_cache::GithubCacheLayer;
+use crate::external_services::github_rate_limit::{rate_limited, GithubRateLimitLayer};
 
 const GITHUB_API_URL: &str = "https://api.github.com";
 
@@ -73,6 +75,46 @@ pub struct GithubWrapper {
     pub repo: String,
 }
 
+/// File changed between two commits, as listed by the compare API.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ComparedFile {
+    pub filename: String,
+    /// `added`, `removed`, `modified`, `renamed`, `copied`, `changed` or `unchanged`
+    pub status: String,
+    pub sha: Option<String>,
+    pub previous_filename: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CommitComparison {
+    /// `ahead` when the head descends from the base, `diverged` or `behind` otherwise
+    pub status: String,
+    #[serde(default)]
+    pub files: Vec<ComparedFile>,
+}
+
+/// The compare API lists at most this many changed files.
+pub const COMPARE_MAX_FILES: usize = 300;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GitBlob {
+    pub sha: String,
+    /// Base64 with line breaks, unless `encoding` says otherwise
+    pub content: String,
+    pub encoding: String,
+}
+
+impl GitBlob {
+    pub fn decoded_content(&self) -> Option<String> {
+        match self.encoding.as_str() {
+            "base64" => base64::decode(self.content.replace('\n', ""))
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok()),
+            _ => Some(self.content.clone()),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct CommitData {
     commit: Commit,
@@ -88,6 +130,26 @@ struct Author {
     name: String,
 }
 
+pub trait GithubResultExt<T> {
+    /// Like `map_err`, but rate limit failures come back as the retryable
+    /// `LDNError::RateLimited` instead of being mapped by `op`.
+    fn map_github_err<F>(self, op: F) -> Result<T, LDNError>
+    where
+        F: FnOnce(OctocrabError) -> LDNError;
+}
+
+impl<T> GithubResultExt<T> for Result<T, OctocrabError> {
+    fn map_github_err<F>(self, op: F) -> Result<T, LDNError>
+    where
+        F: FnOnce(OctocrabError) -> LDNError,
+    {
+        self.map_err(|e| match rate_limited(&e) {
+            Some(limited) => LDNError::RateLimited(limited.clone()),
+            None => op(e),
+        })
+    }
+}
+
 pub async fn github_async_new(owner: String, repo: String) -> Result<GithubWrapper, LDNError> {
     let allocator = get_allocator(owner.as_str(), repo.as_str())
         .await
@@ -142,6 +204,10 @@ impl GithubWrapper {
                 USER_AGENT,
                 header_value,
             )])))
+            .with_layer(&GithubRateLimitLayer::new(
+                installation_id.unwrap_or_default() as u64,
+            ))
+            .with_layer(&GithubCacheLayer)
             .with_auth(AuthState::App(AppAuth {
                 app_id: app_id.into(),
                 key,
@@ -199,6 +265,37 @@ impl GithubWrapper {
         Ok(iid)
     }
 
+    /// First comment of the issue whose body contains `marker`, e.g. an HTML comment the
+    /// backend added to a comment it edits on every run.
+    pub async fn find_issue_comment(
+        &self,
+        number: u64,
+        marker: &str,
+    ) -> Result<Option<Comment>, OctocrabError> {
+        let page = self
+            .inner
+            .issues(&self.owner, &self.repo)
+            .list_comments(number)
+            .per_page(100)
+            .send()
+            .await?;
+        let comments = self.inner.all_pages(page).await?;
+        Ok(comments
+            .into_iter()
+            .find(|comment| comment.body.as_deref().is_some_and(|b| b.contains(marker))))
+    }
+
+    pub async fn update_issue_comment(
+        &self,
+        comment_id: CommentId,
+        body: &str,
+    ) -> Result<Comment, OctocrabError> {
+        self.inner
+            .issues(&self.owner, &self.repo)
+            .update_comment(comment_id, body)
+            .await
+    }
+
     pub async fn replace_issue_labels(
         &self,
         number: u64,
@@ -286,6 +383,62 @@ impl GithubWrapper {
         Ok(iid.into_iter().collect())
     }
 
+    /// Pull requests in any state updated after `since`, most recently updated first.
+    pub async fn list_pull_requests_updated_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<PullRequest>, OctocrabError> {
+        let mut page = self
+            .inner
+            .pulls(&self.owner, &self.repo)
+            .list()
+            .state(State::All)
+            .sort(PullSort::Updated)
+            .direction(Direction::Descending)
+            .per_page(100)
+            .send()
+            .await?;
+        let mut pull_requests = Vec::new();
+        loop {
+            let next = page.next.take();
+            let items = page.take_items();
+            let reached_since = items
+                .iter()
+                .any(|pr| pr.updated_at.is_some_and(|updated_at| updated_at <= since));
+            pull_requests.extend(
+                items
+                    .into_iter()
+                    .filter(|pr| pr.updated_at.is_some_and(|updated_at| updated_at > since)),
+            );
+            if reached_since {
+                break;
+            }
+            match self.inner.get_page::<PullRequest>(&next).await? {
+                Some(next_page) => page = next_page,
+                None => break,
+            }
+        }
+        Ok(pull_requests)
+    }
+
+    pub async fn compare_commits(
+        &self,
+        base: &str,
+        head: &str,
+    ) -> Result<CommitComparison, OctocrabError> {
+        let route = format!(
+            "/repos/{}/{}/compare/{}...{}",
+            self.owner, self.repo, base, head
+        );
+        self.inner.get(route, None::<&()>).await
+    }
+
+    /// Fetches a file by its blob SHA, which stays readable after the branch moved on.
+    pub async fn get_blob(&self, sha: &str) -> Result<GitBlob, OctocrabError> {
+        let route = format!("/repos/{}/{}/git/blobs/{}", self.owner, self.repo, sha);
+        self.inner.get(route, None::<&()>).await
+    }
+
     pub async fn create_commit_in_branch(
         &self,
         branch_name: String,
@@ -720,10 +873,9 @@ impl GithubWrapper {
             .build_request::<String>(request, None)
             .map_err(|e| LDNError::Load(format!("Failed to build request: {}", e)))?;
 
-        let mut response =
-            self.inner.execute(request).await.map_err(|e| {
-                LDNError::Load(format!("Error fetching last commit author: {:?}", e))
-            })?;
+        let mut response = self.inner.execute(request).await.map_github_err(|e| {
+            LDNError::Load(format!("Error fetching last commit author: {:?}", e))
+        })?;
 
         let response_body = response.body_mut();
         let body = hyper::body::to_bytes(response_body)
@@ -779,6 +931,26 @@ impl GithubWrapper {
         Ok(contents_items)
     }
 
+    pub async fn list_file_commits(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+        path: &str,
+        limit: u8,
+    ) -> Result<Vec<RepoCommit>, OctocrabError> {
+        let mut commits = self
+            .inner
+            .repos(owner, repo)
+            .list_commits()
+            .sha(branch)
+            .path(path)
+            .per_page(limit)
+            .send()
+            .await?;
+        Ok(commits.take_items())
+    }
+
     pub async fn filplus_ignored_files(&self, branch: &str) -> Result<Vec<String>, LDNError> {
         self.get_file(".filplusignore", branch)
             .await
@@ -789,7 +961,7 @@ impl GithubWrapper {
                 } if message == "Not Found" => Ok(ContentItems { items: vec![] }),
                 _ => Err(e),
             })
-            .map_err(|e| {
+            .map_github_err(|e| {
                 LDNError::Load(format!(
                     "Failed to load .filplusignore file from repository {}/{}: {}",
                     self.owner, self.repo, e
@@ -807,8 +979,49 @@ impl GithubWrapper {
             })
     }
 
+    /// Contents of the YAML files in `dir` of the main branch, e.g. the issue forms in
+    /// `.github/ISSUE_TEMPLATE`. A missing directory has no files.
+    pub async fn yaml_files(&self, dir: &str) -> Result<Vec<String>, LDNError> {
+        let not_found_is_empty = |e| match e {
+            octocrab::Error::GitHub {
+                source: GitHubError { message, .. },
+                ..
+            } if message == "Not Found" => Ok(ContentItems { items: vec![] }),
+            _ => Err(e),
+        };
+        let load_error = |path: &str, e| {
+            LDNError::Load(format!(
+                "Failed to load {} from repository {}/{}: {}",
+                path, self.owner, self.repo, e
+            ))
+        };
+        let entries = self
+            .get_files(dir)
+            .await
+            .or_else(not_found_is_empty)
+            .map_github_err(|e| load_error(dir, e))?
+            .take_items();
+        let mut files = Vec::new();
+        for entry in entries {
+            if entry.r#type != "file"
+                || !(entry.name.ends_with(".yml") || entry.name.ends_with(".yaml"))
+            {
+                continue;
+            }
+            let content = self
+                .get_files(&entry.path)
+                .await
+                .map_github_err(|e| load_error(&entry.path, e))?
+                .take_items()
+                .pop()
+                .and_then(|file| file.decoded_content());
+            files.extend(content);
+        }
+        Ok(files)
+    }
+
     pub async fn get_issue_reporter_handle(&self, issue_number: &u64) -> Result<String, LDNError> {
-        let issue = self.list_issue(*issue_number).await.map_err(|e| {
+        let issue = self.list_issue(*issue_number).await.map_github_err(|e| {
             LDNError::Load(format!(
                 "Failed to retrieve issue {} from GitHub: {}",
                 issue_number, e
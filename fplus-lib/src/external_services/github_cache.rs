@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use http::header::{ACCEPT, ETAG, IF_NONE_MATCH};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri};
+use hyper::body::{Body, Bytes};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tower::{BoxError, Layer, Service};
+use utoipa::ToSchema;
+
+use crate::config::get_env_var_or_default;
+
+/// Response of the GitHub API kept with its ETag to be revalidated with `If-None-Match`.
+/// A `304 Not Modified` answer does not count against the rate limit.
+struct CachedResponse {
+    /// `/repos/{owner}/{repo}/` prefix of the URL
+    repo: String,
+    etag: HeaderValue,
+    headers: HeaderMap,
+    body: Bytes,
+    stored_at: Instant,
+    validated_at: Instant,
+    last_used: u64,
+}
+
+impl CachedResponse {
+    fn size(&self, key: &str) -> usize {
+        key.len()
+            + self.body.len()
+            + self
+                .headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>()
+    }
+
+    fn to_response(&self) -> Response<Body> {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct GithubCacheStats {
+    /// Responses served from the cache without contacting GitHub
+    pub hits: u64,
+    /// Responses GitHub confirmed unchanged with `304 Not Modified`
+    pub revalidations: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    /// Share of cacheable requests answered from the cache, revalidated or not
+    pub hit_ratio: f64,
+}
+
+enum Lookup {
+    Fresh(Response<Body>),
+    Stale(HeaderValue),
+    Miss,
+}
+
+/// GET responses of repository endpoints keyed by `Accept` header and URL, evicted least
+/// recently used first once `max_bytes` is reached and dropped `max_age` after being stored.
+/// Responses younger than `fresh_for` are served without revalidation.
+struct ResponseCache {
+    entries: HashMap<String, CachedResponse>,
+    bytes: usize,
+    tick: u64,
+    max_bytes: usize,
+    fresh_for: Duration,
+    max_age: Duration,
+    stats: GithubCacheStats,
+}
+
+impl ResponseCache {
+    fn new(max_bytes: usize, fresh_for: Duration, max_age: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            bytes: 0,
+            tick: 0,
+            max_bytes,
+            fresh_for,
+            max_age,
+            stats: GithubCacheStats::default(),
+        }
+    }
+
+    fn from_env() -> Self {
+        let var = |key: &str| get_env_var_or_default(key).parse::<u64>().unwrap_or(0);
+        Self::new(
+            var("GITHUB_CACHE_MAX_BYTES") as usize,
+            Duration::from_secs(var("GITHUB_CACHE_FRESH_SECS")),
+            Duration::from_secs(var("GITHUB_CACHE_MAX_AGE_SECS")),
+        )
+    }
+
+    fn lookup(&mut self, key: &str, now: Instant) -> Lookup {
+        self.tick += 1;
+        let expired = match self.entries.get_mut(key) {
+            None => {
+                self.stats.misses += 1;
+                return Lookup::Miss;
+            }
+            Some(entry) if now.duration_since(entry.stored_at) < self.max_age => {
+                entry.last_used = self.tick;
+                if now.duration_since(entry.validated_at) < self.fresh_for {
+                    self.stats.hits += 1;
+                    return Lookup::Fresh(entry.to_response());
+                }
+                return Lookup::Stale(entry.etag.clone());
+            }
+            Some(_) => true,
+        };
+        if expired {
+            self.remove(key);
+        }
+        self.stats.misses += 1;
+        Lookup::Miss
+    }
+
+    /// Marks an entry as confirmed unchanged by GitHub.
+    fn revalidated(&mut self, key: &str, now: Instant) -> Option<Response<Body>> {
+        let entry = self.entries.get_mut(key)?;
+        entry.validated_at = now;
+        self.stats.revalidations += 1;
+        Some(entry.to_response())
+    }
+
+    /// The request was sent with the ETag of an entry but GitHub answered with new content.
+    fn changed(&mut self) {
+        self.stats.misses += 1;
+    }
+
+    fn store(&mut self, key: String, response: CachedResponse) {
+        self.remove(&key);
+        self.tick += 1;
+        let entry = CachedResponse {
+            last_used: self.tick,
+            ..response
+        };
+        let size = entry.size(&key);
+        if size > self.max_bytes {
+            return;
+        }
+        while self.bytes + size > self.max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+        self.bytes += size;
+        self.entries.insert(key, entry);
+    }
+
+    /// Drops every response of a repository, e.g. `/repos/owner/repo/`.
+    fn invalidate(&mut self, repo: &str) {
+        let keys: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.repo == repo)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            self.remove(&key);
+            self.stats.invalidations += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.size(key);
+        }
+    }
+
+    fn stats(&self) -> GithubCacheStats {
+        let answered = self.stats.hits + self.stats.revalidations;
+        let total = answered + self.stats.misses;
+        GithubCacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            hit_ratio: if total == 0 {
+                0.0
+            } else {
+                answered as f64 / total as f64
+            },
+            ..self.stats.clone()
+        }
+    }
+}
+
+static GITHUB_CACHE: OnceCell<Mutex<ResponseCache>> = OnceCell::new();
+
+fn with_cache<T>(f: impl FnOnce(&mut ResponseCache) -> T) -> T {
+    let cache = GITHUB_CACHE.get_or_init(|| Mutex::new(ResponseCache::from_env()));
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut cache)
+}
+
+pub fn github_cache_stats() -> GithubCacheStats {
+    with_cache(|cache| cache.stats())
+}
+
+/// `/repos/{owner}/{repo}/` prefix of a repository endpoint.
+fn repo_path(uri: &Uri) -> Option<String> {
+    let path = uri.path();
+    let start = path.find("/repos/")?;
+    let mut segments = path[start + "/repos/".len()..].split('/');
+    let owner = segments.next().filter(|owner| !owner.is_empty())?;
+    let repo = segments.next().filter(|repo| !repo.is_empty())?;
+    // Owner and repository names are case insensitive
+    Some(format!("/repos/{}/{}/", owner, repo).to_lowercase())
+}
+
+fn cache_key(request: &Request<String>) -> String {
+    let accept = request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .unwrap_or_default();
+    format!("{} {}", accept, request.uri())
+}
+
+/// Repository of a cacheable request. Only GETs of repository endpoints are cached, other
+/// endpoints depend on the installation the request is authenticated as.
+fn cacheable_repo(request: &Request<String>) -> Option<String> {
+    if request.method() != Method::GET || request.headers().contains_key(IF_NONE_MATCH) {
+        return None;
+    }
+    repo_path(request.uri())
+}
+
+fn clone_request(request: &Request<String>) -> Request<String> {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+/// Layer of the `GithubWrapper` service stack caching GitHub responses by their ETag. Writes
+/// to a repository through the same stack invalidate its cached responses.
+#[derive(Clone, Debug, Default)]
+pub struct GithubCacheLayer;
+
+impl<S> Layer<S> for GithubCacheLayer {
+    type Service = GithubCache<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GithubCache { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GithubCache<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<String>> for GithubCache<S>
+where
+    S: Service<Request<String>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: Request<String>) -> Self::Future {
+        // The ready service is used for this call, a clone stays for the next one
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            let Some(repo) = cacheable_repo(&request) else {
+                let write_to = (request.method() != Method::GET)
+                    .then(|| repo_path(request.uri()))
+                    .flatten();
+                let response = inner.call(request).await.map_err(Into::into)?;
+                if let Some(repo_path) = write_to {
+                    with_cache(|cache| cache.invalidate(&repo_path));
+                }
+                return Ok(response);
+            };
+            let key = cache_key(&request);
+
+            let original = match with_cache(|cache| cache.lookup(&key, Instant::now())) {
+                Lookup::Fresh(response) => return Ok(response),
+                Lookup::Stale(etag) => {
+                    let original = clone_request(&request);
+                    request.headers_mut().insert(IF_NONE_MATCH, etag);
+                    Some(original)
+                }
+                Lookup::Miss => None,
+            };
+            let mut response = inner.call(request).await.map_err(Into::into)?;
+            if let Some(original) = original {
+                if response.status() == StatusCode::NOT_MODIFIED {
+                    if let Some(cached) =
+                        with_cache(|cache| cache.revalidated(&key, Instant::now()))
+                    {
+                        return Ok(cached);
+                    }
+                    // Evicted while waiting for GitHub, ask again for the content
+                    inner = inner.clone();
+                    futures::future::poll_fn(|cx| inner.poll_ready(cx))
+                        .await
+                        .map_err(Into::into)?;
+                    response = inner.call(original).await.map_err(Into::into)?;
+                } else {
+                    with_cache(|cache| cache.changed());
+                }
+            }
+
+            let etag = match response.headers().get(ETAG) {
+                Some(etag) if response.status() == StatusCode::OK => etag.clone(),
+                _ => return Ok(response),
+            };
+            let (parts, body) = response.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let now = Instant::now();
+            let cached = CachedResponse {
+                repo,
+                etag,
+                headers: parts.headers.clone(),
+                body: body.clone(),
+                stored_at: now,
+                validated_at: now,
+                last_used: 0,
+            };
+            with_cache(|cache| cache.store(key, cached));
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = " /repos/owner/repo/pulls";
+
+    fn cache() -> ResponseCache {
+        ResponseCache::new(1000, Duration::from_secs(10), Duration::from_secs(100))
+    }
+
+    fn store(cache: &mut ResponseCache, key: &str, body: &str, now: Instant) {
+        let uri: Uri = key.trim().parse().unwrap();
+        cache.store(
+            key.to_string(),
+            CachedResponse {
+                repo: repo_path(&uri).unwrap(),
+                etag: HeaderValue::from_static("\"etag\""),
+                headers: HeaderMap::new(),
+                body: Bytes::from(body.to_string()),
+                stored_at: now,
+                validated_at: now,
+                last_used: 0,
+            },
+        );
+    }
+
+    #[test]
+    fn responses_are_fresh_then_revalidated_then_expired() {
+        let mut cache = cache();
+        let now = Instant::now();
+        assert!(matches!(cache.lookup(KEY, now), Lookup::Miss));
+        store(&mut cache, KEY, "[]", now);
+        assert!(matches!(cache.lookup(KEY, now), Lookup::Fresh(_)));
+        let later = now + Duration::from_secs(20);
+        assert!(matches!(cache.lookup(KEY, later), Lookup::Stale(_)));
+        assert!(cache.revalidated(KEY, later).is_some());
+        assert!(matches!(cache.lookup(KEY, later), Lookup::Fresh(_)));
+        let expired = now + Duration::from_secs(200);
+        assert!(matches!(cache.lookup(KEY, expired), Lookup::Miss));
+        assert_eq!(cache.bytes, 0);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.revalidations, stats.misses), (2, 1, 2));
+        assert_eq!(stats.hit_ratio, 0.6);
+    }
+
+    #[test]
+    fn least_recently_used_responses_are_evicted() {
+        let mut cache = cache();
+        let now = Instant::now();
+        let body = "x".repeat(400);
+        store(&mut cache, " /repos/o/r/a", &body, now);
+        store(&mut cache, " /repos/o/r/b", &body, now);
+        cache.lookup(" /repos/o/r/a", now);
+        store(&mut cache, " /repos/o/r/c", &body, now);
+        assert!(cache.entries.contains_key(" /repos/o/r/a"));
+        assert!(!cache.entries.contains_key(" /repos/o/r/b"));
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.bytes <= cache.max_bytes);
+    }
+
+    #[test]
+    fn writes_invalidate_the_repository() {
+        let mut cache = cache();
+        let now = Instant::now();
+        store(&mut cache, KEY, "[]", now);
+        store(&mut cache, " /repos/owner/repo-2/pulls", "[]", now);
+        let write: Uri = "https://api.github.com/repos/owner/repo/contents/file.json"
+            .parse()
+            .unwrap();
+        cache.invalidate(&repo_path(&write).unwrap());
+        assert!(!cache.entries.contains_key(KEY));
+        assert_eq!(cache.entries.len(), 1);
+    }
+
+    type SentRequests = std::sync::Arc<Mutex<Vec<(Method, Option<HeaderValue>)>>>;
+
+    /// GitHub answering `304` when the request carries the current ETag.
+    #[derive(Clone, Default)]
+    struct MockGithub {
+        requests: SentRequests,
+    }
+
+    impl Service<Request<String>> for MockGithub {
+        type Response = Response<Body>;
+        type Error = BoxError;
+        type Future = futures::future::Ready<Result<Response<Body>, BoxError>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<String>) -> Self::Future {
+            let if_none_match = request.headers().get(IF_NONE_MATCH).cloned();
+            let mut requests = self.requests.lock().unwrap();
+            requests.push((request.method().clone(), if_none_match.clone()));
+            let writes = requests.iter().filter(|(m, _)| m != Method::GET).count();
+            let etag = HeaderValue::from_str(&format!("\"{}\"", writes)).unwrap();
+            let response = if if_none_match.as_ref() == Some(&etag) {
+                Response::builder()
+                    .status(StatusCode::NOT_MODIFIED)
+                    .body(Body::empty())
+            } else {
+                Response::builder()
+                    .header(ETAG, etag)
+                    .body(Body::from(format!("version {}", writes)))
+            };
+            futures::future::ready(Ok(response.unwrap()))
+        }
+    }
+
+    async fn send(service: &mut GithubCache<MockGithub>, method: Method) -> String {
+        let request = Request::builder()
+            .method(method)
+            .uri("https://api.github.com/repos/cache-test/repo/contents/file.json")
+            .body(String::new())
+            .unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_are_revalidated_and_writes_invalidate() {
+        let github = MockGithub::default();
+        let mut service = GithubCacheLayer.layer(github.clone());
+        assert_eq!(send(&mut service, Method::GET).await, "version 0");
+        assert_eq!(send(&mut service, Method::GET).await, "version 0");
+        send(&mut service, Method::PUT).await;
+        assert_eq!(send(&mut service, Method::GET).await, "version 1");
+
+        let etags: Vec<Option<HeaderValue>> = github
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, etag)| etag.clone())
+            .collect();
+        assert_eq!(
+            etags,
+            vec![None, Some(HeaderValue::from_static("\"0\"")), None, None]
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::RETRY_AFTER;
+use http::{HeaderMap, Request, Response, StatusCode};
+use hyper::body::Body;
+use octocrab::Error as OctocrabError;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tower::{BoxError, Layer, Service};
+use utoipa::ToSchema;
+
+use crate::config::get_env_var_or_default;
+
+const LIMIT_HEADER: &str = "x-ratelimit-limit";
+const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+const RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// Error returned instead of sending a request when the installation ran out of GitHub API
+/// budget. The request can be retried once `retry_after` seconds have passed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct GithubRateLimited {
+    /// `0` for requests authenticated as the GitHub App itself
+    pub installation_id: u64,
+    /// Unix timestamp at which GitHub resets the budget
+    pub reset_at: u64,
+    pub retry_after: u64,
+}
+
+impl Display for GithubRateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GitHub rate limit of installation {} exhausted, retry in {} seconds",
+            self.installation_id, self.retry_after
+        )
+    }
+}
+
+impl std::error::Error for GithubRateLimited {}
+
+/// Rate limit failure behind an error of `GithubWrapper`.
+pub fn rate_limited(error: &OctocrabError) -> Option<&GithubRateLimited> {
+    match error {
+        OctocrabError::Service { source, .. } => source.downcast_ref::<GithubRateLimited>(),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestPriority {
+    /// Part of an application lifecycle step, sent while any budget is left
+    Urgent,
+    /// Maintenance work which can wait for the budget to reset, e.g. cache renewals
+    Background,
+}
+
+tokio::task_local! {
+    static PRIORITY: RequestPriority;
+}
+
+/// Runs `work` with its GitHub requests sent as `RequestPriority::Background`. They are delayed
+/// until the budget resets once the installation is down to its reserve, so that lifecycle
+/// steps are not left half done by a rate limit.
+pub async fn in_background<F: Future>(work: F) -> F::Output {
+    PRIORITY.scope(RequestPriority::Background, work).await
+}
+
+fn current_priority() -> RequestPriority {
+    PRIORITY
+        .try_with(|priority| *priority)
+        .unwrap_or(RequestPriority::Urgent)
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct GithubRateLimitBudget {
+    pub installation_id: u64,
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix timestamp at which GitHub resets the budget
+    pub reset_at: u64,
+    /// Background requests held back until the reset
+    pub delayed: u64,
+    /// Requests refused with `GithubRateLimited`
+    pub rejected: u64,
+}
+
+enum Admission {
+    Send,
+    Wait(Duration),
+    Reject(GithubRateLimited),
+}
+
+/// Budgets of the installations as last reported by GitHub, counted down locally between
+/// responses. Background requests keep `reserve` requests free for urgent ones and wait at
+/// most `max_wait` for a reset.
+struct RateLimits {
+    budgets: HashMap<u64, GithubRateLimitBudget>,
+    reserve: u64,
+    max_wait: Duration,
+}
+
+impl RateLimits {
+    fn new(reserve: u64, max_wait: Duration) -> Self {
+        Self {
+            budgets: HashMap::new(),
+            reserve,
+            max_wait,
+        }
+    }
+
+    fn from_env() -> Self {
+        let var = |key: &str| get_env_var_or_default(key).parse::<u64>().unwrap_or(0);
+        Self::new(
+            var("GITHUB_RATE_LIMIT_RESERVE"),
+            Duration::from_secs(var("GITHUB_RATE_LIMIT_MAX_WAIT_SECS")),
+        )
+    }
+
+    fn admit(&mut self, installation_id: u64, priority: RequestPriority, now: u64) -> Admission {
+        let Some(budget) = self.budgets.get_mut(&installation_id) else {
+            return Admission::Send;
+        };
+        if now >= budget.reset_at {
+            // The window is over, the response reports the new budget
+            return Admission::Send;
+        }
+        let floor = match priority {
+            RequestPriority::Urgent => 0,
+            RequestPriority::Background => self.reserve,
+        };
+        if budget.remaining > floor {
+            budget.remaining -= 1;
+            return Admission::Send;
+        }
+        let wait = budget.reset_at - now;
+        if priority == RequestPriority::Background && Duration::from_secs(wait) <= self.max_wait {
+            budget.delayed += 1;
+            return Admission::Wait(Duration::from_secs(wait));
+        }
+        budget.rejected += 1;
+        Admission::Reject(GithubRateLimited {
+            installation_id,
+            reset_at: budget.reset_at,
+            retry_after: wait,
+        })
+    }
+
+    /// Records the budget reported in the headers of a response. Returns the failure when
+    /// GitHub refused the request because of a primary or secondary rate limit.
+    fn update(
+        &mut self,
+        installation_id: u64,
+        status: StatusCode,
+        headers: &HeaderMap,
+        now: u64,
+    ) -> Option<GithubRateLimited> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        let budget = self
+            .budgets
+            .entry(installation_id)
+            .or_insert_with(|| GithubRateLimitBudget {
+                installation_id,
+                ..Default::default()
+            });
+        if let (Some(remaining), Some(reset_at)) = (header(REMAINING_HEADER), header(RESET_HEADER))
+        {
+            budget.limit = header(LIMIT_HEADER).unwrap_or(budget.limit);
+            budget.remaining = remaining;
+            budget.reset_at = reset_at;
+        }
+
+        if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+            return None;
+        }
+        let reset_at = match header(RETRY_AFTER.as_str()) {
+            Some(retry_after) => now + retry_after,
+            None if header(REMAINING_HEADER) == Some(0) => budget.reset_at.max(now),
+            // Forbidden for another reason than the rate limit
+            None => return None,
+        };
+        budget.remaining = 0;
+        budget.reset_at = reset_at;
+        budget.rejected += 1;
+        Some(GithubRateLimited {
+            installation_id,
+            reset_at,
+            retry_after: reset_at - now,
+        })
+    }
+
+    fn budgets(&self) -> Vec<GithubRateLimitBudget> {
+        let mut budgets: Vec<GithubRateLimitBudget> = self.budgets.values().cloned().collect();
+        budgets.sort_by_key(|budget| budget.installation_id);
+        budgets
+    }
+}
+
+static GITHUB_RATE_LIMITS: OnceCell<Mutex<RateLimits>> = OnceCell::new();
+
+fn with_rate_limits<T>(f: impl FnOnce(&mut RateLimits) -> T) -> T {
+    let limits = GITHUB_RATE_LIMITS.get_or_init(|| Mutex::new(RateLimits::from_env()));
+    let mut limits = limits.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut limits)
+}
+
+pub fn github_rate_limit_budgets() -> Vec<GithubRateLimitBudget> {
+    with_rate_limits(|limits| limits.budgets())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+/// Layer of the `GithubWrapper` service stack keeping the API budget of an installation.
+/// Requests of the GitHub App itself, such as installation token requests, are not counted.
+#[derive(Clone, Debug, Default)]
+pub struct GithubRateLimitLayer {
+    installation_id: u64,
+}
+
+impl GithubRateLimitLayer {
+    pub fn new(installation_id: u64) -> Self {
+        Self { installation_id }
+    }
+}
+
+impl<S> Layer<S> for GithubRateLimitLayer {
+    type Service = GithubRateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GithubRateLimit {
+            inner,
+            installation_id: self.installation_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GithubRateLimit<S> {
+    inner: S,
+    installation_id: u64,
+}
+
+impl<S> Service<Request<String>> for GithubRateLimit<S>
+where
+    S: Service<Request<String>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let installation_id = self.installation_id;
+        Box::pin(async move {
+            if request.uri().path().starts_with("/app/") {
+                return inner.call(request).await.map_err(Into::into);
+            }
+            // Read when polled, the priority is scoped to the task awaiting the request
+            let priority = current_priority();
+            loop {
+                let admission =
+                    with_rate_limits(|limits| limits.admit(installation_id, priority, unix_now()));
+                match admission {
+                    Admission::Send => break,
+                    Admission::Wait(wait) => {
+                        log::info!(
+                            "GitHub budget of installation {} is low, delaying background request for {}s",
+                            installation_id,
+                            wait.as_secs()
+                        );
+                        tokio::time::sleep(wait).await;
+                    }
+                    Admission::Reject(limited) => return Err(limited.into()),
+                }
+            }
+
+            let response = inner.call(request).await.map_err(Into::into)?;
+            let limited = with_rate_limits(|limits| {
+                limits.update(
+                    installation_id,
+                    response.status(),
+                    response.headers(),
+                    unix_now(),
+                )
+            });
+            match limited {
+                Some(limited) => Err(limited.into()),
+                None => Ok(response),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn headers(remaining: u64, reset_at: u64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(LIMIT_HEADER, HeaderValue::from(5000u64));
+        headers.insert(REMAINING_HEADER, HeaderValue::from(remaining));
+        headers.insert(RESET_HEADER, HeaderValue::from(reset_at));
+        headers
+    }
+
+    #[test]
+    fn background_requests_keep_the_reserve_for_urgent_ones() {
+        let mut limits = RateLimits::new(1, Duration::from_secs(60));
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Background, NOW),
+            Admission::Send
+        ));
+        limits.update(1, StatusCode::OK, &headers(1, NOW + 30), NOW);
+
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Background, NOW),
+            Admission::Wait(wait) if wait == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Urgent, NOW),
+            Admission::Send
+        ));
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Urgent, NOW),
+            Admission::Reject(GithubRateLimited {
+                retry_after: 30,
+                ..
+            })
+        ));
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Urgent, NOW + 30),
+            Admission::Send
+        ));
+        // Other installations have their own budget
+        assert!(matches!(
+            limits.admit(2, RequestPriority::Urgent, NOW),
+            Admission::Send
+        ));
+
+        let budget = &limits.budgets()[0];
+        assert_eq!(
+            (budget.remaining, budget.delayed, budget.rejected),
+            (0, 1, 1)
+        );
+    }
+
+    #[test]
+    fn background_requests_are_rejected_when_the_reset_is_too_far() {
+        let mut limits = RateLimits::new(0, Duration::from_secs(60));
+        limits.update(1, StatusCode::OK, &headers(0, NOW + 600), NOW);
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Background, NOW),
+            Admission::Reject(GithubRateLimited { reset_at, .. }) if reset_at == NOW + 600
+        ));
+    }
+
+    #[test]
+    fn rate_limit_responses_are_reported() {
+        let mut limits = RateLimits::new(0, Duration::from_secs(60));
+        let limited = limits.update(1, StatusCode::FORBIDDEN, &headers(0, NOW + 100), NOW);
+        assert_eq!(
+            limited,
+            Some(GithubRateLimited {
+                installation_id: 1,
+                reset_at: NOW + 100,
+                retry_after: 100,
+            })
+        );
+
+        let mut secondary = headers(10, NOW + 100);
+        secondary.insert(RETRY_AFTER, HeaderValue::from(20u64));
+        let limited = limits.update(1, StatusCode::TOO_MANY_REQUESTS, &secondary, NOW);
+        assert_eq!(limited.map(|limited| limited.retry_after), Some(20));
+        assert!(matches!(
+            limits.admit(1, RequestPriority::Urgent, NOW),
+            Admission::Reject(_)
+        ));
+
+        assert_eq!(
+            limits.update(2, StatusCode::FORBIDDEN, &headers(10, NOW + 100), NOW),
+            None
+        );
+    }
+}
@@ -1,5 +1,8 @@
 pub mod blockchain;
 pub mod dmob;
+pub mod embeddings;
 pub mod filecoin;
 pub mod github;
+pub mod github_cache;
+pub mod github_rate_limit;
 pub mod similarity_detection;
@@ -1,25 +1,226 @@
-use super::github::github_async_new;
+use super::{
+    embeddings::{get_embedding_model, SimilarityBackend},
+    github::github_async_new,
+};
 use crate::{
     config::get_env_var_or_default, core::application::file::ApplicationFile, error::LDNError,
 };
+use chrono::{DateTime, Utc};
 use fplus_database::{
     database::{
         applications::get_distinct_applications_by_clients_addresses,
-        comparable_applications::get_comparable_applications,
+        comparable_application_embeddings,
+        comparable_applications::{
+            create_or_update_comparable_application, get_all_comparable_applications,
+            get_comparable_applications, get_comparable_applications_by_addresses,
+        },
+        similarity_index,
+        similarity_reports::create_similarity_report,
     },
     models::comparable_applications::ApplicationComparableData,
 };
-use ndarray::Array1;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     str::FromStr,
 };
 use strsim::levenshtein;
 
-#[derive(Debug, Clone)]
-pub struct Document {
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityFeature {
+    ProjectDescription,
+    StoredDataDescription,
+    ProjectAndStoredDataDescription,
+    DataSetSample,
+    DataOwnerName,
+    SharedStorageProviders,
+    SharedWebsite,
+    SharedSocialMedia,
+    SharedAddress,
+}
+
+const NGRAM_SIZE: usize = 3;
+
+const INDEXED_FEATURES: [SimilarityFeature; 3] = [
+    SimilarityFeature::ProjectDescription,
+    SimilarityFeature::StoredDataDescription,
+    SimilarityFeature::ProjectAndStoredDataDescription,
+];
+
+impl SimilarityFeature {
+    fn index_field(&self) -> &'static str {
+        match *self {
+            SimilarityFeature::ProjectDescription => "project_desc",
+            SimilarityFeature::StoredDataDescription => "stored_data_desc",
+            SimilarityFeature::ProjectAndStoredDataDescription => "project_and_stored_data_desc",
+            SimilarityFeature::DataSetSample => "data_set_sample",
+            SimilarityFeature::DataOwnerName => "data_owner_name",
+            SimilarityFeature::SharedStorageProviders => "storage_providers",
+            SimilarityFeature::SharedWebsite => "website",
+            SimilarityFeature::SharedSocialMedia => "social_media",
+            SimilarityFeature::SharedAddress => "addresses",
+        }
+    }
+
+    fn indexed_text(&self, comparable_data: &ApplicationComparableData) -> Option<String> {
+        match *self {
+            SimilarityFeature::ProjectDescription => Some(comparable_data.project_desc.clone()),
+            SimilarityFeature::StoredDataDescription => {
+                Some(comparable_data.stored_data_desc.clone())
+            }
+            SimilarityFeature::ProjectAndStoredDataDescription => {
+                Some(comparable_data.project_desc.clone() + &comparable_data.stored_data_desc)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match *self {
+            SimilarityFeature::ProjectDescription => "Similar project description",
+            SimilarityFeature::StoredDataDescription => "Similar stored data description",
+            SimilarityFeature::ProjectAndStoredDataDescription => {
+                "Similar project and stored data description"
+            }
+            SimilarityFeature::DataSetSample => "Similar data set sample",
+            SimilarityFeature::DataOwnerName => "The same data owner name",
+            SimilarityFeature::SharedStorageProviders => "Shared storage providers",
+            SimilarityFeature::SharedWebsite => "The same website",
+            SimilarityFeature::SharedSocialMedia => "The same social media handle",
+            SimilarityFeature::SharedAddress => "Reused multisig or contract address",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FeatureScore {
+    pub feature: SimilarityFeature,
+    pub score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimilarityMatch {
     pub client_address: String,
-    pub text: String,
+    pub client_name: String,
+    pub owner: String,
+    pub repo: String,
+    pub issue_number: i64,
+    pub features: Vec<FeatureScore>,
+}
+
+impl SimilarityMatch {
+    fn has_feature(&self, feature: SimilarityFeature) -> bool {
+        self.features.iter().any(|f| f.feature == feature)
+    }
+
+    fn score(&self, feature: SimilarityFeature) -> Option<f64> {
+        self.features
+            .iter()
+            .find(|f| f.feature == feature)
+            .map(|f| f.score)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimilarityReport {
+    pub client_address: String,
+    pub created_at: DateTime<Utc>,
+    pub matches: Vec<SimilarityMatch>,
+}
+
+/// Weights of the linked client signals. A signal with weight 0 is not reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedClientWeights {
+    pub sp_overlap_threshold: f64,
+    pub sp_overlap: f64,
+    pub handle: f64,
+    pub address: f64,
+}
+
+impl LinkedClientWeights {
+    fn is_enabled(&self, feature: SimilarityFeature) -> bool {
+        let weight = match feature {
+            SimilarityFeature::SharedStorageProviders => self.sp_overlap,
+            SimilarityFeature::SharedWebsite | SimilarityFeature::SharedSocialMedia => self.handle,
+            SimilarityFeature::SharedAddress => self.address,
+            _ => 0.0,
+        };
+        weight > 0.0
+    }
+
+    pub fn from_env() -> Result<Self, LDNError> {
+        let parse = |key: &str| {
+            get_env_var_or_default(key)
+                .parse::<f64>()
+                .map_err(|e| LDNError::New(format!("Parse {} to f64 failed: {}", key, e)))
+        };
+        Ok(Self {
+            sp_overlap_threshold: parse("LINKED_CLIENT_SP_OVERLAP_THRESHOLD")?,
+            sp_overlap: parse("LINKED_CLIENT_SP_OVERLAP_WEIGHT")?,
+            handle: parse("LINKED_CLIENT_HANDLE_WEIGHT")?,
+            address: parse("LINKED_CLIENT_ADDRESS_WEIGHT")?,
+        })
+    }
+}
+
+/// How lexical and semantic scores of the description features are combined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarityScoring {
+    pub backend: SimilarityBackend,
+    pub tfidf_threshold: f64,
+    pub embedding_threshold: f64,
+    pub semantic_weight: f64,
+}
+
+impl SimilarityScoring {
+    pub fn from_env() -> Result<Self, LDNError> {
+        let parse = |key: &str| {
+            get_env_var_or_default(key)
+                .parse::<f64>()
+                .map_err(|e| LDNError::New(format!("Parse {} to f64 failed: {}", key, e)))
+        };
+        Ok(Self {
+            backend: SimilarityBackend::from_env()?,
+            tfidf_threshold: parse("TFIDF_THRESHOLD")?,
+            embedding_threshold: parse("EMBEDDING_SIMILARITY_THRESHOLD")?,
+            semantic_weight: parse("SEMANTIC_SCORE_WEIGHT")?,
+        })
+    }
+
+    /// Keeps the documents passing the threshold of any enabled backend. In hybrid mode
+    /// the score is the weighted sum of both scores, a missing score counting as 0.
+    pub fn combine(
+        &self,
+        lexical: Vec<(String, f64)>,
+        semantic: Vec<(String, f64)>,
+    ) -> Vec<(String, f64)> {
+        let mut scores: HashMap<String, (f64, f64)> = HashMap::new();
+        for (address, score) in lexical {
+            scores.entry(address).or_default().0 = score;
+        }
+        for (address, score) in semantic {
+            scores.entry(address).or_default().1 = score;
+        }
+        let mut combined: Vec<(String, f64)> = scores
+            .into_iter()
+            .filter_map(|(address, (lexical, semantic))| {
+                let lexical_match = self.backend.uses_lexical() && lexical > self.tfidf_threshold;
+                let semantic_match =
+                    self.backend.uses_semantic() && semantic > self.embedding_threshold;
+                let score = match self.backend {
+                    SimilarityBackend::Lexical => lexical,
+                    SimilarityBackend::Semantic => semantic,
+                    SimilarityBackend::Hybrid => {
+                        (1.0 - self.semantic_weight) * lexical + self.semantic_weight * semantic
+                    }
+                };
+                (lexical_match || semantic_match).then_some((address, score))
+            })
+            .collect();
+        combined.sort_by(|a, b| b.1.total_cmp(&a.1));
+        combined
+    }
 }
 
 type Owner = String;
@@ -29,146 +230,183 @@ type Similarities = Vec<String>;
 type RepoSimilarities = HashMap<(Owner, Repo), Vec<(ClientAddress, Similarities)>>;
 type SortedRepoSimilarities = Vec<((Owner, Repo), Vec<(ClientAddress, Similarities)>)>;
 
+/// Runs similarity detection for the application, stores the report and posts it on the issue.
 pub async fn detect_similar_applications(
     client_address: &str,
     comparable_data: &ApplicationComparableData,
     owner: &str,
     repo: &str,
     issue_number: &u64,
-) -> Result<(), LDNError> {
-    let comparable_applications = get_comparable_applications().await.map_err(|e| {
-        LDNError::New(format!(
-            "Failed to get comparable applications from database: {}",
-            e
-        ))
-    })?;
-
-    let mut projects_descriptions = Vec::new();
-    projects_descriptions.push(Document {
-        client_address: client_address.to_string(),
-        text: comparable_data.project_desc.clone(),
-    });
+) -> Result<SimilarityReport, LDNError> {
+    let report = get_similarity_report(client_address, comparable_data).await?;
+    let serialized_report = serde_json::to_value(&report)
+        .map_err(|e| LDNError::New(format!("Failed to serialize similarity report: {}", e)))?;
+    create_similarity_report(client_address, owner, repo, serialized_report)
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to store similarity report: {}", e)))?;
 
-    let mut stored_data_descriptions = Vec::new();
-    stored_data_descriptions.push(Document {
-        client_address: client_address.to_string(),
-        text: comparable_data.stored_data_desc.clone(),
-    });
+    let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+    gh.add_comment_to_issue(*issue_number, &format_report_comment(&report))
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to get add comment to the issue: {}", e)))?;
+    Ok(report)
+}
 
-    let mut projects_and_stored_data_descriptions = Vec::new();
-    projects_and_stored_data_descriptions.push(Document {
-        client_address: client_address.to_string(),
-        text: comparable_data.project_desc.clone() + &comparable_data.stored_data_desc.clone(),
-    });
+/// Compares the application with all stored comparable applications except itself.
+pub async fn get_similarity_report(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<SimilarityReport, LDNError> {
+    let linked_client_weights = LinkedClientWeights::from_env()?;
+    let mut features_by_address =
+        get_linked_clients(client_address, comparable_data, &linked_client_weights).await?;
 
-    let mut data_set_samples = Vec::new();
-    data_set_samples.push(Document {
-        client_address: client_address.to_string(),
-        text: comparable_data.data_set_sample.clone(),
-    });
+    let scoring = SimilarityScoring::from_env()?;
+    let top_k = get_env_var_or_default("SIMILARITY_TOP_K")
+        .parse::<usize>()
+        .map_err(|e| LDNError::New(format!("Parse similarity top k to usize failed: {}", e)))?;
 
-    let mut existing_data_owner_name = Vec::new();
-    for app in comparable_applications.iter() {
-        projects_descriptions.push(Document {
-            client_address: app.client_address.clone(),
-            text: app.application.project_desc.clone(),
-        });
-        stored_data_descriptions.push(Document {
-            client_address: app.client_address.clone(),
-            text: app.application.stored_data_desc.clone(),
-        });
-        projects_and_stored_data_descriptions.push(Document {
-            client_address: app.client_address.clone(),
-            text: app.application.project_desc.clone() + &app.application.stored_data_desc.clone(),
-        });
-        data_set_samples.push(Document {
-            client_address: app.client_address.clone(),
-            text: app.application.data_set_sample.clone(),
-        });
-        if comparable_data.data_owner_name == app.application.data_owner_name {
-            existing_data_owner_name.push(app.client_address.clone());
+    for feature in INDEXED_FEATURES {
+        let Some(text) = feature.indexed_text(comparable_data) else {
+            continue;
+        };
+        let lexical = if scoring.backend.uses_lexical() {
+            get_top_k_similar_texts(feature, client_address, &text, top_k).await?
+        } else {
+            vec![]
+        };
+        let semantic = if scoring.backend.uses_semantic() {
+            get_top_k_similar_embeddings(feature, client_address, &text, top_k).await?
+        } else {
+            vec![]
+        };
+        for (address, score) in scoring.combine(lexical, semantic) {
+            features_by_address
+                .entry(address)
+                .or_default()
+                .push(FeatureScore { feature, score });
+        }
+    }
+    for (feature, similar) in [
+        (
+            SimilarityFeature::DataSetSample,
+            get_similar_data_set_samples(client_address, &comparable_data.data_set_sample).await?,
+        ),
+        (
+            SimilarityFeature::DataOwnerName,
+            get_same_data_owner_names(client_address, &comparable_data.data_owner_name).await?,
+        ),
+    ] {
+        for (address, score) in similar {
+            features_by_address
+                .entry(address)
+                .or_default()
+                .push(FeatureScore { feature, score });
         }
     }
-    let similar_project_desciptions = get_similar_texts_tfidf(&projects_descriptions)?;
-    let similar_stored_data_desciptions = get_similar_texts_tfidf(&stored_data_descriptions)?;
-    let similar_project_and_stored_data_desciptions =
-        get_similar_texts_tfidf(&projects_and_stored_data_descriptions)?;
-    let similar_data_set_sample = get_similar_texts_levenshtein(&data_set_samples)?;
 
-    let unique_addresses: HashSet<String> = similar_project_desciptions
-        .clone()
-        .into_iter()
-        .chain(similar_stored_data_desciptions.clone().into_iter())
-        .chain(
-            similar_project_and_stored_data_desciptions
-                .clone()
-                .into_iter(),
-        )
-        .chain(similar_data_set_sample.clone().into_iter())
-        .chain(existing_data_owner_name.clone().into_iter())
-        .collect();
+    let mut matches = Vec::new();
+    if !features_by_address.is_empty() {
+        let unique_addresses: Vec<String> = features_by_address.keys().cloned().collect();
+        let applications = get_distinct_applications_by_clients_addresses(unique_addresses)
+            .await
+            .map_err(|e| {
+                LDNError::New(format!("Failed to get applications from database: {}", e))
+            })?;
 
-    let unique_addresses: Vec<String> = unique_addresses.into_iter().collect();
-    let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        for app_model in applications {
+            let Some(features) = features_by_address.remove(&app_model.id) else {
+                continue;
+            };
+            let app_str = app_model.application.ok_or_else(|| {
+                LDNError::Load(format!(
+                    "Application {} does not have an application field",
+                    app_model.id
+                ))
+            })?;
 
-    if unique_addresses.is_empty() {
-        let comment = "## Similarity Report\n\nNo similar applications found for the issue";
-        gh.add_comment_to_issue(*issue_number, comment)
-            .await
-            .map_err(|e| LDNError::New(format!("Failed to get add comment to the issue: {}", e)))?;
-        return Ok(());
+            let application = ApplicationFile::from_str(&app_str).map_err(|e| {
+                LDNError::Load(format!("Failed to parse application file from DB: {}", e))
+            })?;
+
+            matches.push(SimilarityMatch {
+                client_address: app_model.id,
+                client_name: application.client.name,
+                owner: app_model.owner,
+                repo: app_model.repo,
+                issue_number: app_model.issue_number,
+                features,
+            });
+        }
     }
 
-    let applications = get_distinct_applications_by_clients_addresses(unique_addresses)
-        .await
-        .map_err(|e| LDNError::New(format!("Failed to get applications from database: {}", e)))?;
+    Ok(SimilarityReport {
+        client_address: client_address.to_string(),
+        created_at: Utc::now(),
+        matches,
+    })
+}
 
+pub fn format_report_comment(report: &SimilarityReport) -> String {
     let mut repo_similarities: RepoSimilarities = HashMap::new();
 
-    for app_model in applications {
-        let app_str = app_model.application.ok_or_else(|| {
-            LDNError::Load(format!(
-                "Application {} does not have an application field",
-                app_model.id
-            ))
-        })?;
-
-        let application = ApplicationFile::from_str(&app_str).map_err(|e| {
-            LDNError::Load(format!("Failed to parse application file from DB: {}", e))
-        })?;
-
+    for similar_application in report.matches.iter() {
         let similar_application_link = format!(
             "[#{} - {}](https://allocator.tech/application/{}/{}/{})",
-            app_model.issue_number,
-            application.client.name,
-            app_model.owner,
-            app_model.repo,
-            app_model.id
+            similar_application.issue_number,
+            similar_application.client_name,
+            similar_application.owner,
+            similar_application.repo,
+            similar_application.client_address
         );
 
-        let repo_key = (app_model.owner.clone(), app_model.repo.clone());
-        let entry = repo_similarities.entry(repo_key).or_default();
         let mut similarities = Vec::new();
-
-        if similar_project_and_stored_data_desciptions.contains(&app_model.id) {
-            similarities.push("Similar project and stored data description".to_string());
-        } else if similar_project_desciptions.contains(&app_model.id) {
-            similarities.push("Similar project description".to_string());
-        } else if similar_stored_data_desciptions.contains(&app_model.id) {
-            similarities.push("Similar stored data description".to_string());
+        // Only the strongest of the overlapping description features is reported
+        for feature in [
+            SimilarityFeature::ProjectAndStoredDataDescription,
+            SimilarityFeature::ProjectDescription,
+            SimilarityFeature::StoredDataDescription,
+        ] {
+            if let Some(score) = similar_application.score(feature) {
+                similarities.push(format!("{} ({:.2})", feature.as_str(), score));
+                break;
+            }
         }
-        if similar_data_set_sample.contains(&app_model.id) {
-            similarities.push("Similar data set sample".to_string());
+        if let Some(score) = similar_application.score(SimilarityFeature::SharedStorageProviders) {
+            similarities.push(format!(
+                "{} ({:.2})",
+                SimilarityFeature::SharedStorageProviders.as_str(),
+                score
+            ));
         }
-        if existing_data_owner_name.contains(&app_model.id) {
-            similarities.push("The same data owner name".to_string());
+        for feature in [
+            SimilarityFeature::DataSetSample,
+            SimilarityFeature::DataOwnerName,
+            SimilarityFeature::SharedWebsite,
+            SimilarityFeature::SharedSocialMedia,
+            SimilarityFeature::SharedAddress,
+        ] {
+            if similar_application.has_feature(feature) {
+                similarities.push(feature.as_str().to_string());
+            }
         }
 
         if !similarities.is_empty() {
-            entry.push((similar_application_link, similarities));
+            let repo_key = (
+                similar_application.owner.clone(),
+                similar_application.repo.clone(),
+            );
+            repo_similarities
+                .entry(repo_key)
+                .or_default()
+                .push((similar_application_link, similarities));
         }
     }
 
+    if repo_similarities.is_empty() {
+        return "## Similarity Report\n\nNo similar applications found for the issue".to_string();
+    }
+
     let mut sorted_results: SortedRepoSimilarities = repo_similarities.into_iter().collect();
     sorted_results.sort_by(|owner_repo, similarities| {
         similarities
@@ -179,49 +417,621 @@ pub async fn detect_similar_applications(
             .cmp(&owner_repo.1.iter().map(|(_, sim)| sim.len()).sum::<usize>())
     });
 
-    let comment = format!(
+    format!(
         "## Similarity Report\n\nThis application is similar to the following applications:\n\n{}",
         format_comment(&sorted_results)
-    );
-    gh.add_comment_to_issue(*issue_number, &comment)
+    )
+}
+
+/// Adds the application to the persistent inverted index used by the TF-IDF comparison
+/// and, when the semantic backend is enabled, stores the embeddings of its descriptions.
+pub async fn add_to_similarity_index(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<(), LDNError> {
+    add_to_linked_client_index(client_address, comparable_data).await?;
+    if !is_indexable(comparable_data) {
+        return Ok(());
+    }
+    add_to_lexical_index(client_address, comparable_data).await?;
+    if SimilarityBackend::from_env()?.uses_semantic() {
+        add_embeddings(client_address, comparable_data).await?;
+    }
+    Ok(())
+}
+
+/// Only raw term counts are stored: TF-IDF weights depend on the whole corpus and are
+/// computed when querying.
+async fn add_to_lexical_index(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<(), LDNError> {
+    for feature in INDEXED_FEATURES {
+        let Some(text) = feature.indexed_text(comparable_data) else {
+            continue;
+        };
+        let (term_counts, token_count) = count_terms(&text);
+        similarity_index::index_document(
+            feature.index_field(),
+            client_address,
+            &term_counts,
+            token_count,
+        )
         .await
-        .map_err(|e| LDNError::New(format!("Failed to get add comment to the issue: {}", e)))?;
+        .map_err(|e| LDNError::New(format!("Failed to index application: {}", e)))?;
+    }
+    add_to_fuzzy_index(client_address, comparable_data).await
+}
+
+/// Indexes the character n-grams of the data set sample, used to preselect candidates for
+/// the Levenshtein comparison, and the data owner name, which is matched exactly.
+async fn add_to_fuzzy_index(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<(), LDNError> {
+    let ngrams: HashMap<String, i32> = char_ngrams(&comparable_data.data_set_sample)
+        .into_iter()
+        .map(|(ngram, count)| (ngram, count as i32))
+        .collect();
+    let ngram_count = ngrams.values().sum();
+    let data_owner_names: HashMap<String, i32> = Some(comparable_data.data_owner_name.clone())
+        .filter(|name| !name.is_empty())
+        .map(|name| (name, 1))
+        .into_iter()
+        .collect();
+    let data_owner_name_count = data_owner_names.len() as i32;
+    for (feature, term_counts, token_count) in [
+        (SimilarityFeature::DataSetSample, ngrams, ngram_count),
+        (
+            SimilarityFeature::DataOwnerName,
+            data_owner_names,
+            data_owner_name_count,
+        ),
+    ] {
+        similarity_index::index_document(
+            feature.index_field(),
+            client_address,
+            &term_counts,
+            token_count,
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to index application: {}", e)))?;
+    }
     Ok(())
 }
 
-fn get_similar_texts_tfidf(documents: &[Document]) -> Result<Vec<String>, LDNError> {
-    let tokenized_documents: Vec<Vec<String>> = documents
-        .iter()
-        .map(|doc| tfidf_summarizer::tokenize(&doc.text))
+/// Indexes the SPs, handles and addresses of the application, so applications sharing them
+/// are found by key. Every application is indexed, whether its descriptions are or not.
+pub async fn add_to_linked_client_index(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<(), LDNError> {
+    for (feature, terms) in linked_client_terms(comparable_data) {
+        let term_counts: HashMap<String, i32> = terms.into_iter().map(|term| (term, 1)).collect();
+        similarity_index::index_document(
+            feature.index_field(),
+            client_address,
+            &term_counts,
+            term_counts.len() as i32,
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to index application: {}", e)))?;
+    }
+    Ok(())
+}
+
+async fn add_embeddings(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+) -> Result<(), LDNError> {
+    let model = get_embedding_model()?;
+    for feature in INDEXED_FEATURES {
+        let Some(text) = feature.indexed_text(comparable_data) else {
+            continue;
+        };
+        let Some(embedding) = model.embed(&text).await? else {
+            continue;
+        };
+        comparable_application_embeddings::upsert_embedding(
+            client_address,
+            feature.index_field(),
+            model.name(),
+            embedding,
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to store application embedding: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Indexes all comparable applications if the index, or the embeddings of the configured
+/// model, have not been built yet.
+pub async fn build_similarity_index_if_empty() -> Result<(), LDNError> {
+    let field = SimilarityFeature::ProjectDescription.index_field();
+    let mut build_lexical_index = false;
+    for feature in [
+        SimilarityFeature::ProjectDescription,
+        SimilarityFeature::DataSetSample,
+    ] {
+        build_lexical_index |= similarity_index::get_document_count(feature.index_field())
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?
+            == 0;
+    }
+    let build_embeddings = if SimilarityBackend::from_env()?.uses_semantic() {
+        comparable_application_embeddings::get_embedding_count(field, get_embedding_model()?.name())
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to get application embeddings: {}", e)))?
+            == 0
+    } else {
+        false
+    };
+    let build_linked_client_index = similarity_index::get_document_count(
+        SimilarityFeature::SharedStorageProviders.index_field(),
+    )
+    .await
+    .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?
+        == 0;
+    if build_linked_client_index {
+        let comparable_applications = get_all_comparable_applications().await.map_err(|e| {
+            LDNError::New(format!(
+                "Failed to get comparable applications from database: {}",
+                e
+            ))
+        })?;
+        let clients_addresses = comparable_applications
+            .iter()
+            .map(|app| app.client_address.clone())
+            .collect();
+        let application_files = get_application_files(clients_addresses).await?;
+        for app in comparable_applications {
+            let mut comparable_data = app.application;
+            if let Some(application_file) = application_files.get(&app.client_address) {
+                let backfilled = with_linked_client_data(&comparable_data, application_file);
+                if backfilled != comparable_data {
+                    create_or_update_comparable_application(&app.client_address, &backfilled)
+                        .await
+                        .map_err(|e| {
+                            LDNError::New(format!("Failed to update comparable application: {}", e))
+                        })?;
+                    comparable_data = backfilled;
+                }
+            }
+            add_to_linked_client_index(&app.client_address, &comparable_data).await?;
+        }
+    }
+    if !build_lexical_index && !build_embeddings {
+        return Ok(());
+    }
+    let comparable_applications = get_comparable_applications().await.map_err(|e| {
+        LDNError::New(format!(
+            "Failed to get comparable applications from database: {}",
+            e
+        ))
+    })?;
+    for app in comparable_applications {
+        if build_lexical_index {
+            add_to_lexical_index(&app.client_address, &app.application).await?;
+        }
+        if build_embeddings {
+            add_embeddings(&app.client_address, &app.application).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the application files of the clients by address. Files that cannot be loaded are
+/// logged and left out, so a single broken application does not stop the index from building.
+async fn get_application_files(
+    clients_addresses: Vec<String>,
+) -> Result<HashMap<String, ApplicationFile>, LDNError> {
+    let applications = get_distinct_applications_by_clients_addresses(clients_addresses)
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to get applications from database: {}", e)))?;
+    let mut application_files = HashMap::new();
+    for app_model in applications {
+        let Some(app_str) = app_model.application else {
+            continue;
+        };
+        match ApplicationFile::from_str(&app_str) {
+            Ok(application_file) => {
+                application_files.insert(app_model.id, application_file);
+            }
+            Err(e) => log::warn!(
+                "Failed to parse application file {} from DB: {}",
+                app_model.id,
+                e
+            ),
+        }
+    }
+    Ok(application_files)
+}
+
+/// Fills the SPs, website, social media and addresses of comparable data stored before they
+/// were part of it from the application file.
+fn with_linked_client_data(
+    comparable_data: &ApplicationComparableData,
+    application_file: &ApplicationFile,
+) -> ApplicationComparableData {
+    let from_file = application_file.comparable_data();
+    ApplicationComparableData {
+        storage_providers: from_file.storage_providers,
+        website: from_file.website,
+        social_media: from_file.social_media,
+        addresses: from_file.addresses,
+        ..comparable_data.clone()
+    }
+}
+
+/// Returns up to `top_k` applications with the highest embedding cosine similarity, looked up
+/// through the approximate nearest neighbour index of the embeddings.
+async fn get_top_k_similar_embeddings(
+    feature: SimilarityFeature,
+    client_address: &str,
+    text: &str,
+    top_k: usize,
+) -> Result<Vec<(String, f64)>, LDNError> {
+    let model = get_embedding_model()?;
+    let Some(query) = model.embed(text).await? else {
+        return Ok(vec![]);
+    };
+    comparable_application_embeddings::get_nearest_embeddings(
+        feature.index_field(),
+        model.name(),
+        &query,
+        client_address,
+        top_k as u64,
+    )
+    .await
+    .map_err(|e| LDNError::New(format!("Failed to get application embeddings: {}", e)))
+}
+
+/// Returns up to `top_k` indexed applications with the highest TF-IDF cosine similarity.
+/// Candidates are the applications sharing a term with the query; their weights and norms are
+/// computed from the current corpus, so scores do not depend on the order of indexing.
+async fn get_top_k_similar_texts(
+    feature: SimilarityFeature,
+    client_address: &str,
+    text: &str,
+    top_k: usize,
+) -> Result<Vec<(String, f64)>, LDNError> {
+    let field = feature.index_field();
+    let (term_counts, token_count) = count_terms(text);
+    if token_count == 0 {
+        return Ok(vec![]);
+    }
+
+    let candidates: HashSet<String> =
+        similarity_index::get_postings(field, term_counts.keys().cloned().collect())
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to get index postings: {}", e)))?
+            .into_iter()
+            .map(|posting| posting.client_address)
+            .filter(|candidate| candidate != client_address)
+            .collect();
+    if candidates.is_empty() {
+        return Ok(vec![]);
+    }
+    let candidates: Vec<String> = candidates.into_iter().collect();
+
+    let mut documents: HashMap<String, (i32, HashMap<String, i32>)> =
+        similarity_index::get_documents(field, candidates.clone())
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?
+            .into_iter()
+            .filter(|document| document.token_count > 0)
+            .map(|document| {
+                (
+                    document.client_address,
+                    (document.token_count, HashMap::new()),
+                )
+            })
+            .collect();
+    for posting in similarity_index::get_document_postings(field, candidates)
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to get index postings: {}", e)))?
+    {
+        if let Some((_, terms)) = documents.get_mut(&posting.client_address) {
+            terms.insert(posting.term, posting.term_count);
+        }
+    }
+
+    let terms: HashSet<String> = term_counts
+        .keys()
+        .chain(documents.values().flat_map(|(_, terms)| terms.keys()))
+        .cloned()
         .collect();
+    let (document_count, document_frequencies) =
+        get_corpus_statistics(field, client_address, &term_counts, terms).await?;
+    let mut similarities = tfidf_similarities(
+        &term_counts,
+        token_count,
+        &documents,
+        document_count,
+        &document_frequencies,
+    );
+    similarities.sort_by(|a, b| b.1.total_cmp(&a.1));
+    similarities.truncate(top_k);
+    Ok(similarities)
+}
 
-    let df = tfidf_summarizer::document_frequency(&tokenized_documents);
-    let documents_words: Vec<String> = df.keys().cloned().collect();
-    let idf = tfidf_summarizer::inverse_document_frequency(&df, tokenized_documents.len());
-    let tfidf_result: Vec<HashMap<String, f64>> = tokenized_documents
+/// Cosine similarity of the TF-IDF vectors of the query and of each document, given as its
+/// token count and term counts.
+fn tfidf_similarities(
+    term_counts: &HashMap<String, i32>,
+    token_count: i32,
+    documents: &HashMap<String, (i32, HashMap<String, i32>)>,
+    document_count: u64,
+    document_frequencies: &HashMap<String, i64>,
+) -> Vec<(String, f64)> {
+    let query_weights = tfidf_weights(
+        term_counts,
+        token_count,
+        document_count,
+        document_frequencies,
+    );
+    let query_norm = sparse_norm(query_weights.values());
+    if query_norm == 0.0 {
+        return vec![];
+    }
+    documents
         .iter()
-        .map(|tokens| tfidf_summarizer::tf_idf(tokens.clone(), &idf))
-        .collect();
+        .filter_map(|(client_address, (token_count, terms))| {
+            let weights = tfidf_weights(terms, *token_count, document_count, document_frequencies);
+            let norm = sparse_norm(weights.values());
+            if norm == 0.0 {
+                return None;
+            }
+            let dot_product: f64 = weights
+                .iter()
+                .filter_map(|(term, weight)| Some(query_weights.get(term)? * weight))
+                .sum();
+            Some((client_address.clone(), dot_product / (query_norm * norm)))
+        })
+        .collect()
+}
 
-    let documents_converted_to_array = convert_to_ndarray(&tfidf_result, &documents_words);
-    let mut similar_applications: Vec<String> = Vec::new();
-    let tfidf_threshold = get_env_var_or_default("TFIDF_THRESHOLD")
-        .parse::<f64>()
-        .map_err(|e| LDNError::New(format!("Parse tfidf threshold score to f64 failed: {}", e)))?;
-    for i in 1..documents_converted_to_array.len() {
-        let similarity = cosine_similarity(
-            &documents_converted_to_array[0],
-            &documents_converted_to_array[i],
-        );
-        if similarity > tfidf_threshold {
-            similar_applications.push(documents[i].client_address.clone());
+/// Returns the number of documents and the document frequencies of `terms` in the corpus made
+/// of the queried document and the indexed documents of other clients, so an application that
+/// is already indexed is not counted twice.
+async fn get_corpus_statistics(
+    field: &str,
+    client_address: &str,
+    query_term_counts: &HashMap<String, i32>,
+    terms: HashSet<String>,
+) -> Result<(u64, HashMap<String, i64>), LDNError> {
+    let document_count = similarity_index::get_other_document_count(field, client_address)
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?
+        + 1;
+    let mut document_frequencies = similarity_index::get_document_frequencies(
+        field,
+        terms.into_iter().collect(),
+        client_address,
+    )
+    .await
+    .map_err(|e| LDNError::New(format!("Failed to get document frequencies: {}", e)))?;
+    for term in query_term_counts.keys() {
+        *document_frequencies.entry(term.clone()).or_insert(0) += 1;
+    }
+    Ok((document_count, document_frequencies))
+}
+
+/// Applications sharing SPs, handles or addresses with the application, looked up in the
+/// index by key. These signal that two applications are submitted by the same entity
+/// regardless of how the descriptions are worded.
+async fn get_linked_clients(
+    client_address: &str,
+    comparable_data: &ApplicationComparableData,
+    weights: &LinkedClientWeights,
+) -> Result<HashMap<String, Vec<FeatureScore>>, LDNError> {
+    let mut features_by_address: HashMap<String, Vec<FeatureScore>> = HashMap::new();
+    for (feature, terms) in linked_client_terms(comparable_data) {
+        if terms.is_empty() || !weights.is_enabled(feature) {
+            continue;
+        }
+        let field = feature.index_field();
+        let term_count = terms.len();
+        let postings = similarity_index::get_postings(field, terms.into_iter().collect())
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to get index postings: {}", e)))?;
+        let mut shared_terms: HashMap<String, usize> = HashMap::new();
+        for posting in postings
+            .into_iter()
+            .filter(|posting| posting.client_address != client_address)
+        {
+            *shared_terms.entry(posting.client_address).or_default() += 1;
+        }
+        if shared_terms.is_empty() {
+            continue;
+        }
+        let other_term_counts: HashMap<String, usize> =
+            similarity_index::get_documents(field, shared_terms.keys().cloned().collect())
+                .await
+                .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?
+                .into_iter()
+                .map(|document| {
+                    (
+                        document.client_address,
+                        document.token_count.max(0) as usize,
+                    )
+                })
+                .collect();
+        for (address, shared) in shared_terms {
+            let other_term_count = other_term_counts.get(&address).copied().unwrap_or(shared);
+            if let Some(score) =
+                linked_client_score(feature, weights, term_count, other_term_count, shared)
+            {
+                features_by_address
+                    .entry(address)
+                    .or_default()
+                    .push(FeatureScore { feature, score });
+            }
         }
     }
+    Ok(features_by_address)
+}
 
-    Ok(similar_applications)
+/// Keys under which the linked client signals of an application are indexed.
+fn linked_client_terms(
+    comparable_data: &ApplicationComparableData,
+) -> [(SimilarityFeature, HashSet<String>); 4] {
+    [
+        (
+            SimilarityFeature::SharedStorageProviders,
+            comparable_data.storage_providers.iter().cloned().collect(),
+        ),
+        (
+            SimilarityFeature::SharedWebsite,
+            normalize_website(&comparable_data.website)
+                .into_iter()
+                .collect(),
+        ),
+        (
+            SimilarityFeature::SharedSocialMedia,
+            normalize_social_media(&comparable_data.social_media)
+                .into_iter()
+                .collect(),
+        ),
+        (
+            SimilarityFeature::SharedAddress,
+            comparable_data.addresses.iter().cloned().collect(),
+        ),
+    ]
+}
+
+/// Score of a linked client signal given the number of keys of both applications and the
+/// number of keys they share. Scores are multiplied by the configured weights.
+fn linked_client_score(
+    feature: SimilarityFeature,
+    weights: &LinkedClientWeights,
+    term_count: usize,
+    other_term_count: usize,
+    shared_term_count: usize,
+) -> Option<f64> {
+    if shared_term_count == 0 || !weights.is_enabled(feature) {
+        return None;
+    }
+    match feature {
+        SimilarityFeature::SharedStorageProviders => {
+            let overlap = storage_provider_overlap(term_count, other_term_count, shared_term_count);
+            (overlap > 0.0 && overlap >= weights.sp_overlap_threshold)
+                .then_some(overlap * weights.sp_overlap)
+        }
+        SimilarityFeature::SharedWebsite | SimilarityFeature::SharedSocialMedia => {
+            Some(weights.handle)
+        }
+        SimilarityFeature::SharedAddress => Some(weights.address),
+        _ => None,
+    }
+}
+
+/// Share of the smaller SP list that is also used by the other application.
+fn storage_provider_overlap(sp_count: usize, other_sp_count: usize, shared_sp_count: usize) -> f64 {
+    let smaller = sp_count.min(other_sp_count);
+    if smaller == 0 {
+        return 0.0;
+    }
+    shared_sp_count as f64 / smaller as f64
 }
 
-fn get_similar_texts_levenshtein(documents: &[Document]) -> Result<Vec<String>, LDNError> {
+fn normalize_handle(handle: &str) -> Option<String> {
+    let handle = handle.trim().to_lowercase();
+    let handle = handle
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.")
+        .trim_end_matches('/')
+        .trim_start_matches('@');
+    if matches!(handle, "" | "n/a" | "na" | "none" | "no" | "-")
+        || !handle.chars().any(|c| c.is_alphanumeric())
+    {
+        return None;
+    }
+    Some(handle.to_string())
+}
+
+fn normalize_website(website: &str) -> Option<String> {
+    normalize_handle(website)
+}
+
+/// Social media answers are either a handle or a profile link, so only the last path
+/// segment is compared.
+fn normalize_social_media(social_media: &str) -> Option<String> {
+    let handle = normalize_handle(social_media)?;
+    handle.rsplit('/').next().and_then(normalize_handle)
+}
+
+fn is_indexable(comparable_data: &ApplicationComparableData) -> bool {
+    comparable_data.project_desc.chars().count() > 40
+        || comparable_data.stored_data_desc.chars().count() > 40
+}
+
+fn count_terms(text: &str) -> (HashMap<String, i32>, i32) {
+    let tokens = tfidf_summarizer::tokenize(text);
+    let mut term_counts = HashMap::new();
+    for token in tokens.iter() {
+        *term_counts.entry(token.clone()).or_insert(0) += 1;
+    }
+    (term_counts, tokens.len() as i32)
+}
+
+/// Smoothed so terms present in every document keep a small weight and a corpus of two near
+/// duplicates still compares them.
+fn inverse_document_frequency(document_count: u64, document_frequency: i64) -> f64 {
+    ((1.0 + document_count as f64) / (1.0 + document_frequency.max(1) as f64)).ln() + 1.0
+}
+
+fn tfidf_weights(
+    term_counts: &HashMap<String, i32>,
+    token_count: i32,
+    document_count: u64,
+    document_frequencies: &HashMap<String, i64>,
+) -> HashMap<String, f64> {
+    term_counts
+        .iter()
+        .map(|(term, count)| {
+            let idf = inverse_document_frequency(
+                document_count,
+                document_frequencies.get(term).copied().unwrap_or(1),
+            );
+            (term.clone(), *count as f64 / token_count as f64 * idf)
+        })
+        .collect()
+}
+
+fn sparse_norm<'a>(weights: impl Iterator<Item = &'a f64>) -> f64 {
+    weights.map(|weight| weight * weight).sum::<f64>().sqrt()
+}
+
+/// Returns the applications with the same data owner name.
+async fn get_same_data_owner_names(
+    client_address: &str,
+    data_owner_name: &str,
+) -> Result<Vec<(String, f64)>, LDNError> {
+    if data_owner_name.is_empty() {
+        return Ok(vec![]);
+    }
+    let postings = similarity_index::get_postings(
+        SimilarityFeature::DataOwnerName.index_field(),
+        vec![data_owner_name.to_string()],
+    )
+    .await
+    .map_err(|e| LDNError::New(format!("Failed to get index postings: {}", e)))?;
+    Ok(postings
+        .into_iter()
+        .filter(|posting| posting.client_address != client_address)
+        .map(|posting| (posting.client_address, 1.0))
+        .collect())
+}
+
+/// Returns the applications whose data set sample is within the Levenshtein threshold.
+/// Candidates are preselected from the n-gram postings, so only the samples which can be
+/// within the distance are loaded.
+async fn get_similar_data_set_samples(
+    client_address: &str,
+    data_set_sample: &str,
+) -> Result<Vec<(String, f64)>, LDNError> {
     let levenshtein_threshold = get_env_var_or_default("LEVENSHTEIN_THRESHOLD")
         .parse::<usize>()
         .map_err(|e| {
@@ -230,42 +1040,119 @@ fn get_similar_texts_levenshtein(documents: &[Document]) -> Result<Vec<String>,
                 e
             ))
         })?;
+    let max_distance = levenshtein_threshold.saturating_sub(1);
+    let field = SimilarityFeature::DataSetSample.index_field();
 
-    let similar_texts: Vec<String> = documents
-        .iter()
-        .skip(1)
-        .filter(|doc| levenshtein(&documents[0].text, &doc.text) < levenshtein_threshold)
-        .map(|doc| doc.client_address.clone())
+    let query_ngrams = char_ngrams(data_set_sample);
+    let query_ngram_count = query_ngrams.values().sum::<usize>();
+    let postings = similarity_index::get_postings(field, query_ngrams.keys().cloned().collect())
+        .await
+        .map_err(|e| LDNError::New(format!("Failed to get index postings: {}", e)))?;
+    let mut ngrams_by_address: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for posting in postings {
+        ngrams_by_address
+            .entry(posting.client_address)
+            .or_default()
+            .insert(posting.term, posting.term_count.max(0) as usize);
+    }
+    let shared_ngrams: HashMap<String, usize> = ngrams_by_address
+        .into_iter()
+        .map(|(address, ngrams)| (address, shared_ngram_count(&query_ngrams, &ngrams)))
         .collect();
 
-    Ok(similar_texts)
+    let mut documents =
+        similarity_index::get_documents(field, shared_ngrams.keys().cloned().collect())
+            .await
+            .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?;
+    // Short samples can be within the distance without sharing any n-gram
+    let short_document_ngram_count = (NGRAM_SIZE * max_distance) as i32;
+    if query_ngram_count <= NGRAM_SIZE * max_distance {
+        documents.extend(
+            similarity_index::get_documents_with_max_token_count(field, short_document_ngram_count)
+                .await
+                .map_err(|e| LDNError::New(format!("Failed to get indexed documents: {}", e)))?,
+        );
+    }
+    let candidates: HashSet<String> = documents
+        .into_iter()
+        .filter(|document| document.client_address != client_address)
+        .filter(|document| {
+            may_be_within_distance(
+                query_ngram_count,
+                document.token_count.max(0) as usize,
+                shared_ngrams
+                    .get(&document.client_address)
+                    .copied()
+                    .unwrap_or(0),
+                max_distance,
+            )
+        })
+        .map(|document| document.client_address)
+        .collect();
+    if candidates.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let applications = get_comparable_applications_by_addresses(candidates.into_iter().collect())
+        .await
+        .map_err(|e| {
+            LDNError::New(format!(
+                "Failed to get comparable applications from database: {}",
+                e
+            ))
+        })?;
+    Ok(applications
+        .into_iter()
+        .filter_map(|app| {
+            let text = &app.application.data_set_sample;
+            let distance = levenshtein(data_set_sample, text);
+            (distance < levenshtein_threshold).then(|| {
+                (
+                    app.client_address,
+                    levenshtein_similarity(data_set_sample, text, distance),
+                )
+            })
+        })
+        .collect())
+}
+
+fn char_ngrams(text: &str) -> HashMap<String, usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut ngrams = HashMap::new();
+    for window in chars.windows(NGRAM_SIZE) {
+        *ngrams.entry(window.iter().collect::<String>()).or_insert(0) += 1;
+    }
+    ngrams
 }
 
-fn convert_to_ndarray(
-    tfidf_vectors: &[HashMap<String, f64>],
-    words: &[String],
-) -> Vec<Array1<f64>> {
-    tfidf_vectors
+fn shared_ngram_count(ngrams: &HashMap<String, usize>, other: &HashMap<String, usize>) -> usize {
+    ngrams
         .iter()
-        .map(|doc_vector| {
-            let vec: Vec<f64> = words
-                .iter()
-                .map(|word| *doc_vector.get(word).unwrap_or(&0.0))
-                .collect();
-            Array1::from(vec)
-        })
-        .collect()
+        .map(|(ngram, count)| (*count).min(*other.get(ngram).unwrap_or(&0)))
+        .sum()
 }
 
-fn cosine_similarity(v1: &Array1<f64>, v2: &Array1<f64>) -> f64 {
-    let dot_product = v1.dot(v2);
-    let norm_v1 = v1.dot(v1).sqrt();
-    let norm_v2 = v2.dot(v2).sqrt();
+/// Q-gram lemma: strings within edit distance `max_distance` share at least
+/// `max(|a|, |b|) - n + 1 - n * max_distance` n-grams. Candidates sharing fewer
+/// n-grams are skipped without computing the full Levenshtein distance.
+fn may_be_within_distance(
+    query_ngram_count: usize,
+    ngram_count: usize,
+    shared_ngram_count: usize,
+    max_distance: usize,
+) -> bool {
+    let required =
+        query_ngram_count.max(ngram_count) as isize - (NGRAM_SIZE * max_distance) as isize;
+    required <= 0 || shared_ngram_count as isize >= required
+}
 
-    if norm_v1 == 0.0 || norm_v2 == 0.0 {
-        0.0
+/// Normalizes the edit distance to a score between 0 and 1
+fn levenshtein_similarity(a: &str, b: &str, distance: usize) -> f64 {
+    let max_len = a.chars().count().max(b.chars().count());
+    if max_len == 0 {
+        1.0
     } else {
-        dot_product / (norm_v1 * norm_v2)
+        1.0 - distance as f64 / max_len as f64
     }
 }
 
@@ -289,3 +1176,317 @@ fn format_comment(repos: &SortedRepoSimilarities) -> String {
         .collect::<Vec<String>>()
         .join("\n\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn similarity_match(features: Vec<FeatureScore>) -> SimilarityMatch {
+        SimilarityMatch {
+            client_address: "f1client".to_string(),
+            client_name: "Client".to_string(),
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            issue_number: 1,
+            features,
+        }
+    }
+
+    #[test]
+    fn report_comment_lists_strongest_description_feature() {
+        let report = SimilarityReport {
+            client_address: "f1new".to_string(),
+            created_at: Utc::now(),
+            matches: vec![similarity_match(vec![
+                FeatureScore {
+                    feature: SimilarityFeature::ProjectDescription,
+                    score: 0.5,
+                },
+                FeatureScore {
+                    feature: SimilarityFeature::ProjectAndStoredDataDescription,
+                    score: 0.75,
+                },
+                FeatureScore {
+                    feature: SimilarityFeature::DataOwnerName,
+                    score: 1.0,
+                },
+            ])],
+        };
+        let comment = format_report_comment(&report);
+        assert!(comment.contains("### owner/repo"));
+        assert!(comment.contains("Similar project and stored data description (0.75)"));
+        assert!(!comment.contains("Similar project description"));
+        assert!(comment.contains("The same data owner name"));
+    }
+
+    #[test]
+    fn report_comment_without_matches() {
+        let report = SimilarityReport {
+            client_address: "f1new".to_string(),
+            created_at: Utc::now(),
+            matches: vec![],
+        };
+        assert_eq!(
+            format_report_comment(&report),
+            "## Similarity Report\n\nNo similar applications found for the issue"
+        );
+    }
+
+    fn linked_client_features(
+        comparable_data: &ApplicationComparableData,
+        other: &ApplicationComparableData,
+        weights: &LinkedClientWeights,
+    ) -> Vec<FeatureScore> {
+        linked_client_terms(comparable_data)
+            .into_iter()
+            .zip(linked_client_terms(other))
+            .filter_map(|((feature, terms), (_, other_terms))| {
+                let shared = terms.intersection(&other_terms).count();
+                linked_client_score(feature, weights, terms.len(), other_terms.len(), shared)
+                    .map(|score| FeatureScore { feature, score })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn linked_client_features_are_weighted() {
+        let comparable_data = ApplicationComparableData {
+            storage_providers: vec!["f01000".into(), "f01001".into(), "f01002".into()],
+            website: "https://www.example.org/".into(),
+            social_media: "@example".into(),
+            addresses: vec!["f2contract".into()],
+            ..Default::default()
+        };
+        let other = ApplicationComparableData {
+            storage_providers: vec!["f01001".into(), "f01002".into(), "f09999".into()],
+            website: "example.org".into(),
+            social_media: "https://twitter.com/Example".into(),
+            addresses: vec!["f2contract".into()],
+            ..Default::default()
+        };
+        let weights = LinkedClientWeights {
+            sp_overlap_threshold: 0.5,
+            sp_overlap: 0.5,
+            handle: 1.0,
+            address: 2.0,
+        };
+        let features = linked_client_features(&comparable_data, &other, &weights);
+        assert_eq!(
+            features,
+            vec![
+                FeatureScore {
+                    feature: SimilarityFeature::SharedStorageProviders,
+                    score: 2.0 / 3.0 * 0.5,
+                },
+                FeatureScore {
+                    feature: SimilarityFeature::SharedWebsite,
+                    score: 1.0,
+                },
+                FeatureScore {
+                    feature: SimilarityFeature::SharedSocialMedia,
+                    score: 1.0,
+                },
+                FeatureScore {
+                    feature: SimilarityFeature::SharedAddress,
+                    score: 2.0,
+                },
+            ]
+        );
+
+        let disabled = LinkedClientWeights {
+            sp_overlap_threshold: 0.9,
+            sp_overlap: 1.0,
+            handle: 0.0,
+            address: 0.0,
+        };
+        assert!(linked_client_features(&comparable_data, &other, &disabled).is_empty());
+    }
+
+    #[test]
+    fn placeholder_handles_are_not_linked() {
+        let comparable_data = ApplicationComparableData {
+            website: "N/A".into(),
+            social_media: "-".into(),
+            ..Default::default()
+        };
+        let weights = LinkedClientWeights {
+            sp_overlap_threshold: 0.5,
+            sp_overlap: 1.0,
+            handle: 1.0,
+            address: 1.0,
+        };
+        assert!(linked_client_features(&comparable_data, &comparable_data, &weights).is_empty());
+    }
+
+    #[test]
+    fn hybrid_scoring_combines_lexical_and_semantic_scores() {
+        let scoring = SimilarityScoring {
+            backend: SimilarityBackend::Hybrid,
+            tfidf_threshold: 0.4,
+            embedding_threshold: 0.85,
+            semantic_weight: 0.5,
+        };
+        let lexical = vec![("f1both".to_string(), 0.6), ("f1lexical".to_string(), 0.5)];
+        let semantic = vec![
+            ("f1both".to_string(), 0.9),
+            ("f1paraphrase".to_string(), 0.95),
+            ("f1unrelated".to_string(), 0.3),
+        ];
+        let combined = scoring.combine(lexical.clone(), semantic.clone());
+        assert_eq!(
+            combined,
+            vec![
+                ("f1both".to_string(), 0.75),
+                ("f1paraphrase".to_string(), 0.475),
+                ("f1lexical".to_string(), 0.25),
+            ]
+        );
+
+        let lexical_only = SimilarityScoring {
+            backend: SimilarityBackend::Lexical,
+            ..scoring
+        };
+        assert_eq!(
+            lexical_only.combine(lexical, semantic),
+            vec![("f1both".to_string(), 0.6), ("f1lexical".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn ngram_prefilter_keeps_texts_within_distance() {
+        let query = "https://example.com/datasets/sample-one.csv";
+        let query_ngrams = char_ngrams(query);
+        let within_distance = |text: &str, max_distance: usize| {
+            let ngrams = char_ngrams(text);
+            may_be_within_distance(
+                query_ngrams.values().sum(),
+                ngrams.values().sum(),
+                shared_ngram_count(&query_ngrams, &ngrams),
+                max_distance,
+            )
+        };
+        for (text, max_distance) in [
+            ("https://example.com/datasets/sample-two.csv", 3),
+            ("https://example.com/datasets/sample-one.csv", 0),
+            ("http://example.com/dataset/sample-one.csv", 2),
+        ] {
+            assert!(levenshtein(query, text) <= max_distance);
+            assert!(within_distance(text, max_distance));
+        }
+        assert!(!within_distance(
+            "s3://another-bucket/completely/different/path/to/data.parquet",
+            2
+        ));
+        assert!(may_be_within_distance(3, 2, 0, 1));
+    }
+
+    #[test]
+    fn tfidf_weights_favour_rare_terms() {
+        let (term_counts, token_count) = count_terms("open data open research");
+        let document_frequencies = HashMap::from([
+            ("open".to_string(), 4),
+            ("data".to_string(), 1),
+            ("research".to_string(), 2),
+        ]);
+        let weights = tfidf_weights(&term_counts, token_count, 4, &document_frequencies);
+        assert!(weights["open"] > 0.0);
+        assert!(weights["data"] > weights["research"]);
+        assert!(weights["research"] > weights["open"] / 2.0);
+    }
+
+    fn corpus_similarities(query: &str, indexed: &[(&str, &str)]) -> Vec<(String, f64)> {
+        let (term_counts, token_count) = count_terms(query);
+        let documents: HashMap<String, (i32, HashMap<String, i32>)> = indexed
+            .iter()
+            .map(|(client_address, text)| {
+                let (terms, token_count) = count_terms(text);
+                (client_address.to_string(), (token_count, terms))
+            })
+            .collect();
+        let mut document_frequencies: HashMap<String, i64> = HashMap::new();
+        for terms in documents
+            .values()
+            .map(|(_, terms)| terms)
+            .chain([&term_counts])
+        {
+            for term in terms.keys() {
+                *document_frequencies.entry(term.clone()).or_insert(0) += 1;
+            }
+        }
+        tfidf_similarities(
+            &term_counts,
+            token_count,
+            &documents,
+            documents.len() as u64 + 1,
+            &document_frequencies,
+        )
+    }
+
+    #[test]
+    fn first_indexed_application_is_found_by_near_duplicate() {
+        let first = "We store genome sequencing data of rare plant species collected in 2020";
+        let similarities = corpus_similarities(
+            "We store genome sequencing data of rare plant species collected in 2021",
+            &[("f1first", first)],
+        );
+        assert_eq!(similarities.len(), 1);
+        assert!(similarities[0].1 > 0.8);
+
+        let similarities = corpus_similarities(
+            first,
+            &[
+                ("f1first", first),
+                (
+                    "f1other",
+                    "Satellite imagery of oceans and weather archives",
+                ),
+            ],
+        );
+        let score = |client_address: &str| {
+            similarities
+                .iter()
+                .find(|(address, _)| address == client_address)
+                .map(|(_, score)| *score)
+                .unwrap()
+        };
+        assert!((score("f1first") - 1.0).abs() < 1e-9);
+        assert!(score("f1other") < 0.1);
+    }
+
+    #[tokio::test]
+    async fn linked_client_data_is_backfilled_from_application_file() {
+        use crate::core::application::file::{Client, Datacap, Project, Version};
+
+        let application_file = ApplicationFile::new(
+            "1".to_string(),
+            "f2multisig".to_string(),
+            Version::Number(1),
+            "f1client".to_string(),
+            Client {
+                website: "https://example.com".to_string(),
+                social_media: "@client".to_string(),
+                ..Default::default()
+            },
+            Project {
+                providers: "| SP | Location | Org |\n|---|---|---|\n| f01234 | Germany | A |"
+                    .to_string(),
+                ..Default::default()
+            },
+            Datacap::default(),
+        )
+        .await;
+        let stored = ApplicationComparableData {
+            project_desc: "Stored project description".to_string(),
+            ..Default::default()
+        };
+        let backfilled = with_linked_client_data(&stored, &application_file);
+        assert_eq!(backfilled.project_desc, stored.project_desc);
+        assert_eq!(backfilled.storage_providers, vec!["f01234".to_string()]);
+        assert_eq!(backfilled.website, "https://example.com");
+        assert_eq!(backfilled.social_media, "@client");
+        assert_eq!(
+            backfilled.addresses,
+            application_file.comparable_data().addresses
+        );
+    }
+}
@@ -7,6 +7,39 @@ pub struct VerifiedClientResponse {
     pub count: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifiedDealsResponse {
+    #[serde(deserialize_with = "number_or_string_to_u64")]
+    pub count: u64,
+    pub data: Vec<VerifiedDeal>,
+}
+
+/// Verified deal or DDO claim of a client, as indexed by DataCapStats.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedDeal {
+    /// ID of the SP without the `f0` prefix
+    #[serde(deserialize_with = "number_or_string_to_u64")]
+    pub provider_id: u64,
+    pub piece_cid: String,
+    #[serde(deserialize_with = "number_or_string_to_u64")]
+    pub piece_size: u64,
+}
+
+fn number_or_string_to_u64<'de, D>(de: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let helper: Value = Deserialize::deserialize(de)?;
+
+    match &helper {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim_start_matches("f0").parse::<u64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| serde::de::Error::custom(format!("{} is not a valid number", helper)))
+}
+
 fn number_to_string<'de, D>(de: D) -> Result<Option<String>, D::Error>
 where
     D: serde::Deserializer<'de>,
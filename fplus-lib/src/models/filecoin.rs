@@ -1,14 +1,44 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 pub type StateReadStateResponse = JSONRPCResponse<StateReadStateResult>;
+pub type StateReadActorStateResponse = JSONRPCResponse<Option<StateReadActorStateResult>>;
 pub type StateVerifierStatusResponse = JSONRPCResponse<StateVerifierStatusResult>;
 pub type StateVerifiedClientStatusResponse = JSONRPCResponse<StateVerifiedClientStatusResult>;
+pub type StateMinerPowerResponse = JSONRPCResponse<Option<StateMinerPowerResult>>;
+pub type StateLookupIDResponse = JSONRPCResponse<Option<String>>;
+pub type MsigGetPendingResponse = JSONRPCResponse<Option<Vec<MsigTransaction>>>;
+pub type StateNetworkVersionResponse = JSONRPCResponse<Option<u32>>;
+pub type StateActorCodeCIDsResponse = JSONRPCResponse<Option<HashMap<String, Code>>>;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JSONRPCResponse<T> {
     pub jsonrpc: String,
     pub result: T,
     pub id: u64,
+    /// Set instead of `result` when the node failed to handle the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JSONRPCError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JSONRPCError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl std::fmt::Display for JSONRPCError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl JSONRPCError {
+    /// Lotus reports addresses without an actor as an error rather than an empty result.
+    pub fn is_actor_not_found(&self) -> bool {
+        self.message.contains("actor not found")
+    }
 }
 
 pub type StateVerifierStatusResult = String;
@@ -24,6 +54,15 @@ pub struct StateReadStateResult {
     pub state: MultisigState,
 }
 
+/// `StateReadState` result of an actor of any type.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateReadActorStateResult {
+    #[serde(rename = "Code")]
+    pub code: Code,
+    #[serde(rename = "State")]
+    pub state: serde_json::Value,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Code {
     #[serde(rename = "/")]
@@ -47,3 +86,38 @@ pub struct MultisigState {
     #[serde(rename = "PendingTxns")]
     pub pending_txns: Code,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateMinerPowerResult {
+    #[serde(rename = "MinerPower")]
+    pub miner_power: Claim,
+    #[serde(rename = "TotalPower")]
+    pub total_power: Claim,
+    #[serde(rename = "HasMinPower")]
+    pub has_min_power: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claim {
+    #[serde(rename = "RawBytePower")]
+    pub raw_byte_power: String,
+    #[serde(rename = "QualityAdjPower")]
+    pub quality_adj_power: String,
+}
+
+/// Multisig transaction proposed but not yet approved by enough signers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MsigTransaction {
+    #[serde(rename = "ID")]
+    pub id: i64,
+    #[serde(rename = "To")]
+    pub to: String,
+    #[serde(rename = "Value")]
+    pub value: String,
+    #[serde(rename = "Method")]
+    pub method: u64,
+    #[serde(rename = "Params")]
+    pub params: Option<String>,
+    #[serde(rename = "Approved")]
+    pub approved: Vec<String>,
+}
@@ -4,12 +4,19 @@ use markdown::{mdast::Node, to_mdast, ParseOptions};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::get_env_var_or_default,
     core::application::file::{Client, DataType, Datacap, DatacapGroup, Project, Version},
     error::LDNError,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+use self::providers::ParsedProviders;
+use self::templates::{template_for_issue, FieldType, IssueTemplate};
+
+pub mod forms;
+pub mod providers;
+pub mod templates;
+pub mod validation;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParsedApplicationDataFields {
     Version,
     Address,
@@ -48,76 +55,6 @@ pub enum ParsedApplicationDataFields {
     InvalidField,
 }
 
-impl From<String> for ParsedApplicationDataFields {
-    fn from(s: String) -> Self {
-        match s.as_str() {
-	  "On-chain address for first allocation" => ParsedApplicationDataFields::Address,
-	  // Client Info
-	  "Data Owner Name" => ParsedApplicationDataFields::Name,
-	  "Data Owner Country/Region" => ParsedApplicationDataFields::Region,
-	  "Data Owner Industry" => ParsedApplicationDataFields::Industry,
-	  "Dataset Name" => ParsedApplicationDataFields::Name,
-	  "Dataset Country/Region" => ParsedApplicationDataFields::Region,
-	  "Dataset Industry" => ParsedApplicationDataFields::Industry,
-	  "Website" => ParsedApplicationDataFields::Website,
-	  "Social Media Handle" => ParsedApplicationDataFields::SocialMedia,
-	  "Social Media Type" => ParsedApplicationDataFields::SocialMediaType,
-	  "What is your role related to the dataset" => ParsedApplicationDataFields::Role,
-	  // Project Info
-      "Share a brief history of your project and organization" => {
-		ParsedApplicationDataFields::ProjectBriefHistory
-	  }
-	  "Is this project associated with other projects/ecosystem stakeholders?" => {
-		ParsedApplicationDataFields::AssociatedProjects
-	  }
-	  "Describe the data being stored onto Filecoin" => {
-		ParsedApplicationDataFields::DataDesc
-	  },
-	  "Where was the data currently stored in this dataset sourced from"=> {
-		ParsedApplicationDataFields::DataSrc
-	  },
-	  "If you are a data preparer, how will the data be prepared? Please include tooling used and technical details?" => {
-		ParsedApplicationDataFields::DataPrepare
-	  },
-	  "Please share a sample of the data" => {
-		ParsedApplicationDataFields::DataSampleLink
-	  },
-	  "Confirm that this is a public dataset that can be retrieved by anyone on the Network" => {
-		ParsedApplicationDataFields::ConfirmPublicDataset
-	  },
-	  "What is the expected retrieval frequency for this data" => {
-		ParsedApplicationDataFields::RetrivalFreq
-	  },
-	  "For how long do you plan to keep this dataset stored on Filecoin" => {
-		ParsedApplicationDataFields::DataLifeSpan
-	  },
-	  "In which geographies do you plan on making storage deals" => {
-		ParsedApplicationDataFields::DataGeographies
-	  },
-	  "How will you be distributing your data to storage providers" => {
-		ParsedApplicationDataFields::DataDistribution
-	  },
-	  "Please list the provider IDs and location of the storage providers you will be working with." => {
-		ParsedApplicationDataFields::ProviderIDs
-	  },
-      "Can you confirm that you will follow the Fil+ guideline" => {
-		ParsedApplicationDataFields::FilplusGuideline
-	  },
-	  // Datacap info
-	  "Group" => ParsedApplicationDataFields::DatacapGroup,
-	  "Type" => ParsedApplicationDataFields::Type,
-	  "Total amount of DataCap being requested" => ParsedApplicationDataFields::TotalRequestedAmount,
-	  "Expected size of single dataset (one copy)" => ParsedApplicationDataFields::SingleSizeDataset,
-	  "Number of replicas to store" => ParsedApplicationDataFields::Replicas,
-	  "Weekly allocation of DataCap requested" => ParsedApplicationDataFields::WeeklyAllocation,
-	  "Custom multisig" => ParsedApplicationDataFields::CustomMultisig,
-	  "Identifier" => ParsedApplicationDataFields::Identifier,
-	  // Invalid field
-	  _ => ParsedApplicationDataFields::InvalidField,
-	}
-    }
-}
-
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ParsedIssue {
     pub version: Version,
@@ -128,46 +65,27 @@ pub struct ParsedIssue {
 }
 
 impl ParsedIssue {
-    pub fn from_issue_body(body: &str) -> Result<Self, LDNError> {
-        let tree: Node = to_mdast(body, &ParseOptions::default())
-            .map_err(|e| LDNError::Load(format!("Failed to get node: {}", e)))?;
-        let mut data: IssueValidData = IssueValidData::default();
-        let children = tree.children().ok_or(LDNError::Load(
-            "Failed to get children from node.".to_string(),
-        ))?;
-        let child_iter = children.iter();
-
-        for chunk in child_iter.collect::<Vec<_>>().chunks_exact(2) {
-            if let (Some(prop_node), Some(value_node)) = (chunk.first(), chunk.get(1)) {
-                let prop = prop_node.to_string();
-                let value = value_node.to_string();
-
-                match prop.clone().into() {
-                    ParsedApplicationDataFields::InvalidField => {
-                        continue;
-                    }
-                    _ => data.0.push((Prop(prop), Value(value))),
-                }
-            }
-        }
+    /// Parses the issue with its template, see [`template_for_issue`].
+    pub fn from_issue_body(body: &str, repo_templates: &[IssueTemplate]) -> Result<Self, LDNError> {
+        let template = template_for_issue(body, repo_templates)?;
+        let data = IssueValidData::from_issue_body(body, template)?;
+        template.validate(&data)?;
         let client = Client::from(data.clone());
         let project = Project::from(data.clone());
         let datacap = Datacap::try_from(data.clone())?;
         let id = data
             .0
             .into_iter()
-            .find(|(prop, _)| prop.0 == "On-chain address for first allocation")
+            .find(|(field, _)| *field == ParsedApplicationDataFields::Address)
             .ok_or(LDNError::Load(
                 "Failed to get on-chain address for first allocation.".to_string(),
             ))?
             .1
              .0;
 
-        let version = get_env_var_or_default("ISSUE_TEMPLATE_VERSION");
-
         Ok(Self {
             id,
-            version: Version::Text(version),
+            version: Version::Text(template.version.clone()),
             client,
             project,
             datacap,
@@ -175,19 +93,104 @@ impl ParsedIssue {
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct Prop(pub String);
 #[derive(Debug, Clone, Default)]
 pub struct Value(pub String);
 
+/// Answers of an issue keyed by the application field they are stored in.
 #[derive(Debug, Clone, Default)]
-pub struct IssueValidData(pub Vec<(Prop, Value)>);
+pub struct IssueValidData(pub Vec<(ParsedApplicationDataFields, Value)>);
+
+/// Answer GitHub renders for optional issue form fields left empty
+const NO_RESPONSE: &str = "_No response_";
+
+fn raw_answer<'a>(body: &'a str, nodes: &[&Node]) -> &'a str {
+    let start = nodes.first().and_then(|node| node.position());
+    let end = nodes.last().and_then(|node| node.position());
+    match (start, end) {
+        (Some(start), Some(end)) => body
+            .get(start.start.offset..end.end.offset)
+            .unwrap_or_default()
+            .trim(),
+        _ => "",
+    }
+}
+
+/// Checked options of an issue form checkboxes answer as `[X] option` lines.
+fn checked_options(answer: &str) -> String {
+    answer
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*']).trim_start();
+            line.strip_prefix("[X]")
+                .or_else(|| line.strip_prefix("[x]"))
+                .map(|option| format!("[X] {}", option.trim()))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+impl IssueValidData {
+    /// Reads the answers to the questions of `template` from the issue body.
+    pub fn from_issue_body(body: &str, template: &IssueTemplate) -> Result<Self, LDNError> {
+        let tree: Node = to_mdast(body, &ParseOptions::default())
+            .map_err(|e| LDNError::Load(format!("Failed to get node: {}", e)))?;
+        let mut data: IssueValidData = IssueValidData::default();
+        let children = tree.children().ok_or(LDNError::Load(
+            "Failed to get children from node.".to_string(),
+        ))?;
+
+        // Every heading is a question and the nodes until the next heading are its answer.
+        // HTML, e.g. the template version marker, is not part of an answer.
+        let mut sections: Vec<(String, Vec<&Node>)> = Vec::new();
+        for node in children {
+            match node {
+                Node::Heading(_) => sections.push((node.to_string(), Vec::new())),
+                Node::Html(_) => {}
+                _ => {
+                    if let Some((_, answer)) = sections.last_mut() {
+                        answer.push(node);
+                    }
+                }
+            }
+        }
+
+        for (question, nodes) in sections {
+            let Some(template_field) = template.field(&question) else {
+                continue;
+            };
+            let raw = raw_answer(body, &nodes);
+            if raw.is_empty() || raw == NO_RESPONSE {
+                continue;
+            }
+            let value = if template_field.value_type == FieldType::Checkboxes {
+                checked_options(raw)
+            } else {
+                nodes
+                    .iter()
+                    .map(|node| node.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n\n")
+            };
+            if !value.is_empty() {
+                data.0.push((template_field.field, Value(value)));
+            }
+        }
+        Ok(data)
+    }
+
+    pub fn answer(&self, field: ParsedApplicationDataFields) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(answered, _)| *answered == field)
+            .map(|(_, value)| value.0.as_str())
+    }
+}
 
 impl From<IssueValidData> for Project {
     fn from(data: IssueValidData) -> Self {
         let mut project = Project::default();
-        for (prop, value) in data.0 {
-            match prop.0.into() {
+        for (field, value) in data.0 {
+            match field {
                 ParsedApplicationDataFields::ProjectBriefHistory => {
                     project.history = value.0;
                 }
@@ -222,6 +225,7 @@ impl From<IssueValidData> for Project {
                     project.distribution = value.0;
                 }
                 ParsedApplicationDataFields::ProviderIDs => {
+                    project.storage_providers = ParsedProviders::from_answer(&value.0).into();
                     project.providers = value.0;
                 }
                 ParsedApplicationDataFields::FilplusGuideline => {
@@ -237,8 +241,8 @@ impl From<IssueValidData> for Project {
 impl From<IssueValidData> for Client {
     fn from(data: IssueValidData) -> Self {
         let mut client = Client::default();
-        for (prop, value) in data.0 {
-            match prop.0.into() {
+        for (field, value) in data.0 {
+            match field {
                 ParsedApplicationDataFields::Name => {
                     client.name = value.0;
                 }
@@ -271,8 +275,8 @@ impl TryFrom<IssueValidData> for Datacap {
     type Error = LDNError;
     fn try_from(data: IssueValidData) -> Result<Self, Self::Error> {
         let mut datacap = Datacap::default();
-        for (prop, value) in data.0 {
-            match prop.0.into() {
+        for (field, value) in data.0 {
+            match field {
                 ParsedApplicationDataFields::DatacapGroup => {
                     datacap._group = DatacapGroup::from_str(&value.0).map_err(|e| {
                         LDNError::Load(format!("Failed to get DataCap group: {}", e))
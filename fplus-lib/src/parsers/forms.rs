@@ -0,0 +1,334 @@
+use serde::Deserialize;
+
+use super::templates::{FieldType, IssueTemplate, TemplateField};
+use super::ParsedApplicationDataFields;
+use crate::error::LDNError;
+
+/// Issue form bundled with the backend, registered as template version 2.0.
+pub const APPLICATION_FORM: &str = include_str!("application_form.yml");
+
+/// Label of the dropdown with id `template_version` that issue forms use to declare their
+/// version, as forms cannot add the HTML marker of markdown templates to the issue body.
+pub const FORM_VERSION_LABEL: &str = "Issue template version";
+
+/// Directory of the issue forms in allocator repositories
+pub const ISSUE_FORMS_DIR: &str = ".github/ISSUE_TEMPLATE";
+
+/// Element id of the dropdown declaring the version of an issue form
+const FORM_VERSION_ID: &str = "template_version";
+
+/// GitHub issue form, see
+/// https://docs.github.com/en/communities/using-templates-to-encourage-useful-issues-and-pull-requests/syntax-for-issue-forms
+#[derive(Deserialize, Debug, Clone)]
+pub struct IssueForm {
+    pub body: Vec<FormElement>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FormElementType {
+    Markdown,
+    Input,
+    Textarea,
+    Dropdown,
+    Checkboxes,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FormElement {
+    #[serde(rename = "type")]
+    pub element_type: FormElementType,
+    pub id: Option<String>,
+    #[serde(default)]
+    pub attributes: FormAttributes,
+    #[serde(default)]
+    pub validations: FormValidations,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FormAttributes {
+    pub label: Option<String>,
+    /// Strings for dropdowns, `label` mappings for checkboxes
+    #[serde(default)]
+    pub options: Vec<serde_yaml::Value>,
+    /// Index of the preselected dropdown option
+    pub default: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FormValidations {
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Application field and value type of the issue form elements, by element id.
+pub fn form_field(id: &str) -> Option<(ParsedApplicationDataFields, FieldType)> {
+    use FieldType as T;
+    use ParsedApplicationDataFields as F;
+    let field = match id {
+        FORM_VERSION_ID => (F::Version, T::Text),
+        "address" => (F::Address, T::FilecoinAddress),
+        // Client Info
+        "data_owner_name" => (F::Name, T::Text),
+        "data_owner_region" => (F::Region, T::Text),
+        "data_owner_industry" => (F::Industry, T::Text),
+        "website" => (F::Website, T::Text),
+        "social_media" => (F::SocialMedia, T::Text),
+        "social_media_type" => (F::SocialMediaType, T::Text),
+        "role" => (F::Role, T::Text),
+        // Project Info
+        "project_history" => (F::ProjectBriefHistory, T::Text),
+        "associated_projects" => (F::AssociatedProjects, T::Text),
+        "data_description" => (F::DataDesc, T::Text),
+        "data_source" => (F::DataSrc, T::Text),
+        "data_preparation" => (F::DataPrepare, T::Text),
+        "data_sample_link" => (F::DataSampleLink, T::Text),
+        "public_dataset" => (F::ConfirmPublicDataset, T::Text),
+        "retrieval_frequency" => (F::RetrivalFreq, T::Text),
+        "data_lifespan" => (F::DataLifeSpan, T::Text),
+        "data_geographies" => (F::DataGeographies, T::Text),
+        "data_distribution" => (F::DataDistribution, T::Text),
+        "providers" => (F::ProviderIDs, T::Text),
+        "filplus_guideline" => (F::FilplusGuideline, T::Checkboxes),
+        // Datacap info
+        "datacap_group" => (F::DatacapGroup, T::DatacapGroup),
+        "data_type" => (F::Type, T::DataType),
+        "total_requested_amount" => (F::TotalRequestedAmount, T::Size),
+        "single_size_dataset" => (F::SingleSizeDataset, T::Size),
+        "replicas" => (F::Replicas, T::Number),
+        "weekly_allocation" => (F::WeeklyAllocation, T::Size),
+        "custom_multisig" => (F::CustomMultisig, T::Checkboxes),
+        "identifier" => (F::Identifier, T::Text),
+        _ => return None,
+    };
+    Some(field)
+}
+
+impl IssueForm {
+    pub fn parse(form: &str) -> Result<Self, LDNError> {
+        serde_yaml::from_str(form)
+            .map_err(|e| LDNError::Load(format!("Failed to parse issue form: {}", e)))
+    }
+
+    /// Preselected option of the `template_version` dropdown, `None` for forms that are not
+    /// application forms.
+    pub fn version(&self) -> Option<String> {
+        let element = self
+            .body
+            .iter()
+            .find(|element| element.id.as_deref() == Some(FORM_VERSION_ID))?;
+        let option = element
+            .attributes
+            .options
+            .get(element.attributes.default.unwrap_or(0))?;
+        match option {
+            serde_yaml::Value::String(version) => Some(version.clone()),
+            serde_yaml::Value::Number(version) => Some(version.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl IssueTemplate {
+    /// Template of an issue form. GitHub renders every element as a `### <label>` heading
+    /// followed by the answer, so the questions are the labels of the form and the elements
+    /// are mapped to application fields by their `id`. Rewording a label therefore needs the
+    /// form itself, see [`IssueTemplate::from_repo_issue_form`]. Elements with unknown ids
+    /// are ignored.
+    pub fn from_issue_form(version: &str, form: &str) -> Result<Self, LDNError> {
+        Ok(Self::from_form(version, IssueForm::parse(form)?))
+    }
+
+    /// Template of an issue form of an allocator repository, with the version preselected in
+    /// its `template_version` dropdown. `None` if the form is not an application form.
+    pub fn from_repo_issue_form(form: &str) -> Result<Option<Self>, LDNError> {
+        let form = IssueForm::parse(form)?;
+        Ok(form
+            .version()
+            .map(|version| Self::from_form(&version, form)))
+    }
+
+    fn from_form(version: &str, form: IssueForm) -> Self {
+        let fields = form
+            .body
+            .into_iter()
+            .filter(|element| element.element_type != FormElementType::Markdown)
+            .filter_map(|element| {
+                let id = element.id?;
+                let label = element.attributes.label?;
+                let (field, value_type) = form_field(&id)?;
+                let value_type = if element.element_type == FormElementType::Checkboxes {
+                    FieldType::Checkboxes
+                } else {
+                    value_type
+                };
+                Some(TemplateField {
+                    question: label,
+                    id: Some(id),
+                    field,
+                    // Required checkboxes are validated by GitHub per option
+                    required: element.validations.required && value_type != FieldType::Checkboxes,
+                    value_type,
+                })
+            })
+            .collect();
+        Self {
+            version: version.to_string(),
+            fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::Version;
+    use crate::parsers::templates::issue_template;
+    use crate::parsers::ParsedIssue;
+
+    const FORM_ISSUE: &str = "### Issue template version
+
+2.0
+
+### Data owner name
+
+Client
+
+### Data owner country/region
+
+Germany
+
+### Data owner industry
+
+Life Science / Healthcare
+
+### Website
+
+_No response_
+
+### Share a brief history of your project and organization
+
+We started in 2019.
+
+Since then we have grown to 50 people.
+
+### Describe the data being stored onto Filecoin
+
+Genomes
+
+### Storage providers
+
+| SP | Location | Org |
+|---|---|---|
+| f01 | Germany | A |
+
+### Fil+ guideline
+
+- [X] I will engage at least 4 SPs and no single SP ID will receive more than 30% of the DataCap
+
+### Data type
+
+Public, Open Dataset (Research/Non-Profit)
+
+### Total amount of DataCap being requested
+
+5PiB
+
+### Expected size of single dataset (one copy)
+
+1PiB
+
+### Number of replicas to store
+
+5
+
+### Weekly allocation of DataCap requested
+
+500TiB
+
+### On-chain address for first allocation
+
+f1client
+
+### Custom multisig
+
+- [X] Use Custom Multisig
+
+### Identifier
+
+_No response_
+";
+
+    #[test]
+    fn form_fields_are_mapped_by_id() {
+        let template = issue_template("2.0").unwrap();
+        let field = template.field("Storage providers").unwrap();
+        assert_eq!(field.id.as_deref(), Some("providers"));
+        assert_eq!(field.field, ParsedApplicationDataFields::ProviderIDs);
+        assert!(field.required);
+        assert_eq!(
+            template.field("Custom multisig").unwrap().value_type,
+            FieldType::Checkboxes
+        );
+    }
+
+    #[test]
+    fn form_issue_is_parsed() {
+        let parsed = ParsedIssue::from_issue_body(FORM_ISSUE, &[]).unwrap();
+        assert_eq!(parsed.version, Version::Text("2.0".into()));
+        assert_eq!(parsed.id, "f1client");
+        assert_eq!(parsed.client.website, "");
+        assert_eq!(
+            parsed.project.history,
+            "We started in 2019.\n\nSince then we have grown to 50 people."
+        );
+        assert_eq!(parsed.project.storage_providers.len(), 1);
+        assert_eq!(parsed.datacap.custom_multisig, "[X] Use Custom Multisig");
+        assert_eq!(
+            parsed.project.filplus_guideline,
+            "[X] I will engage at least 4 SPs and no single SP ID will receive more than 30% of the DataCap"
+        );
+    }
+
+    #[test]
+    fn repo_form_labels_are_the_questions() {
+        let form = APPLICATION_FORM
+            .replace("label: Data owner name", "label: Name of the data owner")
+            .replace(
+                "label: Number of replicas to store",
+                "label: How many copies will be stored?",
+            )
+            .replace("- \"2.0\"", "- \"2.1\"");
+        let template = IssueTemplate::from_repo_issue_form(&form).unwrap().unwrap();
+        assert_eq!(template.version, "2.1");
+        let body = FORM_ISSUE
+            .replace(
+                "### Issue template version\n\n2.0",
+                "### Issue template version\n\n2.1",
+            )
+            .replace("### Data owner name", "### Name of the data owner")
+            .replace(
+                "### Number of replicas to store",
+                "### How many copies will be stored?",
+            );
+
+        let parsed = ParsedIssue::from_issue_body(&body, &[template]).unwrap();
+        assert_eq!(parsed.version, Version::Text("2.1".into()));
+        assert_eq!(parsed.client.name, "Client");
+        assert_eq!(parsed.datacap.replicas, 5);
+        // Without the form of the repository the version is unknown
+        assert!(ParsedIssue::from_issue_body(&body, &[]).is_err());
+    }
+
+    #[test]
+    fn forms_without_version_are_not_application_forms() {
+        let form = "name: Bug report
+body:
+  - type: textarea
+    id: what_happened
+    attributes:
+      label: What happened?
+";
+        assert!(IssueTemplate::from_repo_issue_form(form).unwrap().is_none());
+    }
+}
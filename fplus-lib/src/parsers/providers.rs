@@ -0,0 +1,343 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::application::file::Provider;
+
+/// SPs read from the free-text providers answer of an issue. The answer is usually a markdown
+/// table, a list or comma separated values with the location and organization after the ID.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedProviders {
+    pub providers: Vec<Provider>,
+    /// IDs listed more than once, only the first entry is kept
+    pub duplicate_ids: Vec<String>,
+    /// Values in place of an SP ID that are not valid `f0`/`t0` IDs
+    pub malformed_ids: Vec<String>,
+}
+
+impl From<ParsedProviders> for Vec<Provider> {
+    fn from(parsed: ParsedProviders) -> Self {
+        parsed.providers
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Id(u64),
+    MalformedId(String),
+    Text(String),
+}
+
+/// Columns of a providers table, read from its header row.
+#[derive(Debug, Default)]
+struct Columns {
+    id: Option<usize>,
+    location: Option<usize>,
+    spo_org: Option<usize>,
+}
+
+impl Columns {
+    fn from_header(cells: &[String]) -> Option<Self> {
+        let mut columns = Columns::default();
+        for (index, cell) in cells.iter().enumerate() {
+            let cell = cell.to_lowercase();
+            if columns.location.is_none()
+                && ["location", "region", "country", "city", "geo"]
+                    .iter()
+                    .any(|keyword| cell.contains(keyword))
+            {
+                columns.location = Some(index);
+            } else if columns.spo_org.is_none()
+                && ["org", "company", "entity", "operator"]
+                    .iter()
+                    .any(|keyword| cell.contains(keyword))
+            {
+                columns.spo_org = Some(index);
+            } else if columns.id.is_none()
+                && ["id", "miner", "sp", "provider"]
+                    .iter()
+                    .any(|keyword| cell.contains(keyword))
+            {
+                columns.id = Some(index);
+            }
+        }
+        columns.id.map(|_| columns)
+    }
+}
+
+/// Classifies a word of the answer as an SP ID, a value meant as an ID that is not valid
+/// (e.g. `f0abc` or a wallet address) or anything else.
+fn classify(word: &str) -> Option<Result<u64, String>> {
+    let trimmed = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    let lower = trimmed.to_lowercase();
+    if let Some(id) = lower
+        .strip_prefix("f0")
+        .or_else(|| lower.strip_prefix("t0"))
+    {
+        return Some(id.parse::<u64>().map_err(|_| trimmed.to_string()));
+    }
+    let mut chars = lower.chars();
+    let is_address = matches!(chars.next(), Some('f' | 't'))
+        && matches!(chars.next(), Some('1'..='4'))
+        && lower.len() > 5
+        && chars.all(|c| c.is_ascii_alphanumeric());
+    is_address.then(|| Err(trimmed.to_string()))
+}
+
+fn is_separator_row(line: &str) -> bool {
+    line.chars()
+        .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let mut cells: Vec<String> = line
+        .split('|')
+        .map(|cell| cell.trim().to_string())
+        .collect();
+    if cells.first().is_some_and(String::is_empty) {
+        cells.remove(0);
+    }
+    if cells.last().is_some_and(String::is_empty) {
+        cells.pop();
+    }
+    cells
+}
+
+fn clean_text(text: &str) -> String {
+    text.trim_matches(|c: char| !c.is_alphanumeric())
+        .trim()
+        .to_string()
+}
+
+/// Splits a line into IDs and the text between them. Cell separators and dashes end a text.
+fn tokens(line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for cell in line.split(['|', ',', ';', '\t']) {
+        let mut text: Vec<&str> = Vec::new();
+        let flush = |text: &mut Vec<&str>, tokens: &mut Vec<Token>| {
+            let joined = clean_text(&text.join(" "));
+            if !joined.is_empty() {
+                tokens.push(Token::Text(joined));
+            }
+            text.clear();
+        };
+        for word in cell.split_whitespace() {
+            match classify(word) {
+                Some(Ok(id)) => {
+                    flush(&mut text, &mut tokens);
+                    tokens.push(Token::Id(id));
+                }
+                Some(Err(malformed)) => {
+                    flush(&mut text, &mut tokens);
+                    tokens.push(Token::MalformedId(malformed));
+                }
+                None if matches!(word, "-" | "–" | "—" | "/") => flush(&mut text, &mut tokens),
+                None => text.push(word),
+            }
+        }
+        flush(&mut text, &mut tokens);
+    }
+    tokens
+}
+
+impl ParsedProviders {
+    pub fn from_answer(answer: &str) -> Self {
+        let mut parsed = ParsedProviders::default();
+        let mut columns: Option<Columns> = None;
+        for line in answer.lines() {
+            let line = line.trim().trim_start_matches(['-', '*', '+']).trim_start();
+            if line.is_empty() || is_separator_row(line) {
+                continue;
+            }
+            let line_tokens = tokens(line);
+            let has_id = line_tokens
+                .iter()
+                .any(|token| !matches!(token, Token::Text(_)));
+
+            if line.contains('|') {
+                let cells = table_cells(line);
+                if !has_id {
+                    columns = Columns::from_header(&cells).or(columns);
+                    continue;
+                }
+                if let Some(columns) = &columns {
+                    parsed.push_table_row(&cells, columns);
+                    continue;
+                }
+            }
+            parsed.push_tokens(line_tokens);
+        }
+        parsed
+    }
+
+    /// The ID cell may hold several IDs or text next to the ID, which is used as the location
+    /// and organization when the row has no such columns.
+    fn push_table_row(&mut self, cells: &[String], columns: &Columns) {
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| cells.get(index))
+                .map(|cell| clean_text(cell))
+                .unwrap_or_default()
+        };
+        let Some(id_cell) = columns.id.and_then(|index| cells.get(index)) else {
+            return;
+        };
+        let id_tokens = tokens(id_cell);
+        if id_tokens
+            .iter()
+            .all(|token| matches!(token, Token::Text(_)))
+        {
+            let id_cell = clean_text(id_cell);
+            if !id_cell.is_empty() {
+                self.malformed_ids.push(id_cell);
+            }
+            return;
+        }
+        let (location, spo_org) = (cell(columns.location), cell(columns.spo_org));
+        let mut id_tokens = id_tokens.into_iter().peekable();
+        while let Some(token) = id_tokens.next() {
+            match token {
+                Token::Id(id) => {
+                    let mut texts = Vec::new();
+                    while let Some(Token::Text(text)) =
+                        id_tokens.next_if(|token| matches!(token, Token::Text(_)))
+                    {
+                        texts.push(text);
+                    }
+                    let mut texts = texts.into_iter();
+                    let mut column_or_text = |column: &String| {
+                        if column.is_empty() {
+                            texts.next().unwrap_or_default()
+                        } else {
+                            column.clone()
+                        }
+                    };
+                    let location = column_or_text(&location);
+                    let spo_org = column_or_text(&spo_org);
+                    self.push(id, location, spo_org);
+                }
+                Token::MalformedId(malformed) => self.malformed_ids.push(malformed),
+                Token::Text(_) => {}
+            }
+        }
+    }
+
+    /// The first text after an ID is its location and the second its organization.
+    fn push_tokens(&mut self, tokens: Vec<Token>) {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Id(id) => {
+                    let mut texts = Vec::new();
+                    while let Some(Token::Text(text)) =
+                        tokens.next_if(|token| matches!(token, Token::Text(_)))
+                    {
+                        texts.push(text);
+                    }
+                    let mut texts = texts.into_iter();
+                    self.push(
+                        id,
+                        texts.next().unwrap_or_default(),
+                        texts.next().unwrap_or_default(),
+                    );
+                }
+                Token::MalformedId(malformed) => self.malformed_ids.push(malformed),
+                Token::Text(_) => {}
+            }
+        }
+    }
+
+    fn push(&mut self, id: u64, location: String, spo_org: String) {
+        let id = format!("f0{}", id);
+        if self.providers.iter().any(|provider| provider.id == id) {
+            if !self.duplicate_ids.contains(&id) {
+                self.duplicate_ids.push(id);
+            }
+            return;
+        }
+        self.providers.push(Provider {
+            id,
+            location,
+            spo_org,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(id: &str, location: &str, spo_org: &str) -> Provider {
+        Provider {
+            id: id.to_string(),
+            location: location.to_string(),
+            spo_org: spo_org.to_string(),
+        }
+    }
+
+    #[test]
+    fn table_is_read_by_header() {
+        let parsed = ParsedProviders::from_answer(
+            "| Location | SP ID | Organization |\n|---|---|---|\n| Germany | f01234 | Org A |\n| Hong Kong, China | t05678 | Org B |\n| USA | f0abc | Org C |\n| Japan | f01234 | Org D |",
+        );
+        assert_eq!(
+            parsed.providers,
+            vec![
+                provider("f01234", "Germany", "Org A"),
+                provider("f05678", "Hong Kong, China", "Org B"),
+            ]
+        );
+        assert_eq!(parsed.malformed_ids, vec!["f0abc".to_string()]);
+        assert_eq!(parsed.duplicate_ids, vec!["f01234".to_string()]);
+    }
+
+    #[test]
+    fn list_entries_are_read() {
+        let parsed = ParsedProviders::from_answer(
+            "- f01234 - Germany - Org A\n* **f05678** (Japan, Org B)\n1. f09999 US",
+        );
+        assert_eq!(
+            parsed.providers,
+            vec![
+                provider("f01234", "Germany", "Org A"),
+                provider("f05678", "Japan", "Org B"),
+                provider("f09999", "US", ""),
+            ]
+        );
+        assert_eq!(parsed.malformed_ids, Vec::<String>::new());
+    }
+
+    #[test]
+    fn csv_ids_and_wallet_addresses() {
+        let parsed = ParsedProviders::from_answer("f01, f02;f03\tf02, f1abcdefgh");
+        let ids: Vec<&str> = parsed.providers.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["f01", "f02", "f03"]);
+        assert_eq!(parsed.duplicate_ids, vec!["f02".to_string()]);
+        assert_eq!(parsed.malformed_ids, vec!["f1abcdefgh".to_string()]);
+    }
+
+    #[test]
+    fn table_id_cell_with_organization() {
+        let parsed = ParsedProviders::from_answer(
+            "| SP ID | Location |\n|---|---|\n| f01234 (Org A) | Germany |\n| f0abc (Org B) | Japan |",
+        );
+        assert_eq!(
+            parsed.providers,
+            vec![provider("f01234", "Germany", "Org A")]
+        );
+        assert_eq!(parsed.malformed_ids, vec!["f0abc".to_string()]);
+    }
+
+    #[test]
+    fn table_id_cell_with_several_ids() {
+        let parsed = ParsedProviders::from_answer(
+            "| Miner ID | Location | Organization |\n|---|---|---|\n| f01234, f05678 | Germany | Org A |",
+        );
+        assert_eq!(
+            parsed.providers,
+            vec![
+                provider("f01234", "Germany", "Org A"),
+                provider("f05678", "Germany", "Org A"),
+            ]
+        );
+        assert_eq!(parsed.malformed_ids, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use super::forms::{APPLICATION_FORM, FORM_VERSION_LABEL};
+use super::validation::{IssueProblem, IssueValidationReport};
+use super::{IssueValidData, ParsedApplicationDataFields};
+use crate::config::get_env_var_or_default;
+use crate::core::application::file::{DataType, DatacapGroup};
+use crate::error::LDNError;
+use crate::helpers::{parse_size_to_bytes, process_amount};
+
+/// Marker placed by the issue template in the issue body, e.g.
+/// `<!-- issue-template-version: 1.3 -->`.
+pub const TEMPLATE_VERSION_MARKER: &str = "issue-template-version:";
+
+static TEMPLATE_REGISTRY: OnceCell<HashMap<String, IssueTemplate>> = OnceCell::new();
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    #[default]
+    Text,
+    Size,
+    Number,
+    FilecoinAddress,
+    DatacapGroup,
+    DataType,
+    /// Checkboxes of an issue form, the answer is the checked options
+    Checkboxes,
+}
+
+impl FieldType {
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        match self {
+            FieldType::Text | FieldType::Checkboxes => Ok(()),
+            FieldType::Size => parse_size_to_bytes(&process_amount(value.to_string()))
+                .map(|_| ())
+                .ok_or(format!("{} is not a valid size", value)),
+            FieldType::Number => value
+                .parse::<u8>()
+                .map(|_| ())
+                .map_err(|_| format!("{} is not a valid number", value)),
+            FieldType::FilecoinAddress => is_filecoin_address(value)
+                .then_some(())
+                .ok_or(format!("{} is not a valid Filecoin address", value)),
+            FieldType::DatacapGroup => DatacapGroup::from_str(value).map(|_| ()),
+            FieldType::DataType => DataType::from_str(value).map(|_| ()),
+        }
+    }
+}
+
+fn is_filecoin_address(value: &str) -> bool {
+    let mut chars = value.chars();
+    matches!(chars.next(), Some('f' | 't'))
+        && matches!(chars.next(), Some('0'..='4'))
+        && value.len() > 2
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// Question of an issue template and the application field its answer is stored in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemplateField {
+    pub question: String,
+    /// Element id if the template is an issue form
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub field: ParsedApplicationDataFields,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub value_type: FieldType,
+}
+
+impl TemplateField {
+    fn new(
+        question: &str,
+        field: ParsedApplicationDataFields,
+        required: bool,
+        value_type: FieldType,
+    ) -> Self {
+        Self {
+            question: question.to_string(),
+            id: None,
+            field,
+            required,
+            value_type,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IssueTemplate {
+    pub version: String,
+    pub fields: Vec<TemplateField>,
+}
+
+impl IssueTemplate {
+    pub fn field(&self, question: &str) -> Option<&TemplateField> {
+        self.fields
+            .iter()
+            .find(|field| field.question == question.trim())
+    }
+
+    /// Unanswered required questions and answers that do not have the declared type.
+    /// Questions mapped to the same field, e.g. "Data Owner Name" and "Dataset Name", are
+    /// alternatives of each other.
+    pub fn problems(&self, data: &IssueValidData) -> Vec<IssueProblem> {
+        let mut problems = Vec::new();
+        let mut checked = Vec::new();
+        for template_field in &self.fields {
+            if checked.contains(&template_field.field) {
+                continue;
+            }
+            checked.push(template_field.field);
+            let answers: Vec<&str> = data
+                .0
+                .iter()
+                .filter(|(field, _)| *field == template_field.field)
+                .map(|(_, value)| value.0.as_str())
+                .collect();
+            if answers.is_empty() && template_field.required {
+                problems.push(IssueProblem::new(
+                    Some(&template_field.question),
+                    "The question is not answered",
+                ));
+            }
+            for answer in answers {
+                if let Err(e) = template_field.value_type.validate(answer) {
+                    problems.push(IssueProblem::new(Some(&template_field.question), e));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Fails with every problem of [`IssueTemplate::problems`].
+    pub fn validate(&self, data: &IssueValidData) -> Result<(), LDNError> {
+        let problems = self.problems(data);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LDNError::Load(format!(
+                "Issue does not match template {}: {}",
+                self.version,
+                IssueValidationReport::new(problems).format_error()
+            )))
+        }
+    }
+}
+
+fn template_v1_3() -> IssueTemplate {
+    use FieldType::*;
+    use ParsedApplicationDataFields as F;
+    let fields = vec![
+        TemplateField::new(
+            "On-chain address for first allocation",
+            F::Address,
+            true,
+            FilecoinAddress,
+        ),
+        // Client Info
+        TemplateField::new("Data Owner Name", F::Name, true, Text),
+        TemplateField::new("Data Owner Country/Region", F::Region, false, Text),
+        TemplateField::new("Data Owner Industry", F::Industry, false, Text),
+        TemplateField::new("Dataset Name", F::Name, true, Text),
+        TemplateField::new("Dataset Country/Region", F::Region, false, Text),
+        TemplateField::new("Dataset Industry", F::Industry, false, Text),
+        TemplateField::new("Website", F::Website, false, Text),
+        TemplateField::new("Social Media Handle", F::SocialMedia, false, Text),
+        TemplateField::new("Social Media Type", F::SocialMediaType, false, Text),
+        TemplateField::new(
+            "What is your role related to the dataset",
+            F::Role,
+            false,
+            Text,
+        ),
+        // Project Info
+        TemplateField::new(
+            "Share a brief history of your project and organization",
+            F::ProjectBriefHistory,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Is this project associated with other projects/ecosystem stakeholders?",
+            F::AssociatedProjects,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Describe the data being stored onto Filecoin",
+            F::DataDesc,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Where was the data currently stored in this dataset sourced from",
+            F::DataSrc,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "If you are a data preparer, how will the data be prepared? Please include tooling used and technical details?",
+            F::DataPrepare,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Please share a sample of the data",
+            F::DataSampleLink,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Confirm that this is a public dataset that can be retrieved by anyone on the Network",
+            F::ConfirmPublicDataset,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "What is the expected retrieval frequency for this data",
+            F::RetrivalFreq,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "For how long do you plan to keep this dataset stored on Filecoin",
+            F::DataLifeSpan,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "In which geographies do you plan on making storage deals",
+            F::DataGeographies,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "How will you be distributing your data to storage providers",
+            F::DataDistribution,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Please list the provider IDs and location of the storage providers you will be working with.",
+            F::ProviderIDs,
+            false,
+            Text,
+        ),
+        TemplateField::new(
+            "Can you confirm that you will follow the Fil+ guideline",
+            F::FilplusGuideline,
+            false,
+            Text,
+        ),
+        // Datacap info
+        TemplateField::new("Group", F::DatacapGroup, false, DatacapGroup),
+        TemplateField::new("Type", F::Type, false, DataType),
+        TemplateField::new(
+            "Total amount of DataCap being requested",
+            F::TotalRequestedAmount,
+            true,
+            Size,
+        ),
+        TemplateField::new(
+            "Expected size of single dataset (one copy)",
+            F::SingleSizeDataset,
+            true,
+            Size,
+        ),
+        TemplateField::new("Number of replicas to store", F::Replicas, true, Number),
+        TemplateField::new(
+            "Weekly allocation of DataCap requested",
+            F::WeeklyAllocation,
+            true,
+            Size,
+        ),
+        TemplateField::new("Custom multisig", F::CustomMultisig, false, Text),
+        TemplateField::new("Identifier", F::Identifier, false, Text),
+    ];
+    IssueTemplate {
+        version: "1.3".to_string(),
+        fields,
+    }
+}
+
+/// Built-in templates and the custom templates of allocators configured in `ISSUE_TEMPLATES`.
+pub fn template_registry() -> Result<&'static HashMap<String, IssueTemplate>, LDNError> {
+    TEMPLATE_REGISTRY.get_or_try_init(|| {
+        let mut templates = vec![
+            template_v1_3(),
+            IssueTemplate::from_issue_form("2.0", APPLICATION_FORM)?,
+        ];
+        let custom = get_env_var_or_default("ISSUE_TEMPLATES");
+        if !custom.is_empty() {
+            let custom: Vec<IssueTemplate> = serde_json::from_str(&custom)
+                .map_err(|e| LDNError::Load(format!("Failed to parse ISSUE_TEMPLATES: {}", e)))?;
+            templates.extend(custom);
+        }
+        Ok(templates
+            .into_iter()
+            .map(|template| (template.version.clone(), template))
+            .collect())
+    })
+}
+
+pub fn issue_template(version: &str) -> Result<&'static IssueTemplate, LDNError> {
+    template_registry()?
+        .get(version)
+        .ok_or(LDNError::Load(format!(
+            "Unsupported issue template version: {}",
+            version
+        )))
+}
+
+fn headings(body: &str) -> impl Iterator<Item = &str> {
+    body.lines()
+        .filter_map(|line| line.trim().strip_prefix("###").map(str::trim))
+}
+
+/// First line after the `### <question>` heading of an issue form answer.
+fn heading_answer(body: &str, question: &str) -> Option<String> {
+    let mut lines = body.lines().map(str::trim);
+    lines
+        .position(|line| line.strip_prefix("###").map(str::trim) == Some(question))
+        .and_then(|_| lines.find(|line| !line.is_empty()))
+        .map(str::to_string)
+}
+
+/// Template the issue was opened with. The issue forms of the allocator repository come
+/// first, as their labels are the headings of the issue: a form matches if the issue answers
+/// its version question with the form's version, and among several the one with the most
+/// questions in the issue wins. Otherwise the registered template of [`template_version`].
+pub fn template_for_issue<'a>(
+    body: &str,
+    repo_templates: &'a [IssueTemplate],
+) -> Result<&'a IssueTemplate, LDNError> {
+    let questions: Vec<&str> = headings(body).collect();
+    let repo_template = repo_templates
+        .iter()
+        .filter(|template| {
+            template
+                .fields
+                .iter()
+                .filter(|field| field.field == ParsedApplicationDataFields::Version)
+                .any(|field| {
+                    heading_answer(body, &field.question).as_deref()
+                        == Some(template.version.as_str())
+                })
+        })
+        .max_by_key(|template| {
+            template
+                .fields
+                .iter()
+                .filter(|field| questions.contains(&field.question.as_str()))
+                .count()
+        });
+    match repo_template {
+        Some(template) => Ok(template),
+        None => issue_template(&template_version(body)),
+    }
+}
+
+/// Template version declared in the issue body by the marker of markdown templates or the
+/// version dropdown of issue forms. Issues opened before the marker was added fall back to
+/// `ISSUE_TEMPLATE_VERSION`.
+pub fn template_version(body: &str) -> String {
+    if let Some(version) = heading_answer(body, FORM_VERSION_LABEL) {
+        return version;
+    }
+    body.split("<!--")
+        .skip(1)
+        .filter_map(|comment| comment.split("-->").next())
+        .find_map(|comment| {
+            comment
+                .trim()
+                .strip_prefix(TEMPLATE_VERSION_MARKER)
+                .map(|version| version.trim().to_string())
+        })
+        .unwrap_or_else(|| get_env_var_or_default("ISSUE_TEMPLATE_VERSION"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{ParsedIssue, Value};
+
+    #[test]
+    fn version_is_read_from_marker() {
+        assert_eq!(
+            template_version("<!-- issue-template-version: 2.0 -->\n### Website\nexample.com"),
+            "2.0"
+        );
+        assert_eq!(template_version("### Website\nexample.com"), "1.3");
+    }
+
+    #[test]
+    fn required_and_typed_fields_are_validated() {
+        let template = issue_template("1.3").unwrap();
+        let answer = |field, value: &str| (field, Value(value.to_string()));
+        let mut data = IssueValidData(vec![
+            answer(ParsedApplicationDataFields::Address, "f1client"),
+            answer(ParsedApplicationDataFields::Name, "Client"),
+            answer(ParsedApplicationDataFields::TotalRequestedAmount, "5PiB"),
+            answer(ParsedApplicationDataFields::SingleSizeDataset, "1PiB"),
+            answer(ParsedApplicationDataFields::Replicas, "5"),
+            answer(ParsedApplicationDataFields::WeeklyAllocation, "100TiBs"),
+        ]);
+        assert!(template.validate(&data).is_ok());
+
+        data.0[4] = answer(ParsedApplicationDataFields::Replicas, "five");
+        data.0.remove(1);
+        let error = template.validate(&data).unwrap_err().to_string();
+        assert!(error.contains("Number of replicas to store: five is not a valid number"));
+        assert!(error.contains("Data Owner Name: The question is not answered"));
+    }
+
+    #[test]
+    fn issue_is_parsed_with_its_template() {
+        let body = "<!-- issue-template-version: 1.3 -->\n\
+### Data Owner Name\nClient\n\
+### Website\nexample.com\n\
+### Total amount of DataCap being requested\n5PiB\n\
+### Expected size of single dataset (one copy)\n1PiB\n\
+### Number of replicas to store\n5\n\
+### Weekly allocation of DataCap requested\n100TiB\n\
+### On-chain address for first allocation\nf1client\n";
+        let parsed = ParsedIssue::from_issue_body(body, &[]).unwrap();
+        assert_eq!(parsed.id, "f1client");
+        assert_eq!(parsed.client.name, "Client");
+        assert_eq!(parsed.datacap.replicas, 5);
+
+        let unknown = body.replace("1.3", "0.1");
+        assert!(ParsedIssue::from_issue_body(&unknown, &[]).is_err());
+    }
+}
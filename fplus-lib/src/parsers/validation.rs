@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::providers::ParsedProviders;
+use super::templates::{template_for_issue, IssueTemplate};
+use super::{IssueValidData, ParsedApplicationDataFields};
+use crate::core::application::compliance::MIN_PROVIDERS;
+use crate::helpers::{parse_size_to_bytes, process_amount};
+
+/// Relative difference tolerated between the total requested amount and
+/// `single_size_dataset` × `replicas`
+pub const AMOUNT_TOLERANCE: f64 = 0.1;
+
+/// Problem found in an application issue. `question` is the question of the template the
+/// problem is about, if any.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct IssueProblem {
+    pub question: Option<String>,
+    pub message: String,
+}
+
+impl IssueProblem {
+    pub fn new(question: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            question: question.map(str::to_string),
+            message: message.into(),
+        }
+    }
+}
+
+/// Marker of the validation comment, which is edited instead of adding a new comment each
+/// time the issue is validated.
+pub const VALIDATION_COMMENT_MARKER: &str = "<!-- application-validation -->";
+
+/// `problems` block the application: the issue cannot be parsed or required questions are
+/// unanswered. `warnings` are inconsistencies the client should look at, e.g. too few SPs or
+/// amounts that do not add up, which do not stop the application.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct IssueValidationReport {
+    pub valid: bool,
+    pub template_version: Option<String>,
+    pub problems: Vec<IssueProblem>,
+    #[serde(default)]
+    pub warnings: Vec<IssueProblem>,
+}
+
+impl IssueValidationReport {
+    pub fn new(problems: Vec<IssueProblem>) -> Self {
+        Self {
+            valid: problems.is_empty(),
+            template_version: None,
+            problems,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Neither problems nor warnings
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty() && self.warnings.is_empty()
+    }
+
+    fn checklist(problems: &[IssueProblem]) -> String {
+        problems
+            .iter()
+            .map(|problem| format!("- [ ] {}", Self::describe(problem)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn describe(problem: &IssueProblem) -> String {
+        match &problem.question {
+            Some(question) => format!("{}: {}", question, problem.message),
+            None => problem.message.clone(),
+        }
+    }
+
+    pub fn format_error(&self) -> String {
+        self.problems
+            .iter()
+            .map(Self::describe)
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    /// Checklist posted on the issue so the client can fix every problem at once. The
+    /// comment starts with [`VALIDATION_COMMENT_MARKER`] so later runs can edit it.
+    pub fn format_comment(&self) -> String {
+        let mut sections = vec![format!(
+            "{}\n## Application Validation",
+            VALIDATION_COMMENT_MARKER
+        )];
+        if self.is_clean() {
+            sections.push("All problems of the application are fixed.".to_string());
+        }
+        if !self.problems.is_empty() {
+            sections.push(format!(
+                "The application could not be processed. Please edit the issue and fix the following:\n\n{}",
+                Self::checklist(&self.problems)
+            ));
+        }
+        if !self.warnings.is_empty() {
+            sections.push(format!(
+                "Please check the following, it does not block the application:\n\n{}",
+                Self::checklist(&self.warnings)
+            ));
+        }
+        sections.join("\n\n")
+    }
+}
+
+/// Minimum number of SPs from the `required_sps` of an allocator, e.g. `5+` or `4-6`.
+pub fn min_required_sps(required_sps: Option<&str>) -> usize {
+    required_sps
+        .and_then(|required| {
+            required
+                .trim()
+                .split(|c: char| !c.is_ascii_digit())
+                .next()
+                .and_then(|min| min.parse::<usize>().ok())
+        })
+        .unwrap_or(MIN_PROVIDERS)
+}
+
+/// Collects every problem of an application issue: unanswered or mistyped questions of its
+/// template and malformed SP IDs. Too few or duplicated SPs and requested amounts that do not
+/// add up are warnings. `repo_templates` are the
+/// issue forms of the allocator repository, see [`template_for_issue`].
+pub fn validate_issue_body(
+    body: &str,
+    repo_templates: &[IssueTemplate],
+    min_providers: usize,
+) -> IssueValidationReport {
+    let template = match template_for_issue(body, repo_templates) {
+        Ok(template) => template,
+        Err(e) => return IssueValidationReport::new(vec![IssueProblem::new(None, e.to_string())]),
+    };
+    let data = match IssueValidData::from_issue_body(body, template) {
+        Ok(data) => data,
+        Err(e) => return IssueValidationReport::new(vec![IssueProblem::new(None, e.to_string())]),
+    };
+    let mut problems = template.problems(&data);
+    let mut warnings = Vec::new();
+    let question = |field: ParsedApplicationDataFields| {
+        template
+            .fields
+            .iter()
+            .find(|template_field| template_field.field == field)
+            .map(|template_field| template_field.question.as_str())
+    };
+
+    if let Some(providers) = data.answer(ParsedApplicationDataFields::ProviderIDs) {
+        let providers_question = question(ParsedApplicationDataFields::ProviderIDs);
+        let parsed = ParsedProviders::from_answer(providers);
+        if parsed.providers.len() < min_providers {
+            warnings.push(IssueProblem::new(
+                providers_question,
+                format!(
+                    "{} SP IDs listed, at least {} are required",
+                    parsed.providers.len(),
+                    min_providers
+                ),
+            ));
+        }
+        for id in parsed.duplicate_ids {
+            warnings.push(IssueProblem::new(
+                providers_question,
+                format!("{} is listed more than once", id),
+            ));
+        }
+        for id in parsed.malformed_ids {
+            problems.push(IssueProblem::new(
+                providers_question,
+                format!("{} is not a valid SP ID", id),
+            ));
+        }
+    }
+    warnings.extend(amount_warnings(&data, &question));
+
+    IssueValidationReport {
+        template_version: Some(template.version.clone()),
+        warnings,
+        ..IssueValidationReport::new(problems)
+    }
+}
+
+fn amount_warnings<'a>(
+    data: &IssueValidData,
+    question: &dyn Fn(ParsedApplicationDataFields) -> Option<&'a str>,
+) -> Vec<IssueProblem> {
+    let bytes = |field| {
+        data.answer(field)
+            .and_then(|answer| parse_size_to_bytes(&process_amount(answer.to_string())))
+    };
+    let replicas = data
+        .answer(ParsedApplicationDataFields::Replicas)
+        .and_then(|replicas| replicas.parse::<u8>().ok());
+    let total = bytes(ParsedApplicationDataFields::TotalRequestedAmount);
+    let single = bytes(ParsedApplicationDataFields::SingleSizeDataset);
+    let weekly = bytes(ParsedApplicationDataFields::WeeklyAllocation);
+
+    let mut warnings = Vec::new();
+    if let (Some(total), Some(single), Some(replicas)) = (total, single, replicas) {
+        let expected = single as f64 * replicas as f64;
+        if (total as f64 - expected).abs() > expected * AMOUNT_TOLERANCE {
+            warnings.push(IssueProblem::new(
+                question(ParsedApplicationDataFields::TotalRequestedAmount),
+                format!(
+                    "The total requested amount ({} bytes) does not match the single dataset size times {} replicas ({} bytes)",
+                    total, replicas, expected as u64
+                ),
+            ));
+        }
+    }
+    if let (Some(total), Some(weekly)) = (total, weekly) {
+        if weekly > total {
+            warnings.push(IssueProblem::new(
+                question(ParsedApplicationDataFields::WeeklyAllocation),
+                "The weekly allocation is bigger than the total requested amount",
+            ));
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(replicas: &str, weekly: &str, providers: &str) -> String {
+        format!(
+            "### Data Owner Name\nClient\n\
+### Please list the provider IDs and location of the storage providers you will be working with.\n{}\n\
+### Total amount of DataCap being requested\n5PiB\n\
+### Expected size of single dataset (one copy)\n1PiB\n\
+### Number of replicas to store\n{}\n\
+### Weekly allocation of DataCap requested\n{}\n\
+### On-chain address for first allocation\nf1client\n",
+            providers, replicas, weekly
+        )
+    }
+
+    #[test]
+    fn consistent_issue_is_valid() {
+        let report = validate_issue_body(&issue("5", "1PiB", "f01, f02, f03, f04"), &[], 4);
+        assert_eq!(report.problems, vec![]);
+        assert_eq!(report.template_version.as_deref(), Some("1.3"));
+    }
+
+    #[test]
+    fn only_unparsable_or_missing_answers_block() {
+        let body = issue("3", "6PiB", "f01, f02, f01")
+            .replace("f1client", "0xclient")
+            .replace("### Data Owner Name\nClient\n", "");
+        let report = validate_issue_body(&body, &[], 4);
+        let questions = |problems: &[IssueProblem]| -> Vec<String> {
+            problems
+                .iter()
+                .filter_map(|problem| problem.question.clone())
+                .collect()
+        };
+        assert!(!report.valid);
+        assert_eq!(
+            questions(&report.problems),
+            vec!["On-chain address for first allocation", "Data Owner Name"]
+        );
+        assert_eq!(
+            questions(&report.warnings),
+            vec![
+                "Please list the provider IDs and location of the storage providers you will be working with.",
+                "Please list the provider IDs and location of the storage providers you will be working with.",
+                "Total amount of DataCap being requested",
+                "Weekly allocation of DataCap requested",
+            ]
+        );
+        let comment = report.format_comment();
+        assert!(comment.starts_with(VALIDATION_COMMENT_MARKER));
+        assert!(comment.contains("- [ ] Data Owner Name"));
+        assert!(comment.contains("- [ ] Weekly allocation of DataCap requested"));
+    }
+
+    #[test]
+    fn heuristics_do_not_block() {
+        let report = validate_issue_body(&issue("3", "6PiB", "f01, f02"), &[], 4);
+        assert!(report.valid);
+        assert_eq!(report.problems, vec![]);
+        assert_eq!(report.warnings.len(), 3);
+    }
+
+    #[test]
+    fn min_sps_are_read_from_allocator() {
+        assert_eq!(min_required_sps(Some("5+")), 5);
+        assert_eq!(min_required_sps(Some(" 3-6 ")), 3);
+        assert_eq!(min_required_sps(None), MIN_PROVIDERS);
+    }
+}
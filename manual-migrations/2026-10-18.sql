@@ -0,0 +1,8 @@
+CREATE TABLE consumed_signatures
+(
+    message_hash text NOT NULL,
+    signer_address text NOT NULL,
+    expires_at timestamp with time zone NOT NULL,
+    consumed_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (message_hash)
+);
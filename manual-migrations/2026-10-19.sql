@@ -0,0 +1,2 @@
+ALTER TABLE IF EXISTS public.allocators
+    ADD COLUMN identity_verifiers jsonb;
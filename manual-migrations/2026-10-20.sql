@@ -0,0 +1,14 @@
+CREATE TABLE kyc_verifications
+(
+    client_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    evm_address text NOT NULL,
+    provider text NOT NULL,
+    score double precision NOT NULL,
+    verified_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (client_id, owner, repo)
+);
+
+ALTER TABLE IF EXISTS public.allocators
+    ADD COLUMN kyc_validity_days integer;
@@ -0,0 +1,13 @@
+CREATE TABLE similarity_reports
+(
+    id serial NOT NULL,
+    client_address text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    report jsonb NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX similarity_reports_application_idx
+    ON similarity_reports (client_address, owner, repo);
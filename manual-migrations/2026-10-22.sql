@@ -0,0 +1,20 @@
+CREATE TABLE similarity_index_documents
+(
+    field text NOT NULL,
+    client_address text NOT NULL,
+    token_count integer NOT NULL,
+    norm double precision NOT NULL,
+    PRIMARY KEY (field, client_address)
+);
+
+CREATE TABLE similarity_index_postings
+(
+    field text NOT NULL,
+    term text NOT NULL,
+    client_address text NOT NULL,
+    term_count integer NOT NULL,
+    PRIMARY KEY (field, term, client_address)
+);
+
+CREATE INDEX similarity_index_postings_document_idx
+    ON similarity_index_postings (field, client_address);
@@ -0,0 +1,11 @@
+CREATE TABLE comparable_application_embeddings
+(
+    client_address text NOT NULL,
+    field text NOT NULL,
+    model text NOT NULL,
+    embedding jsonb NOT NULL,
+    PRIMARY KEY (client_address, field, model)
+);
+
+CREATE INDEX comparable_application_embeddings_field_model_idx
+    ON comparable_application_embeddings (field, model);
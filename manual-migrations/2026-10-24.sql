@@ -0,0 +1,2 @@
+ALTER TABLE IF EXISTS public.allocators
+    ADD COLUMN removed_from_registry_at timestamp with time zone;
@@ -0,0 +1,5 @@
+ALTER TABLE IF EXISTS public.allocators
+    ADD COLUMN multisig_signers text[],
+    ADD COLUMN pending_multisig_transactions jsonb,
+    ADD COLUMN multisig_synced_at timestamp with time zone,
+    ADD COLUMN multisig_signers_diverged_at timestamp with time zone;
@@ -0,0 +1,14 @@
+CREATE TABLE allocator_allowance_snapshots
+(
+    id serial PRIMARY KEY,
+    allocator_id integer NOT NULL REFERENCES allocators (id) ON DELETE CASCADE,
+    allowance bigint NOT NULL,
+    pending_allocations bigint NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+CREATE INDEX allocator_allowance_snapshots_allocator_id_created_at_idx
+    ON allocator_allowance_snapshots (allocator_id, created_at);
+
+ALTER TABLE IF EXISTS public.allocators
+    ADD COLUMN allowance_shortfall_since timestamp with time zone;
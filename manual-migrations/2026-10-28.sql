@@ -0,0 +1,9 @@
+CREATE TABLE github_sync_cursors
+(
+    owner text NOT NULL,
+    repo text NOT NULL,
+    main_sha text NOT NULL,
+    pull_requests_updated_at timestamp with time zone,
+    synced_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (owner, repo)
+);
@@ -0,0 +1,2 @@
+CREATE INDEX similarity_index_documents_token_count_idx
+    ON similarity_index_documents (field, token_count);
@@ -0,0 +1,19 @@
+CREATE EXTENSION IF NOT EXISTS vector;
+
+-- Embeddings of the previous word-vector model are not comparable with sentence embeddings
+DELETE FROM comparable_application_embeddings;
+
+ALTER TABLE comparable_application_embeddings
+    ALTER COLUMN embedding TYPE vector(384) USING embedding::text::vector(384);
+
+CREATE INDEX comparable_application_embeddings_project_desc_idx
+    ON comparable_application_embeddings USING hnsw (embedding vector_cosine_ops)
+    WHERE field = 'project_desc';
+
+CREATE INDEX comparable_application_embeddings_stored_data_desc_idx
+    ON comparable_application_embeddings USING hnsw (embedding vector_cosine_ops)
+    WHERE field = 'stored_data_desc';
+
+CREATE INDEX comparable_application_embeddings_project_and_stored_data_desc_idx
+    ON comparable_application_embeddings USING hnsw (embedding vector_cosine_ops)
+    WHERE field = 'project_and_stored_data_desc';
@@ -0,0 +1,2 @@
+-- TF-IDF norms depend on the whole corpus and are computed when querying
+ALTER TABLE similarity_index_documents DROP COLUMN norm;